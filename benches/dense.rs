@@ -0,0 +1,71 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ndarray::Array2;
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::neural_network::{
+    activation_function::{Linear, Relu},
+    cost_function::QuadraticCost,
+    layer::{DenseLayer, Layer},
+    optimizer::adam_optimizer::ADAM,
+    Network,
+};
+
+// Run once with `cargo bench --bench dense` and once with a BLAS feature enabled
+// (e.g. `--features openblas-system`) to compare both matrix multiplication paths.
+const BACKEND: &str = if cfg!(feature = "blas") {
+    "blas"
+} else {
+    "ndarray"
+};
+
+const BATCH_SIZE: usize = 128;
+const WIDTHS: [usize; 3] = [32, 128, 256];
+
+fn random_batch(rows: usize, cols: usize) -> Array2<f64> {
+    Array2::random((rows, cols), Uniform::new(0.0, 1.0))
+}
+
+fn dense_forward(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("dense_forward/{}", BACKEND));
+
+    for width in WIDTHS {
+        let mut layer = DenseLayer::new(width, Box::new(Relu));
+        layer.initialize(width, width);
+        let input = random_batch(BATCH_SIZE, width);
+
+        group.bench_with_input(BenchmarkId::from_parameter(width), &input, |b, input| {
+            b.iter(|| layer.forward(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
+fn network_backprop(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("network_backprop/{}", BACKEND));
+
+    for width in WIDTHS {
+        // Same shape as the demo in main.rs, with the hidden width varied
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(DenseLayer::new(2, Box::new(Relu))),
+            Box::new(DenseLayer::new(width, Box::new(Relu))),
+            Box::new(DenseLayer::new(width, Box::new(Relu))),
+            Box::new(DenseLayer::new(width, Box::new(Relu))),
+            Box::new(DenseLayer::new(3, Box::new(Linear))),
+        ];
+        let network =
+            Network::new(layers, Box::new(ADAM::default()), Box::new(QuadraticCost)).unwrap();
+
+        let x = random_batch(BATCH_SIZE, 2);
+        let y = random_batch(BATCH_SIZE, 3);
+
+        group.bench_function(BenchmarkId::from_parameter(width), |b| {
+            b.iter(|| network.backprop(black_box(&x), black_box(&y)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, dense_forward, network_backprop);
+criterion_main!(benches);
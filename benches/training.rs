@@ -0,0 +1,145 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ndarray::Array2;
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::neural_network::{
+    activation_function::{Linear, Relu, Sigmoid},
+    cost_function::{CostFunction, QuadraticCost},
+    layer::{DenseLayer, Layer},
+    optimizer::adam_optimizer::ADAM,
+    workspace::Workspace,
+    Network,
+};
+
+const BATCH_SIZE: usize = 128;
+const WIDTHS: [usize; 2] = [32, 128];
+
+fn layers(width: usize) -> Vec<Box<dyn Layer>> {
+    vec![
+        Box::new(DenseLayer::new(2, Box::new(Relu))),
+        Box::new(DenseLayer::new(width, Box::new(Relu))),
+        Box::new(DenseLayer::new(width, Box::new(Sigmoid))),
+        Box::new(DenseLayer::new(width, Box::new(Relu))),
+        Box::new(DenseLayer::new(3, Box::new(Linear))),
+    ]
+}
+
+// The backward pass as it was before workspaces: every weighted sum,
+// activation, delta and gradient is a new array, and the deltas are cloned
+fn allocating_backprop(
+    layers: &[Box<dyn Layer>],
+    cost: &dyn CostFunction,
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+) -> (Vec<Array2<f64>>, Vec<Array2<f64>>) {
+    let mut nabla_bs = Vec::new();
+    let mut nabla_ws = Vec::new();
+
+    let mut activation = x.clone();
+    let mut activations = vec![activation.clone()];
+    let mut zs = Vec::new();
+    for layer in layers {
+        let z = layer.forward(&activation);
+        zs.push(z.clone());
+        activation = z.mapv(|z| layer.get_activation().f(z));
+        activations.push(activation.clone());
+    }
+
+    let nabla_c = cost.d(&activation, y);
+    let last = &layers[layers.len() - 1];
+    let sig_prime = zs[zs.len() - 1].mapv(|z| last.get_activation().d(z));
+    let mut delta = nabla_c * sig_prime;
+
+    nabla_bs.push(delta.clone());
+    nabla_ws.push(activations[activations.len() - 2].t().dot(&delta));
+
+    for i in 2..layers.len() + 1 {
+        let layer = &layers[layers.len() - i];
+        let sig_prime = zs[zs.len() - i].mapv(|z| layer.get_activation().d(z));
+        let nabla_c = &delta.dot(&layers[layers.len() - i + 1].get_weights().t());
+        delta = nabla_c * sig_prime;
+
+        nabla_bs.push(delta.clone());
+        nabla_ws.push(activations[activations.len() - i - 1].t().dot(&delta));
+    }
+
+    nabla_bs.reverse();
+    nabla_ws.reverse();
+
+    let batch_size = x.nrows() as f64;
+    for (nabla_b, nabla_w) in nabla_bs.iter_mut().zip(nabla_ws.iter_mut()) {
+        *nabla_b = nabla_b
+            .sum_axis(ndarray::Axis(0))
+            .into_shape((1, nabla_b.ncols()))
+            .unwrap();
+        *nabla_b /= batch_size;
+        *nabla_w /= batch_size;
+    }
+
+    (nabla_bs, nabla_ws)
+}
+
+// Compares the backward pass that allocates all intermediate values on every
+// call with one that reuses the buffers of a workspace across calls
+fn backprop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backprop");
+
+    for width in WIDTHS {
+        let network = Network::new(
+            layers(width),
+            Box::new(ADAM::default()),
+            Box::new(QuadraticCost),
+        )
+        .unwrap();
+
+        let x = Array2::random((BATCH_SIZE, 2), Uniform::new(0.0, 1.0));
+        let y = Array2::random((BATCH_SIZE, 3), Uniform::new(0.0, 1.0));
+
+        let cost: Box<dyn CostFunction> = Box::new(QuadraticCost);
+        group.bench_function(BenchmarkId::new("allocating", width), |b| {
+            b.iter(|| {
+                allocating_backprop(
+                    network.layers(),
+                    cost.as_ref(),
+                    black_box(&x),
+                    black_box(&y),
+                )
+            })
+        });
+
+        let mut workspace = Workspace::new();
+        group.bench_function(BenchmarkId::new("workspace", width), |b| {
+            b.iter(|| network.backprop_into(black_box(&x), black_box(&y), &mut workspace))
+        });
+    }
+
+    group.finish();
+}
+
+// A full training step, including the optimizer update
+fn train_minibatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("train_minibatch");
+
+    for width in WIDTHS {
+        let mut network = Network::new(
+            layers(width),
+            Box::new(ADAM::default()),
+            Box::new(QuadraticCost),
+        )
+        .unwrap();
+
+        let batch = (
+            Array2::random((BATCH_SIZE, 2), Uniform::new(0.0, 1.0)),
+            Array2::random((BATCH_SIZE, 3), Uniform::new(0.0, 1.0)),
+        );
+
+        group.bench_function(BenchmarkId::from_parameter(width), |b| {
+            b.iter(|| network.train_minibatch(black_box(&batch)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, backprop, train_minibatch);
+criterion_main!(benches);
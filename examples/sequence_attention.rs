@@ -0,0 +1,49 @@
+// Trains a small transformer encoder to continue a sine wave, the attention
+// counterpart of the `sequence_prediction` example.
+//
+//     cargo run --release --example sequence_attention
+
+use std::path::Path;
+
+use ndarray::s;
+
+use neural_network_rs::{
+    dataset::example_datasets::SINE_SEQUENCE,
+    error::Result,
+    neural_network::{
+        activation_function::Linear, cost_function::QuadraticCost, graph::Graph,
+        optimizer::adam_optimizer::ADAM, Summary,
+    },
+    plotter::graph_plotter::plot_graph,
+};
+
+const STEPS: usize = 16;
+
+fn main() -> Result<()> {
+    let mut builder = Graph::builder();
+    let x = builder.input(STEPS);
+    // one value per step, projected to 8 so it can be split into heads
+    let h = builder.time_distributed(x, STEPS, 8, Linear);
+    let h = builder.positional_encoding(h, STEPS);
+    let h = builder.transformer_encoder(h, STEPS, 2, 16);
+    let y = builder.dense(h, 1, Linear);
+    let mut graph = builder.build(
+        &[y],
+        ADAM::new(0.005, 1e-4, 1e-8, 0.9, 0.999),
+        QuadraticCost,
+    )?;
+
+    let cost_history = graph.train_and_log(&SINE_SEQUENCE, 32, 128, 1000)?;
+
+    let (x, y) = SINE_SEQUENCE.get_full()?;
+    let prediction = graph.predict(&[x.slice(s![..8, ..]).to_owned()])?.remove(0);
+    for (expected, predicted) in y.iter().zip(prediction.iter()) {
+        println!("expected {:.4}, predicted {:.4}", expected, predicted);
+    }
+
+    let name = format!("{}_{}", SINE_SEQUENCE.name, graph.summerize());
+    let path = plot_graph(Path::new("images"), &name, &cost_history)?;
+    println!("wrote {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Trains an LSTM to continue a sine wave and compares its predictions with the
+// expected next samples.
+//
+//     cargo run --release --example sequence_prediction
+
+use std::path::Path;
+
+use ndarray::s;
+
+use neural_network_rs::{
+    dataset::example_datasets::SINE_SEQUENCE,
+    error::Result,
+    neural_network::{
+        activation_function::Linear, cost_function::QuadraticCost, optimizer::adam_optimizer::ADAM,
+        Network, Summary,
+    },
+    plotter::graph_plotter::plot_graph,
+};
+
+fn main() -> Result<()> {
+    let mut network = Network::builder()
+        .sequence_input(16, 1)
+        .lstm(16, false)
+        .dense(1, Linear)
+        .optimizer(ADAM::new(0.01, 1e-4, 1e-7, 0.9, 0.999))
+        .cost(QuadraticCost)
+        .build()?;
+
+    let cost_history = network.train_and_log(&SINE_SEQUENCE, 32, 128, 1000)?;
+
+    let (x, y) = SINE_SEQUENCE.get_full()?;
+    let prediction = network.predict(&x.slice(s![..8, ..]).to_owned())?;
+    for (expected, predicted) in y.iter().zip(prediction.iter()) {
+        println!("expected {:.4}, predicted {:.4}", expected, predicted);
+    }
+
+    let name = format!("{}_{}", SINE_SEQUENCE.name, network.summerize());
+    let path = plot_graph(Path::new("images"), &name, &cost_history)?;
+    println!("wrote {}", path.display());
+
+    Ok(())
+}
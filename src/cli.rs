@@ -0,0 +1,396 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use clap::{builder::RangedU64ValueParser, Args, Parser, Subcommand};
+
+use crate::{
+    config::{ExperimentConfig, SavedModel},
+    dataset::{
+        csv::{read_csv, write_csv},
+        slice::FeatureSlice,
+        Dataset,
+    },
+    neural_network::{Network, Summary},
+    plotter::{
+        animation::{AnimationFormat, TrainingAnimation},
+        architecture::write_architecture,
+        decision_boundary::BoundaryPlot,
+        graph_plotter::{plot_graph, ImageFormat},
+        png_plotter::{Colormap, Normalization, PngPlot},
+    },
+};
+
+#[derive(Parser)]
+#[command(version, about = "Train and use neural networks")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Train a network described by a config file and save the result
+    Train(TrainArgs),
+    /// Print the cost of a trained model on a dataset
+    Eval(EvalArgs),
+    /// Write the predictions of a trained model for the inputs in a CSV file
+    Predict(PredictArgs),
+    /// Plot the predictions of a trained model over the unit square
+    Plot(PlotArgs),
+    /// Print the layers of a trained model and draw its architecture
+    Summary(SummaryArgs),
+}
+
+#[derive(Args)]
+struct DatasetArgs {
+    /// CSV file with the inputs in the first columns, followed by the expected outputs,
+    /// or a PNG image whose pixel coordinates are mapped to their colors.
+    /// Defaults to the example dataset named in the config
+    #[arg(long)]
+    dataset: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct TrainArgs {
+    /// Experiment config (.toml or .json)
+    #[arg(long)]
+    config: PathBuf,
+    #[command(flatten)]
+    dataset: DatasetArgs,
+    /// Where to save the trained model
+    #[arg(long)]
+    model: PathBuf,
+    /// Directory for the prediction plot and the cost history
+    #[arg(long)]
+    images: Option<PathBuf>,
+    /// File name of the plots, defaults to the dataset and a summary of the network
+    #[arg(long, requires = "images")]
+    name: Option<String>,
+    /// Overrides the batch size of the config
+    #[arg(long)]
+    batch_size: Option<usize>,
+    /// Overrides the number of epochs of the config
+    #[arg(long)]
+    epochs: Option<i32>,
+    /// Overrides the number of samples used to log the cost
+    #[arg(long)]
+    verification_samples: Option<usize>,
+    /// Also write an animated GIF of the training, with a frame every this many epochs
+    #[arg(long, requires = "images")]
+    animate: Option<i32>,
+}
+
+#[derive(Args)]
+struct EvalArgs {
+    /// Trained model
+    #[arg(long)]
+    model: PathBuf,
+    #[command(flatten)]
+    dataset: DatasetArgs,
+    /// Number of samples to evaluate the cost on
+    #[arg(long, default_value_t = 1024)]
+    samples: usize,
+}
+
+#[derive(Args)]
+struct PredictArgs {
+    /// Trained model
+    #[arg(long)]
+    model: PathBuf,
+    /// CSV file with one input per row
+    #[arg(long)]
+    input: PathBuf,
+    /// Where to write the predictions, defaults to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct PlotArgs {
+    /// Trained model
+    #[arg(long)]
+    model: PathBuf,
+    /// Directory to write the image to
+    #[arg(long, default_value = "images")]
+    images: PathBuf,
+    /// Path of the image, overrides the directory and the name taken from the model
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Width and height of the image in pixels
+    #[arg(long, default_value_t = 512, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    resolution: usize,
+    /// Plot the decision boundary with the samples of the dataset on top
+    #[arg(long)]
+    boundary: bool,
+    #[command(flatten)]
+    dataset: DatasetArgs,
+    /// Values to draw contour lines at in the decision boundary plot
+    #[arg(long, value_delimiter = ',', default_value = "0.5")]
+    thresholds: Vec<f64>,
+    /// Two inputs to sweep over their range in the dataset, the others stay at their mean
+    #[arg(long, value_delimiter = ',', num_args = 1.., requires = "boundary")]
+    features: Vec<usize>,
+    /// Colormap for the first output: grayscale, viridis, magma or diverging
+    #[arg(long, value_parser = parse_colormap, conflicts_with = "boundary")]
+    colormap: Option<Colormap>,
+    /// How outputs are brought into [0, 1]: clamp, minmax or symmetric
+    #[arg(long, value_parser = parse_normalization, default_value = "clamp", conflicts_with = "boundary")]
+    normalize: Normalization,
+}
+
+fn parse_colormap(name: &str) -> Result<Colormap, String> {
+    Colormap::from_name(name).ok_or_else(|| format!("unknown colormap {}", name))
+}
+
+fn parse_normalization(name: &str) -> Result<Normalization, String> {
+    Normalization::from_name(name).ok_or_else(|| format!("unknown normalization {}", name))
+}
+
+#[derive(Args)]
+struct SummaryArgs {
+    /// Trained model
+    #[arg(long)]
+    model: PathBuf,
+    /// Where to write the architecture as a Graphviz DOT file
+    #[arg(long)]
+    dot: Option<PathBuf>,
+    /// Where to draw the architecture, as SVG if the path ends in .svg and as PNG otherwise
+    #[arg(long)]
+    diagram: Option<PathBuf>,
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Train(args) => train(args),
+        Command::Eval(args) => eval(args),
+        Command::Predict(args) => predict(args),
+        Command::Plot(args) => plot(args),
+        Command::Summary(args) => summary(args),
+    }
+}
+
+fn train(args: TrainArgs) -> Result<(), Box<dyn Error>> {
+    let mut config = ExperimentConfig::from_file(&args.config)?;
+    if let Some(batch_size) = args.batch_size {
+        config.training.batch_size = batch_size;
+    }
+    if let Some(epochs) = args.epochs {
+        config.training.epochs = epochs;
+    }
+    if let Some(verification_samples) = args.verification_samples {
+        config.training.verification_samples = verification_samples;
+    }
+    config.training.validate()?;
+
+    let mut network = config.build_network()?;
+
+    let name = dataset_name(&args.dataset, &config);
+    let dataset = load_dataset(&args.dataset, &config, &name)?;
+
+    let mut animation = args.animate.map(|every| TrainingAnimation::new(every, 64));
+    let cost_history = match animation.as_mut() {
+        Some(animation) => network.train_and_record(
+            &dataset,
+            config.training.batch_size,
+            config.training.verification_samples,
+            config.training.epochs,
+            animation,
+        )?,
+        None => network.train_and_log(
+            &dataset,
+            config.training.batch_size,
+            config.training.verification_samples,
+            config.training.epochs,
+        )?,
+    };
+
+    SavedModel::new(&config, &network).save(&args.model)?;
+
+    if let Some(images) = args.images {
+        let name = args
+            .name
+            .unwrap_or_else(|| format!("{}_{}", dataset.name, network.summerize()));
+        let mut paths = vec![plot_graph(&images, &name, &cost_history)?];
+        // the prediction is drawn over the unit square of the two inputs
+        match network.input_size() {
+            2 => paths.push(plot_prediction(
+                &network,
+                &images.join(&name).with_extension("png"),
+                512,
+                None,
+                Normalization::Clamp,
+            )?),
+            inputs => eprintln!(
+                "Skipping the prediction plot, the network has {} inputs instead of 2",
+                inputs
+            ),
+        }
+        if let Some(animation) = animation {
+            paths.push(animation.plot(&images, &name, AnimationFormat::Gif)?);
+        }
+        for path in paths {
+            println!("Wrote {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn eval(args: EvalArgs) -> Result<(), Box<dyn Error>> {
+    let model = SavedModel::load(&args.model)?;
+    let network = model.build_network()?;
+
+    let name = dataset_name(&args.dataset, &model.config);
+    let dataset = load_dataset(&args.dataset, &model.config, &name)?;
+
+    println!("Cost: {:.8}", network.eval(&dataset, args.samples)?);
+
+    Ok(())
+}
+
+fn predict(args: PredictArgs) -> Result<(), Box<dyn Error>> {
+    let network = SavedModel::load(&args.model)?.build_network()?;
+
+    let input = read_csv(&args.input)?;
+    let prediction = network.predict(&input)?;
+
+    match args.output {
+        Some(path) => write_csv(&mut BufWriter::new(File::create(path)?), &prediction)?,
+        None => write_csv(&mut io::stdout().lock(), &prediction)?,
+    }
+
+    Ok(())
+}
+
+fn plot(args: PlotArgs) -> Result<(), Box<dyn Error>> {
+    let model = SavedModel::load(&args.model)?;
+    let network = model.build_network()?;
+
+    let name = args.model.file_stem().map_or_else(
+        || String::from("model"),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+
+    if args.boundary {
+        let dataset_name = dataset_name(&args.dataset, &model.config);
+        let dataset = load_dataset(&args.dataset, &model.config, &dataset_name)?;
+        let slice = match args.features[..] {
+            [] => None,
+            [x, y] => {
+                let (inputs, _) = match dataset.get_full() {
+                    Err(_) => dataset.get_batch(1024)?,
+                    full => full?,
+                };
+                Some(FeatureSlice::at_mean(&inputs, (x, y)))
+            }
+            _ => {
+                return Err(
+                    format!("--features takes two inputs, got {}", args.features.len()).into(),
+                )
+            }
+        };
+        let options = BoundaryPlot {
+            resolution: args.resolution,
+            thresholds: args.thresholds,
+            slice,
+            ..BoundaryPlot::default()
+        };
+        let path = match args.output {
+            Some(path) => options.save(&path, &network, &dataset)?,
+            None => options.plot(&args.images, &name, &network, &dataset)?,
+        };
+        println!("Wrote {}", path.display());
+        return Ok(());
+    }
+
+    let path = args
+        .output
+        .unwrap_or_else(|| args.images.join(&name).with_extension("png"));
+    let path = plot_prediction(
+        &network,
+        &path,
+        args.resolution,
+        args.colormap,
+        args.normalize,
+    )?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+fn summary(args: SummaryArgs) -> Result<(), Box<dyn Error>> {
+    let network = SavedModel::load(&args.model)?.build_network()?;
+    let summary = network.summary();
+
+    print!("{}", summary);
+
+    if let Some(path) = args.dot {
+        std::fs::write(&path, summary.to_dot())?;
+        println!("Wrote {}", path.display());
+    }
+    if let Some(path) = args.diagram {
+        let format = match path.extension().is_some_and(|extension| extension == "svg") {
+            true => ImageFormat::Svg,
+            false => ImageFormat::Png,
+        };
+        write_architecture(BufWriter::new(File::create(&path)?), &summary, format)?;
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+// Renders the prediction over the unit square, in grayscale for networks with
+// one output, unless a colormap is given, and with the first outputs as color
+// channels for networks with more
+fn plot_prediction(
+    network: &Network,
+    path: &Path,
+    resolution: usize,
+    colormap: Option<Colormap>,
+    normalization: Normalization,
+) -> Result<PathBuf, Box<dyn Error>> {
+    if network.input_size() != 2 {
+        return Err("only networks with two inputs can be plotted".into());
+    }
+
+    let (dim, unit_square_prediction) = network.predict_unit_square(resolution)?;
+    let mut plot = PngPlot::for_outputs(dim, &unit_square_prediction).normalization(normalization);
+    if let Some(colormap) = colormap {
+        plot = plot.colormap(colormap);
+    }
+    Ok(plot.save(path)?)
+}
+
+fn dataset_name(args: &DatasetArgs, config: &ExperimentConfig) -> String {
+    match &args.dataset {
+        Some(path) => path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+        None => config.training.dataset.clone(),
+    }
+}
+
+// Loads the CSV or PNG dataset if one is given, otherwise looks up the example
+// dataset named in the config
+fn load_dataset<'a>(
+    args: &DatasetArgs,
+    config: &ExperimentConfig,
+    name: &'a str,
+) -> Result<Dataset<'a>, Box<dyn Error>> {
+    match &args.dataset {
+        Some(path)
+            if path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("png")) =>
+        {
+            Ok(Dataset::from_png(name, path)?)
+        }
+        Some(path) => Ok(Dataset::from_csv(name, path, config.network.input_width())?),
+        None => Ok(Dataset::new(name, config.dataset()?.dataset_type.clone())),
+    }
+}
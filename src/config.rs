@@ -0,0 +1,511 @@
+use std::{fmt, fs, path::Path};
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dataset::{example_datasets, Dataset},
+    neural_network::{
+        activation_function::{ActivationFunction, Linear, Relu, Sigmoid, Tanh},
+        builder::BuildError,
+        cost_function::{
+            AbsoluteCost, CostFunction, HuberCost, LogCoshCost, QuadraticCost, QuantileCost,
+            SmoothL1Cost,
+        },
+        divergence::DivergencePolicy,
+        layer::recurrent::Cell,
+        optimizer::{
+            adam_optimizer::ADAM, rmsprop_optimizer::RMS_PROP, sgd_optimzer::SGD, Optimizer,
+        },
+        Network,
+    },
+};
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(String),
+    UnsupportedFormat(String),
+    UnknownActivation(String),
+    UnknownCost(String),
+    MissingCostParameter {
+        cost: String,
+        parameter: &'static str,
+    },
+    InvalidCostParameter {
+        cost: String,
+        parameter: &'static str,
+        value: f64,
+    },
+    MissingLayerParameter {
+        layer: usize,
+        parameter: &'static str,
+    },
+    InvalidTrainingParameter {
+        parameter: &'static str,
+        value: i64,
+        expected: &'static str,
+    },
+    UnknownDataset(String),
+    Build(BuildError),
+    Serialize(String),
+    LayerCount {
+        expected: usize,
+        found: usize,
+    },
+    ParameterShape {
+        layer: usize,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "could not parse config: {}", e),
+            ConfigError::UnsupportedFormat(extension) => write!(
+                f,
+                "unsupported config format '{}', expected .toml or .json",
+                extension
+            ),
+            ConfigError::UnknownActivation(name) => write!(
+                f,
+                "unknown activation function '{}', expected one of {:?}",
+                name, ACTIVATIONS
+            ),
+            ConfigError::UnknownCost(name) => write!(
+                f,
+                "unknown cost function '{}', expected one of {:?}",
+                name, COSTS
+            ),
+            ConfigError::MissingCostParameter { cost, parameter } => write!(
+                f,
+                "cost function '{}' requires the parameter '{}'",
+                cost, parameter
+            ),
+            ConfigError::InvalidCostParameter {
+                cost,
+                parameter,
+                value,
+            } => write!(
+                f,
+                "invalid value {} for the parameter '{}' of cost function '{}'",
+                value, parameter, cost
+            ),
+            ConfigError::MissingLayerParameter { layer, parameter } => {
+                write!(f, "layer {} requires the parameter '{}'", layer, parameter)
+            }
+            ConfigError::InvalidTrainingParameter {
+                parameter,
+                value,
+                expected,
+            } => write!(
+                f,
+                "the training parameter '{}' must be {}, found {}",
+                parameter, expected, value
+            ),
+            ConfigError::UnknownDataset(name) => write!(f, "unknown dataset '{}'", name),
+            ConfigError::Build(e) => write!(f, "invalid network: {}", e),
+            ConfigError::Serialize(e) => write!(f, "could not write model: {}", e),
+            ConfigError::LayerCount { expected, found } => write!(
+                f,
+                "model has parameters for {} layers, but the network has {}",
+                found, expected
+            ),
+            ConfigError::ParameterShape {
+                layer,
+                expected,
+                found,
+            } => write!(
+                f,
+                "parameters of layer {} have shape {:?}, expected {:?}",
+                layer, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> ConfigError {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<BuildError> for ConfigError {
+    fn from(e: BuildError) -> ConfigError {
+        ConfigError::Build(e)
+    }
+}
+
+const ACTIVATIONS: [&str; 4] = ["relu", "sigmoid", "linear", "tanh"];
+const COSTS: [&str; 6] = [
+    "quadratic",
+    "absolute",
+    "huber",
+    "smooth_l1",
+    "log_cosh",
+    "quantile",
+];
+
+// Describes a whole experiment: the network, how to optimize it and what to
+// train it on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub network: NetworkConfig,
+    pub optimizer: OptimizerConfig,
+    pub cost: CostConfig,
+    pub training: TrainingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    // The number of inputs, or of features per time step if `steps` is set
+    pub input: usize,
+    // Makes the input a sequence of this many time steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steps: Option<usize>,
+    pub layers: Vec<LayerConfig>,
+}
+
+impl NetworkConfig {
+    // The number of values in one row of input data
+    pub fn input_width(&self) -> usize {
+        self.input * self.steps.unwrap_or(1)
+    }
+}
+
+// A layer, given by its output size and activation. For recurrent layers the
+// size is the number of hidden units and the activation is applied to the
+// candidate state, for embeddings it is the size of the vectors and the
+// activation is ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerConfig {
+    #[serde(rename = "type", default)]
+    pub kind: LayerKind,
+    pub size: usize,
+    #[serde(default = "default_activation")]
+    pub activation: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub return_sequences: bool,
+    // The number of indices an embedding can look up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vocabulary_size: Option<usize>,
+}
+
+fn default_activation() -> String {
+    String::from("linear")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerKind {
+    #[default]
+    Dense,
+    SimpleRnn,
+    Lstm,
+    Gru,
+    Embedding,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OptimizerConfig {
+    Adam {
+        learning_rate: f64,
+        decay: f64,
+        epsilon: f64,
+        beta_1: f64,
+        beta_2: f64,
+    },
+    RmsProp {
+        learning_rate: f64,
+        decay: f64,
+        epsilon: f64,
+        rho: f64,
+    },
+    Sgd {
+        learning_rate: f64,
+        momentum: f64,
+        decay: f64,
+    },
+}
+
+// A cost function by name, with the parameters some of them need
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CostConfig {
+    #[serde(rename = "type")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beta: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tau: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrainingConfig {
+    pub dataset: String,
+    pub batch_size: usize,
+    pub verification_samples: usize,
+    pub epochs: i32,
+    // What to do if the training diverges, aborts by default
+    #[serde(default)]
+    pub divergence: DivergencePolicy,
+}
+
+impl TrainingConfig {
+    // Checks the values serde accepts but training can't use, call it again
+    // after overriding any of them
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.batch_size == 0 {
+            return Err(ConfigError::InvalidTrainingParameter {
+                parameter: "batch_size",
+                value: 0,
+                expected: "positive",
+            });
+        }
+        if self.epochs < 0 {
+            return Err(ConfigError::InvalidTrainingParameter {
+                parameter: "epochs",
+                value: self.epochs as i64,
+                expected: "at least 0",
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ExperimentConfig {
+    // Loads a config, the format is chosen by the file extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ExperimentConfig, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => ExperimentConfig::from_toml(&content),
+            Some("json") => ExperimentConfig::from_json(&content),
+            extension => Err(ConfigError::UnsupportedFormat(
+                extension.unwrap_or_default().to_string(),
+            )),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<ExperimentConfig, ConfigError> {
+        let config: ExperimentConfig =
+            toml::from_str(content).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.training.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(content: &str) -> Result<ExperimentConfig, ConfigError> {
+        let config: ExperimentConfig =
+            serde_json::from_str(content).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.training.validate()?;
+        Ok(config)
+    }
+
+    // Creates a freshly initialized network as described by the config
+    pub fn build_network(&self) -> Result<Network, ConfigError> {
+        let mut builder = match self.network.steps {
+            Some(steps) => Network::builder().sequence_input(steps, self.network.input),
+            None => Network::builder().input(self.network.input),
+        };
+
+        for (i, layer) in self.network.layers.iter().enumerate() {
+            let activation = activation(&layer.activation)?;
+            let (size, return_sequences) = (layer.size, layer.return_sequences);
+
+            builder = match layer.kind {
+                LayerKind::Dense => builder.dense_boxed(size, activation),
+                LayerKind::SimpleRnn => {
+                    builder.recurrent(Cell::SimpleRnn, size, activation, return_sequences)
+                }
+                LayerKind::Lstm => {
+                    builder.recurrent(Cell::Lstm, size, activation, return_sequences)
+                }
+                LayerKind::Gru => builder.recurrent(Cell::Gru, size, activation, return_sequences),
+                LayerKind::Embedding => {
+                    let vocabulary_size =
+                        layer
+                            .vocabulary_size
+                            .ok_or(ConfigError::MissingLayerParameter {
+                                layer: i + 1,
+                                parameter: "vocabulary_size",
+                            })?;
+                    builder.embedding(vocabulary_size, size)
+                }
+            };
+        }
+
+        Ok(builder
+            .optimizer_boxed(self.optimizer.build())
+            .cost_boxed(self.cost.build()?)
+            .divergence_policy(self.training.divergence)
+            .build()?)
+    }
+
+    pub fn dataset(&self) -> Result<&'static Dataset<'static>, ConfigError> {
+        example_datasets::by_name(&self.training.dataset)
+            .ok_or_else(|| ConfigError::UnknownDataset(self.training.dataset.clone()))
+    }
+}
+
+// A trained network: the experiment it was created from together with the
+// learned parameters of every layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedModel {
+    pub config: ExperimentConfig,
+    pub layers: Vec<LayerParameters>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerParameters {
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+}
+
+impl SavedModel {
+    pub fn new(config: &ExperimentConfig, network: &Network) -> SavedModel {
+        SavedModel {
+            config: config.clone(),
+            layers: network
+                .layers()
+                .iter()
+                .map(|layer| LayerParameters {
+                    weights: layer.get_weights().clone(),
+                    biases: layer.get_bias().clone(),
+                })
+                .collect(),
+        }
+    }
+
+    // Models are stored as JSON
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let content =
+            serde_json::to_string(self).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SavedModel, ConfigError> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    // Rebuilds the network described by the config and restores its parameters
+    pub fn build_network(&self) -> Result<Network, ConfigError> {
+        let mut network = self.config.build_network()?;
+
+        if network.layers().len() != self.layers.len() {
+            return Err(ConfigError::LayerCount {
+                expected: network.layers().len(),
+                found: self.layers.len(),
+            });
+        }
+
+        for (i, (layer, parameters)) in network
+            .layers_mut()
+            .iter_mut()
+            .zip(&self.layers)
+            .enumerate()
+        {
+            for (expected, found) in [
+                (layer.get_weights().dim(), parameters.weights.dim()),
+                (layer.get_bias().dim(), parameters.biases.dim()),
+            ] {
+                if expected != found {
+                    return Err(ConfigError::ParameterShape {
+                        layer: i,
+                        expected,
+                        found,
+                    });
+                }
+            }
+
+            layer.set_weights(parameters.weights.clone());
+            layer.set_bias(parameters.biases.clone());
+        }
+
+        Ok(network)
+    }
+}
+
+impl OptimizerConfig {
+    pub fn build(&self) -> Box<dyn Optimizer> {
+        match *self {
+            OptimizerConfig::Adam {
+                learning_rate,
+                decay,
+                epsilon,
+                beta_1,
+                beta_2,
+            } => Box::new(ADAM::new(learning_rate, decay, epsilon, beta_1, beta_2)),
+            OptimizerConfig::RmsProp {
+                learning_rate,
+                decay,
+                epsilon,
+                rho,
+            } => Box::new(RMS_PROP::new(learning_rate, decay, epsilon, rho)),
+            OptimizerConfig::Sgd {
+                learning_rate,
+                momentum,
+                decay,
+            } => Box::new(SGD::new(learning_rate, momentum, decay)),
+        }
+    }
+}
+
+impl CostConfig {
+    pub fn build(&self) -> Result<Box<dyn CostFunction>, ConfigError> {
+        let parameter = |value: Option<f64>, parameter| {
+            value.ok_or_else(|| ConfigError::MissingCostParameter {
+                cost: self.name.clone(),
+                parameter,
+            })
+        };
+        let invalid = |parameter, value| ConfigError::InvalidCostParameter {
+            cost: self.name.clone(),
+            parameter,
+            value,
+        };
+
+        Ok(match self.name.as_str() {
+            "quadratic" => Box::new(QuadraticCost),
+            "absolute" => Box::new(AbsoluteCost),
+            "huber" => {
+                let delta = parameter(self.delta, "delta")?;
+                Box::new(HuberCost::new(delta).map_err(|_| invalid("delta", delta))?)
+            }
+            "smooth_l1" => {
+                let beta = parameter(self.beta, "beta")?;
+                Box::new(SmoothL1Cost::new(beta).map_err(|_| invalid("beta", beta))?)
+            }
+            "log_cosh" => Box::new(LogCoshCost),
+            "quantile" => {
+                let tau = parameter(self.tau, "tau")?;
+                Box::new(QuantileCost::new(tau).map_err(|_| invalid("tau", tau))?)
+            }
+            name => return Err(ConfigError::UnknownCost(name.to_string())),
+        })
+    }
+}
+
+fn activation(name: &str) -> Result<Box<dyn ActivationFunction>, ConfigError> {
+    match name {
+        "relu" => Ok(Box::new(Relu)),
+        "sigmoid" => Ok(Box::new(Sigmoid)),
+        "linear" => Ok(Box::new(Linear)),
+        "tanh" => Ok(Box::new(Tanh)),
+        name => Err(ConfigError::UnknownActivation(name.to_string())),
+    }
+}
@@ -1,12 +1,28 @@
 use ndarray::prelude::*;
 use ndarray::{Array, Array2};
-use ndarray_rand::{rand, RandomExt};
+use ndarray_rand::{
+    rand::{self, Rng},
+    RandomExt,
+};
 
+use crate::{
+    error::{Error, Result},
+    neural_network::workspace::ensure_shape,
+};
+
+pub mod csv;
 pub mod example_datasets;
+pub mod image;
+pub mod projection;
+pub mod sequence;
+pub mod slice;
+pub mod synthetic;
 
+#[derive(Clone)]
 pub enum DatasetType {
     Static(fn() -> (Array2<f64>, Array2<f64>)),
     Dynamic(fn(Array1<f64>) -> Array1<f64>, (usize, usize)),
+    InMemory(Array2<f64>, Array2<f64>),
 }
 
 pub struct Dataset<'a> {
@@ -15,14 +31,18 @@ pub struct Dataset<'a> {
 }
 
 impl Dataset<'_> {
-    pub fn new(name: &str, dataset_type: DatasetType) -> Dataset {
+    pub fn new(name: &str, dataset_type: DatasetType) -> Dataset<'_> {
         Dataset { dataset_type, name }
     }
 
-    pub fn get_full(&self) -> (Array2<f64>, Array2<f64>) {
+    pub fn get_full(&self) -> Result<(Array2<f64>, Array2<f64>)> {
         match &self.dataset_type {
-            DatasetType::Static(f) => f(),
-            _ => panic!("Cannot get full dataset from dynamic dataset"),
+            DatasetType::Static(f) => Ok(f()),
+            DatasetType::InMemory(x, y) => {
+                self.check_rows(x, y)?;
+                Ok((x.clone(), y.clone()))
+            }
+            DatasetType::Dynamic(..) => Err(Error::NoFullDataset(self.name.to_string())),
         }
     }
 
@@ -40,22 +60,13 @@ impl Dataset<'_> {
         x
     }
 
-    pub fn get_batch(&self, batch_size: usize) -> (Array2<f64>, Array2<f64>) {
+    pub fn get_batch(&self, batch_size: usize) -> Result<(Array2<f64>, Array2<f64>)> {
         match &self.dataset_type {
             DatasetType::Static(f) => {
                 let (data, labels) = f();
-
-                let indices = Array1::random(
-                    batch_size,
-                    rand::distributions::Uniform::new(0, data.shape()[0]),
-                )
-                .to_vec();
-
-                let data = data.select(Axis(0), &indices);
-                let labels = labels.select(Axis(0), &indices);
-
-                (data, labels)
+                self.sample_rows(&data, &labels, batch_size)
             }
+            DatasetType::InMemory(data, labels) => self.sample_rows(data, labels, batch_size),
             DatasetType::Dynamic(f, (input_dim, output_dim)) => {
                 let x = Array::random(
                     (batch_size, *input_dim),
@@ -68,8 +79,78 @@ impl Dataset<'_> {
                     y.row_mut(i).assign(&yi);
                 }
 
-                (x, y)
+                Ok((x, y))
             }
         }
     }
+
+    // Like `get_batch`, but writes the batch into `batch`, reusing its memory
+    // when the shapes match. Only in-memory datasets are sampled without
+    // allocating: static datasets are generated again on every call and dynamic
+    // datasets allocate every sample.
+    pub fn get_batch_into(
+        &self,
+        batch_size: usize,
+        batch: &mut (Array2<f64>, Array2<f64>),
+    ) -> Result<()> {
+        let DatasetType::InMemory(data, labels) = &self.dataset_type else {
+            *batch = self.get_batch(batch_size)?;
+            return Ok(());
+        };
+        if data.nrows() == 0 {
+            return Err(Error::EmptyDataset(self.name.to_string()));
+        }
+        self.check_rows(data, labels)?;
+
+        let (x, y) = batch;
+        ensure_shape(x, (batch_size, data.ncols()));
+        ensure_shape(y, (batch_size, labels.ncols()));
+
+        let mut rng = rand::thread_rng();
+        for i in 0..batch_size {
+            let row = rng.gen_range(0..data.nrows());
+            x.row_mut(i).assign(&data.row(row));
+            y.row_mut(i).assign(&labels.row(row));
+        }
+
+        Ok(())
+    }
+
+    // Draws `batch_size` random rows (with replacement) from the data and the labels
+    fn sample_rows(
+        &self,
+        data: &Array2<f64>,
+        labels: &Array2<f64>,
+        batch_size: usize,
+    ) -> Result<(Array2<f64>, Array2<f64>)> {
+        if data.nrows() == 0 {
+            return Err(Error::EmptyDataset(self.name.to_string()));
+        }
+        self.check_rows(data, labels)?;
+
+        let indices = Array1::random(
+            batch_size,
+            rand::distributions::Uniform::new(0, data.shape()[0]),
+        )
+        .to_vec();
+
+        Ok((
+            data.select(Axis(0), &indices),
+            labels.select(Axis(0), &indices),
+        ))
+    }
+
+    // Samples are drawn row by row, so data and labels need the same rows
+    fn check_rows(&self, data: &Array2<f64>, labels: &Array2<f64>) -> Result<()> {
+        if data.nrows() != labels.nrows() {
+            return Err(Error::InvalidData(format!(
+                "dataset '{}' has {} inputs but {} labels",
+                self.name,
+                data.nrows(),
+                labels.nrows()
+            )));
+        }
+
+        Ok(())
+    }
 }
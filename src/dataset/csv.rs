@@ -0,0 +1,86 @@
+use std::{fs, io::Write, path::Path};
+
+use ndarray::{s, Array2};
+
+use super::{Dataset, DatasetType};
+use crate::error::{Error, Result};
+
+impl<'a> Dataset<'a> {
+    // Loads a dataset from a CSV file, the first `input_dim` columns are the
+    // inputs and the remaining columns the expected outputs
+    pub fn from_csv<P: AsRef<Path>>(
+        name: &'a str,
+        path: P,
+        input_dim: usize,
+    ) -> Result<Dataset<'a>> {
+        let data = read_csv(path)?;
+
+        if input_dim == 0 || input_dim >= data.ncols() {
+            return Err(invalid_data(format!(
+                "expected more than {} columns to split into inputs and outputs, found {}",
+                input_dim,
+                data.ncols()
+            )));
+        }
+
+        let x = data.slice(s![.., ..input_dim]).to_owned();
+        let y = data.slice(s![.., input_dim..]).to_owned();
+
+        Ok(Dataset::new(name, DatasetType::InMemory(x, y)))
+    }
+}
+
+// Reads a CSV file containing only numbers. A first line that can't be parsed
+// is treated as a header and skipped.
+pub fn read_csv<P: AsRef<Path>>(path: P) -> Result<Array2<f64>> {
+    let content = fs::read_to_string(path)?;
+
+    let mut rows = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let row = line
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<std::result::Result<Vec<_>, _>>();
+
+        match row {
+            Ok(row) => rows.push(row),
+            Err(_) if i == 0 => continue,
+            Err(e) => return Err(invalid_data(format!("line {}: {}", i + 1, e))),
+        }
+    }
+
+    let cols = rows.first().map_or(0, |row| row.len());
+    if let Some(i) = rows.iter().position(|row| row.len() != cols) {
+        return Err(invalid_data(format!(
+            "row {} has {} columns, expected {}",
+            i + 1,
+            rows[i].len(),
+            cols
+        )));
+    }
+
+    Array2::from_shape_vec((rows.len(), cols), rows.concat())
+        .map_err(|e| invalid_data(e.to_string()))
+}
+
+// Writes one line per row, values separated by commas
+pub fn write_csv<W: Write>(writer: &mut W, data: &Array2<f64>) -> Result<()> {
+    for row in data.rows() {
+        let line = row
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(writer, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::InvalidData(message)
+}
@@ -1,6 +1,6 @@
-use ndarray::array;
+use ndarray::{array, Array, Axis};
 
-use super::{Dataset, DatasetType};
+use super::{sequence::sliding_windows, synthetic::Synthetic, Dataset, DatasetType};
 
 // The XOR dataset: [0, 0] -> 0, [0, 1] -> 1, [1, 0] -> 1, [1, 1] -> 0
 pub static XOR: Dataset = Dataset {
@@ -59,3 +59,82 @@ pub static RGB_DONUT: Dataset = Dataset {
         (2, 3),
     ),
 };
+
+// The SINE_SEQUENCE dataset: windows of 16 samples of a sine wave scaled to
+// [0, 1] -> the next sample, for recurrent networks
+pub static SINE_SEQUENCE: Dataset = Dataset {
+    name: "SINE_SEQUENCE",
+    dataset_type: DatasetType::Static(|| {
+        let series = Array::range(0.0, 512.0, 1.0)
+            .mapv(|t: f64| 0.5 + 0.4 * (0.25 * t).sin())
+            .insert_axis(Axis(1));
+        sliding_windows(&series, 16).unwrap()
+    }),
+};
+
+// The synthetic benchmarks below are drawn by `Synthetic::default()`: 1024
+// samples with a noise of 0.05 and a fixed seed. Other sample counts, noise
+// levels and class counts are generated with `Synthetic` directly.
+
+// The SPIRALS dataset: two interleaved spirals -> 0 or 1
+pub static SPIRALS: Dataset = Dataset {
+    name: "SPIRALS",
+    dataset_type: DatasetType::Static(|| Synthetic::default().spirals(2).unwrap()),
+};
+
+// The MOONS dataset: two interleaving half circles -> 0 or 1
+pub static MOONS: Dataset = Dataset {
+    name: "MOONS",
+    dataset_type: DatasetType::Static(|| Synthetic::default().moons().unwrap()),
+};
+
+// The RINGS dataset: two concentric rings -> 0 for the inner, 1 for the outer
+pub static RINGS: Dataset = Dataset {
+    name: "RINGS",
+    dataset_type: DatasetType::Static(|| Synthetic::default().rings(2).unwrap()),
+};
+
+// The BLOBS dataset: three Gaussian blobs -> their class, one-hot
+pub static BLOBS: Dataset = Dataset {
+    name: "BLOBS",
+    dataset_type: DatasetType::Static(|| Synthetic::default().blobs(3).unwrap()),
+};
+
+// The CHECKERBOARD dataset: a 4x4 checkerboard -> 0 or 1
+pub static CHECKERBOARD: Dataset = Dataset {
+    name: "CHECKERBOARD",
+    dataset_type: DatasetType::Static(|| Synthetic::default().checkerboard(4).unwrap()),
+};
+
+// The NOISY_SINE dataset: x -> one period of a sine wave in [0, 1], with noise
+pub static NOISY_SINE: Dataset = Dataset {
+    name: "NOISY_SINE",
+    dataset_type: DatasetType::Static(|| Synthetic::default().sine().unwrap()),
+};
+
+// The SWISS_ROLL dataset: [x, y, z] on a rolled up sheet -> the position along
+// the roll
+pub static SWISS_ROLL: Dataset = Dataset {
+    name: "SWISS_ROLL",
+    dataset_type: DatasetType::Static(|| Synthetic::default().swiss_roll().unwrap()),
+};
+
+// Looks up one of the datasets above by its name
+pub fn by_name(name: &str) -> Option<&'static Dataset<'static>> {
+    [
+        &XOR,
+        &CIRCLE,
+        &RGB_TEST,
+        &RGB_DONUT,
+        &SINE_SEQUENCE,
+        &SPIRALS,
+        &MOONS,
+        &RINGS,
+        &BLOBS,
+        &CHECKERBOARD,
+        &NOISY_SINE,
+        &SWISS_ROLL,
+    ]
+    .into_iter()
+    .find(|dataset| dataset.name == name)
+}
@@ -0,0 +1,108 @@
+use std::{fs::File, io::Read, path::Path};
+
+use ndarray::{Array, Array2};
+
+use super::{Dataset, DatasetType};
+use crate::error::{Error, Result};
+
+impl<'a> Dataset<'a> {
+    // Loads a PNG image as a dataset of pixel coordinates in the unit square
+    // -> the RGB values of the pixels, so a network can learn to draw the
+    // image. The coordinates follow `get_2d_unit_square`: x from the left and
+    // y from the top.
+    pub fn from_png<P: AsRef<Path>>(name: &'a str, path: P) -> Result<Dataset<'a>> {
+        let (x, y) = Image::read(path)?.samples();
+        Ok(Dataset::new(name, DatasetType::InMemory(x, y)))
+    }
+}
+
+// The pixels of an image as RGB values in [0, 1], row by row from the top,
+// which is the layout `plot_png` writes. The original and a network's drawing
+// of it can be plotted side by side:
+//
+//     let image = Image::read("images/logo.png")?;
+//     plot_png(dir, "original", image.dims, &image.pixels, png::ColorType::Rgb)?;
+//     let drawn = network.predict_image(&image)?;
+//     plot_png(dir, "drawn", image.dims, &drawn, png::ColorType::Rgb)?;
+//     println!("error: {}", image.mean_squared_error(&drawn)?);
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    // (columns, rows)
+    pub dims: (usize, usize),
+    pub pixels: Vec<Vec<f64>>,
+}
+
+impl Image {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Image> {
+        Image::decode(File::open(path)?)
+    }
+
+    // Decodes a PNG of any color type and bit depth. Grayscale is repeated in
+    // every channel and the alpha channel is dropped.
+    pub fn decode<R: Read>(reader: R) -> Result<Image> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let samples = info.color_type.samples();
+        let pixels = buffer
+            .chunks_exact(samples)
+            .map(|pixel| {
+                let value = |channel: usize| pixel[channel] as f64 / 255.0;
+                match info.color_type {
+                    png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                        vec![value(0); 3]
+                    }
+                    _ => vec![value(0), value(1), value(2)],
+                }
+            })
+            .collect();
+
+        Ok(Image {
+            dims: (info.width as usize, info.height as usize),
+            pixels,
+        })
+    }
+
+    // The coordinates of every pixel in the unit square, in the order of the
+    // pixels
+    pub fn coordinates(&self) -> Array2<f64> {
+        let (columns, rows) = self.dims;
+        let xs = Array::linspace(0.0, 1.0, columns);
+        let ys = Array::linspace(0.0, 1.0, rows);
+
+        Array2::from_shape_fn((columns * rows, 2), |(i, j)| match j {
+            0 => xs[i % columns],
+            _ => ys[i / columns],
+        })
+    }
+
+    // The coordinates and the RGB values of every pixel
+    pub fn samples(&self) -> (Array2<f64>, Array2<f64>) {
+        let y = Array2::from_shape_fn((self.pixels.len(), 3), |(i, j)| self.pixels[i][j]);
+        (self.coordinates(), y)
+    }
+
+    // The mean squared difference of every channel of every pixel
+    pub fn mean_squared_error(&self, pixels: &[Vec<f64>]) -> Result<f64> {
+        if pixels.len() != self.pixels.len() || pixels.iter().any(|pixel| pixel.len() != 3) {
+            return Err(Error::InvalidData(format!(
+                "expected {}x{} pixels with 3 values each",
+                self.dims.0, self.dims.1
+            )));
+        }
+
+        let total = self
+            .pixels
+            .iter()
+            .flatten()
+            .zip(pixels.iter().flatten())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>();
+        Ok(total / (3 * pixels.len()).max(1) as f64)
+    }
+}
@@ -0,0 +1,297 @@
+use ndarray::{s, Array1, Array2, Axis};
+
+use crate::error::{Error, Result};
+
+// Principal component analysis, a linear projection onto the directions of
+// largest variance:
+//
+//     let pca = Pca::fit(&x, 2)?;
+//     let points = pca.transform(&x)?;
+#[derive(Debug, Clone)]
+pub struct Pca {
+    mean: Array1<f64>,
+    // One component per column, sorted by decreasing variance
+    components: Array2<f64>,
+    variance: Vec<f64>,
+    total_variance: f64,
+}
+
+impl Pca {
+    pub fn fit(data: &Array2<f64>, components: usize) -> Result<Pca> {
+        let (samples, features) = data.dim();
+        if samples < 2 || components == 0 || components > features {
+            return Err(Error::InvalidData(format!(
+                "can't find {} components of {} samples with {} features",
+                components, samples, features
+            )));
+        }
+
+        let mean = data.mean_axis(Axis(0)).unwrap();
+        let centered = data - &mean;
+        let covariance = centered.t().dot(&centered) / (samples - 1) as f64;
+        let total_variance = covariance.diag().sum();
+
+        let (eigenvalues, mut eigenvectors) = symmetric_eigen(covariance);
+
+        let mut order = (0..features).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+        order.truncate(components);
+
+        // the sign of an eigenvector is arbitrary, its largest entry is made
+        // positive so that fits of the same data agree
+        for &k in &order {
+            let mut column = eigenvectors.column_mut(k);
+            let largest = column.iter().copied().fold(0.0, |largest: f64, value| {
+                match value.abs() > largest.abs() {
+                    true => value,
+                    false => largest,
+                }
+            });
+            if largest < 0.0 {
+                column.mapv_inplace(|value| -value);
+            }
+        }
+
+        Ok(Pca {
+            mean,
+            components: eigenvectors.select(Axis(1), &order),
+            variance: order.iter().map(|&k| eigenvalues[k].max(0.0)).collect(),
+            total_variance,
+        })
+    }
+
+    pub fn components(&self) -> &Array2<f64> {
+        &self.components
+    }
+
+    // The variance of the data along each component
+    pub fn explained_variance(&self) -> &[f64] {
+        &self.variance
+    }
+
+    // The share of the total variance along each component
+    pub fn explained_variance_ratio(&self) -> Vec<f64> {
+        self.variance
+            .iter()
+            .map(|variance| match self.total_variance > 0.0 {
+                true => variance / self.total_variance,
+                false => 0.0,
+            })
+            .collect()
+    }
+
+    pub fn transform(&self, data: &Array2<f64>) -> Result<Array2<f64>> {
+        if data.ncols() != self.mean.len() {
+            return Err(Error::ShapeMismatch {
+                what: "projected data",
+                expected: self.mean.len(),
+                found: data.ncols(),
+            });
+        }
+
+        Ok((data - &self.mean).dot(&self.components))
+    }
+}
+
+// The eigenvalues and eigenvectors (as columns) of a symmetric matrix, by
+// cyclic Jacobi rotations
+fn symmetric_eigen(mut a: Array2<f64>) -> (Vec<f64>, Array2<f64>) {
+    let n = a.nrows();
+    let mut v = Array2::eye(n);
+
+    for _ in 0..100 {
+        let off_diagonal = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[[p, q]] * a[[p, q]])
+            .sum::<f64>();
+        if off_diagonal < 1e-22 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[[p, q]].abs() < 1e-300 {
+                    continue;
+                }
+
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    (a.diag().to_vec(), v)
+}
+
+// t-distributed stochastic neighbor embedding, a nonlinear projection to two
+// dimensions that keeps neighbors close. It starts from the PCA projection,
+// so the same data always gives the same embedding. Every sample is compared
+// with every other one, so it is meant for a few thousand samples at most.
+#[derive(Debug, Clone)]
+pub struct Tsne {
+    // Roughly the number of neighbors every sample keeps close
+    pub perplexity: f64,
+    pub iterations: usize,
+    // Small datasets of a few dozen samples need a much smaller rate, e.g. 10
+    pub learning_rate: f64,
+    // Pulls clusters apart during the first quarter of the iterations
+    pub exaggeration: f64,
+}
+
+impl Default for Tsne {
+    fn default() -> Self {
+        Tsne {
+            perplexity: 30.0,
+            iterations: 500,
+            learning_rate: 200.0,
+            exaggeration: 12.0,
+        }
+    }
+}
+
+impl Tsne {
+    pub fn embed(&self, data: &Array2<f64>) -> Result<Array2<f64>> {
+        let samples = data.nrows();
+        if samples < 4 || data.ncols() == 0 {
+            return Err(Error::InvalidData(format!(
+                "can't embed {} samples with {} features",
+                samples,
+                data.ncols()
+            )));
+        }
+
+        let p = self.affinities(data);
+
+        let components = data.ncols().min(2);
+        let initial = Pca::fit(data, components)?.transform(data)?;
+        let mut y = Array2::zeros((samples, 2));
+        y.slice_mut(s![.., ..components]).assign(&initial);
+        let spread = y.std_axis(Axis(0), 0.0).fold(0.0, |a: f64, &b| a.max(b));
+        if spread > 0.0 {
+            y *= 1e-4 / spread;
+        }
+
+        let mut velocity = Array2::<f64>::zeros((samples, 2));
+        let mut gains = Array2::<f64>::ones((samples, 2));
+        let early = self.iterations / 4;
+
+        for iteration in 0..self.iterations {
+            let exaggeration = if iteration < early {
+                self.exaggeration
+            } else {
+                1.0
+            };
+            let momentum = if iteration < early { 0.5 } else { 0.8 };
+
+            // Student-t similarities of the embedding
+            let mut q = Array2::<f64>::zeros((samples, samples));
+            for i in 0..samples {
+                for j in i + 1..samples {
+                    let (dx, dy) = (y[[i, 0]] - y[[j, 0]], y[[i, 1]] - y[[j, 1]]);
+                    let value = 1.0 / (1.0 + dx * dx + dy * dy);
+                    q[[i, j]] = value;
+                    q[[j, i]] = value;
+                }
+            }
+            let total = q.sum().max(1e-12);
+
+            let mut gradient = Array2::<f64>::zeros((samples, 2));
+            for i in 0..samples {
+                for j in 0..samples {
+                    let force = (exaggeration * p[[i, j]] - q[[i, j]] / total) * q[[i, j]];
+                    if force != 0.0 {
+                        for k in 0..2 {
+                            gradient[[i, k]] += 4.0 * force * (y[[i, k]] - y[[j, k]]);
+                        }
+                    }
+                }
+            }
+
+            for ((gain, &g), &v) in gains.iter_mut().zip(&gradient).zip(&velocity) {
+                *gain = match (g > 0.0) != (v > 0.0) {
+                    true => *gain + 0.2,
+                    false => (*gain * 0.8).max(0.01),
+                };
+            }
+            velocity = momentum * &velocity - self.learning_rate * &gains * &gradient;
+            y += &velocity;
+
+            let mean = y.mean_axis(Axis(0)).unwrap();
+            y -= &mean;
+        }
+
+        Ok(y)
+    }
+
+    // The symmetric affinities of the samples, each row spread so that its
+    // entropy matches the perplexity
+    fn affinities(&self, data: &Array2<f64>) -> Array2<f64> {
+        let samples = data.nrows();
+        let perplexity = self.perplexity.clamp(1.0, (samples - 1) as f64 / 3.0);
+        let target = perplexity.ln();
+
+        let squared = data.map_axis(Axis(1), |row| row.dot(&row));
+        let gram = data.dot(&data.t());
+        let distances = Array2::from_shape_fn((samples, samples), |(i, j)| {
+            (squared[i] + squared[j] - 2.0 * gram[[i, j]]).max(0.0)
+        });
+
+        let mut p = Array2::<f64>::zeros((samples, samples));
+        for i in 0..samples {
+            let row = distances.row(i);
+            let (mut beta, mut low, mut high) = (1.0, 0.0, f64::INFINITY);
+
+            for _ in 0..64 {
+                // shifted by the nearest neighbor for numerical stability
+                let nearest = (0..samples)
+                    .filter(|&j| j != i)
+                    .map(|j| row[j])
+                    .fold(f64::INFINITY, f64::min);
+                let weights = Array1::from_shape_fn(samples, |j| match j == i {
+                    true => 0.0,
+                    false => (-(row[j] - nearest) * beta).exp(),
+                });
+                let sum = weights.sum();
+                let entropy = sum.ln() + beta * (&weights * &(&row - nearest)).sum() / sum;
+
+                p.row_mut(i).assign(&(&weights / sum));
+
+                if (entropy - target).abs() < 1e-5 {
+                    break;
+                }
+                if entropy > target {
+                    low = beta;
+                    beta = if high.is_finite() {
+                        (beta + high) / 2.0
+                    } else {
+                        beta * 2.0
+                    };
+                } else {
+                    high = beta;
+                    beta = (beta + low) / 2.0;
+                }
+            }
+        }
+
+        let p = (&p + &p.t()) / (2.0 * samples as f64);
+        p.mapv(|value| value.max(1e-12))
+    }
+}
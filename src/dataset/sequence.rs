@@ -0,0 +1,67 @@
+use ndarray::{s, Array2, Array3};
+
+use super::{Dataset, DatasetType};
+use crate::error::{Error, Result};
+
+impl<'a> Dataset<'a> {
+    // Creates a dataset of sequences shaped (samples, steps, features). Every
+    // sequence is flattened into one row, time step after time step, which is
+    // the layout recurrent layers expect.
+    pub fn from_sequences(
+        name: &'a str,
+        sequences: &Array3<f64>,
+        targets: Array2<f64>,
+    ) -> Result<Dataset<'a>> {
+        let (samples, steps, features) = sequences.dim();
+        if samples != targets.nrows() {
+            return Err(Error::InvalidData(format!(
+                "{} sequences, but {} targets",
+                samples,
+                targets.nrows()
+            )));
+        }
+
+        let x = sequences
+            .as_standard_layout()
+            .into_owned()
+            .into_shape((samples, steps * features))
+            .map_err(|e| Error::InvalidData(e.to_string()))?;
+
+        Ok(Dataset::new(name, DatasetType::InMemory(x, targets)))
+    }
+
+    // Cuts a time series shaped (time, features) into windows of `steps`
+    // consecutive rows, each labeled with the row following it
+    pub fn sliding_windows(
+        name: &'a str,
+        series: &Array2<f64>,
+        steps: usize,
+    ) -> Result<Dataset<'a>> {
+        let (x, y) = sliding_windows(series, steps)?;
+        Ok(Dataset::new(name, DatasetType::InMemory(x, y)))
+    }
+}
+
+// The flattened windows and the row following each of them
+pub fn sliding_windows(series: &Array2<f64>, steps: usize) -> Result<(Array2<f64>, Array2<f64>)> {
+    if steps == 0 || series.nrows() <= steps {
+        return Err(Error::InvalidData(format!(
+            "a series of {} rows has no windows of {} steps followed by a target",
+            series.nrows(),
+            steps
+        )));
+    }
+
+    let (samples, features) = (series.nrows() - steps, series.ncols());
+
+    let mut x = Array2::zeros((samples, steps * features));
+    for (i, mut row) in x.rows_mut().into_iter().enumerate() {
+        let window = series.slice(s![i..i + steps, ..]);
+        row.iter_mut()
+            .zip(window.iter())
+            .for_each(|(value, &x)| *value = x);
+    }
+    let y = series.slice(s![steps.., ..]).to_owned();
+
+    Ok((x, y))
+}
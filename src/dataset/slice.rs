@@ -0,0 +1,93 @@
+use ndarray::{Array, Array2, Axis};
+
+use crate::error::{Error, Result};
+
+// A 2D cut through the input space of a model with any number of inputs. Two
+// features are swept over their ranges, all others are held at fixed values:
+//
+//     // inputs 0 and 2 over [-1, 1], the others at the mean of the data
+//     let slice = FeatureSlice::at_mean(&x, (0, 2)).ranges((-1.0, 1.0), (-1.0, 1.0));
+//     let (dims, prediction) = network.predict_slice(&slice, 100)?;
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureSlice {
+    // The inputs swept along the x and the y axis
+    pub features: (usize, usize),
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    // The values of all inputs, the swept ones are ignored
+    pub fixed: Vec<f64>,
+}
+
+impl FeatureSlice {
+    // Sweeps the features over [0, 1] with the other inputs at zero
+    pub fn new(inputs: usize, features: (usize, usize)) -> FeatureSlice {
+        FeatureSlice {
+            features,
+            x_range: (0.0, 1.0),
+            y_range: (0.0, 1.0),
+            fixed: vec![0.0; inputs],
+        }
+    }
+
+    // Sweeps the features over their range in the data, with the other inputs
+    // at their mean
+    pub fn at_mean(data: &Array2<f64>, features: (usize, usize)) -> FeatureSlice {
+        let range = |feature: usize| {
+            if feature >= data.ncols() || data.nrows() == 0 {
+                return (0.0, 1.0);
+            }
+            data.column(feature)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                    (min.min(value), max.max(value))
+                })
+        };
+
+        FeatureSlice {
+            features,
+            x_range: range(features.0),
+            y_range: range(features.1),
+            fixed: data
+                .mean_axis(Axis(0))
+                .map_or_else(|| vec![0.0; data.ncols()], |mean| mean.to_vec()),
+        }
+    }
+
+    pub fn ranges(mut self, x_range: (f64, f64), y_range: (f64, f64)) -> FeatureSlice {
+        self.x_range = x_range;
+        self.y_range = y_range;
+        self
+    }
+
+    // The inputs at `resolution` x `resolution` evenly spaced points, row by
+    // row like `Dataset::get_2d_unit_square`
+    pub fn grid(&self, resolution: usize) -> Result<Array2<f64>> {
+        let (x, y) = self.features;
+        let inputs = self.fixed.len();
+        if x >= inputs || y >= inputs || x == y {
+            return Err(Error::InvalidData(format!(
+                "can't sweep features {} and {} of {} inputs",
+                x, y, inputs
+            )));
+        }
+
+        let xs = Array::linspace(self.x_range.0, self.x_range.1, resolution);
+        let ys = Array::linspace(self.y_range.0, self.y_range.1, resolution);
+
+        let mut grid = Array2::zeros((resolution * resolution, inputs));
+        for (k, mut row) in grid.rows_mut().into_iter().enumerate() {
+            row.iter_mut()
+                .zip(&self.fixed)
+                .for_each(|(value, &fixed)| *value = fixed);
+            row[x] = xs[k % resolution];
+            row[y] = ys[k / resolution];
+        }
+
+        Ok(grid)
+    }
+
+    // Maps a point of the unit square to the swept ranges
+    pub fn scale(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        let lerp = |(low, high): (f64, f64), t: f64| low + t * (high - low);
+        (lerp(self.x_range, u), lerp(self.y_range, v))
+    }
+}
@@ -0,0 +1,196 @@
+use std::f64::consts::PI;
+
+use ndarray::Array2;
+use ndarray_rand::{
+    rand::{rngs::StdRng, Rng, SeedableRng},
+    rand_distr::{Distribution, Normal},
+};
+
+use super::{Dataset, DatasetType};
+use crate::error::{Error, Result};
+
+// Generates classic synthetic benchmarks. Two dimensional inputs lie in the
+// unit square, so the networks can be plotted with `predict_unit_square`.
+// Classifications with two classes have one output that is 0 or 1, like
+// CIRCLE, and those with more classes one-hot outputs. The same seed always
+// gives the same samples:
+//
+//     let (x, y) = Synthetic::new(512, 0.05).blobs(4)?;
+//     let dataset = Dataset::new("Blobs", DatasetType::InMemory(x, y));
+//
+// `synthetic.dataset("Blobs", synthetic.blobs(4)?)` does the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Synthetic {
+    pub samples: usize,
+    // The standard deviation of the Gaussian noise added to every input, in
+    // units of the unit square
+    pub noise: f64,
+    pub seed: u64,
+}
+
+impl Default for Synthetic {
+    fn default() -> Self {
+        Synthetic::new(1024, 0.05)
+    }
+}
+
+impl Synthetic {
+    pub fn new(samples: usize, noise: f64) -> Synthetic {
+        Synthetic {
+            samples,
+            noise,
+            seed: 0,
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // Wraps generated samples into a dataset
+    pub fn dataset<'a>(&self, name: &'a str, data: (Array2<f64>, Array2<f64>)) -> Dataset<'a> {
+        Dataset::new(name, DatasetType::InMemory(data.0, data.1))
+    }
+
+    // Interleaved spirals, one arm per class, winding one and a half times
+    // around the center
+    pub fn spirals(&self, classes: usize) -> Result<(Array2<f64>, Array2<f64>)> {
+        self.classification(classes, |class, rng| {
+            let t = rng.gen::<f64>();
+            let angle = 3.0 * PI * t + 2.0 * PI * class as f64 / classes as f64;
+            let radius = 0.05 + 0.4 * t;
+            [0.5 + radius * angle.cos(), 0.5 + radius * angle.sin()]
+        })
+    }
+
+    // Two interleaving half circles
+    pub fn moons(&self) -> Result<(Array2<f64>, Array2<f64>)> {
+        self.classification(2, |class, rng| {
+            let angle = PI * rng.gen::<f64>();
+            // the moons span [-1, 2] x [-0.5, 1] and are scaled to the square
+            let (x, y) = match class {
+                0 => (angle.cos(), angle.sin()),
+                _ => (1.0 - angle.cos(), 0.5 - angle.sin()),
+            };
+            [(x + 1.0) / 3.0, (y + 0.5) / 3.0 + 0.25]
+        })
+    }
+
+    // Concentric rings around the center, the innermost ring is class 0
+    pub fn rings(&self, classes: usize) -> Result<(Array2<f64>, Array2<f64>)> {
+        self.classification(classes, |class, rng| {
+            let angle = 2.0 * PI * rng.gen::<f64>();
+            let radius = 0.45 * (class + 1) as f64 / classes as f64;
+            [0.5 + radius * angle.cos(), 0.5 + radius * angle.sin()]
+        })
+    }
+
+    // Gaussian blobs with centers evenly spaced on a circle, spread by the
+    // noise
+    pub fn blobs(&self, classes: usize) -> Result<(Array2<f64>, Array2<f64>)> {
+        self.classification(classes, |class, _| {
+            let angle = 2.0 * PI * class as f64 / classes as f64;
+            [0.5 + 0.3 * angle.cos(), 0.5 + 0.3 * angle.sin()]
+        })
+    }
+
+    // Alternating classes on a grid of `cells` x `cells` squares
+    pub fn checkerboard(&self, cells: usize) -> Result<(Array2<f64>, Array2<f64>)> {
+        if cells == 0 {
+            return Err(Error::InvalidData(String::from(
+                "a checkerboard needs at least one cell",
+            )));
+        }
+
+        // the class follows from the point, so points are drawn until they
+        // fall on a square of the class
+        let cell = |value: f64| ((value * cells as f64) as usize).min(cells - 1);
+        self.classification(2, |class, rng| loop {
+            let point = [rng.gen::<f64>(), rng.gen::<f64>()];
+            if (cell(point[0]) + cell(point[1])) % 2 == class {
+                return point;
+            }
+        })
+    }
+
+    // One period of a sine wave scaled to [0, 1], with the noise added to the
+    // targets
+    pub fn sine(&self) -> Result<(Array2<f64>, Array2<f64>)> {
+        let (mut rng, normal) = self.generator()?;
+
+        let x = Array2::from_shape_simple_fn((self.samples, 1), || rng.gen::<f64>());
+        let y = x.mapv(|x| 0.5 + 0.4 * (2.0 * PI * x).sin() + normal.sample(&mut rng));
+
+        Ok((x, y))
+    }
+
+    // Points on a rolled up sheet in three dimensions, labeled with their
+    // position along the roll in [0, 1]. A projection like t-SNE can unroll
+    // it.
+    pub fn swiss_roll(&self) -> Result<(Array2<f64>, Array2<f64>)> {
+        let (mut rng, normal) = self.generator()?;
+        // the largest angle, which scales the roll to the unit cube
+        let largest = 4.5 * PI;
+
+        let mut x = Array2::zeros((self.samples, 3));
+        let mut y = Array2::zeros((self.samples, 1));
+        for i in 0..self.samples {
+            let t = rng.gen::<f64>();
+            let angle = 1.5 * PI * (1.0 + 2.0 * t);
+            let point = [
+                0.5 + angle * angle.cos() / (2.0 * largest),
+                rng.gen::<f64>(),
+                0.5 + angle * angle.sin() / (2.0 * largest),
+            ];
+            for (j, value) in point.into_iter().enumerate() {
+                x[[i, j]] = value + normal.sample(&mut rng);
+            }
+            y[[i, 0]] = t;
+        }
+
+        Ok((x, y))
+    }
+
+    // Samples that are spread evenly over the classes, each drawn as a point
+    // of its class with noise added
+    fn classification(
+        &self,
+        classes: usize,
+        mut point: impl FnMut(usize, &mut StdRng) -> [f64; 2],
+    ) -> Result<(Array2<f64>, Array2<f64>)> {
+        if classes < 2 {
+            return Err(Error::InvalidData(format!(
+                "can't classify into {} classes",
+                classes
+            )));
+        }
+        let (mut rng, normal) = self.generator()?;
+
+        let outputs = if classes == 2 { 1 } else { classes };
+        let mut x = Array2::zeros((self.samples, 2));
+        let mut y = Array2::zeros((self.samples, outputs));
+        for i in 0..self.samples {
+            let class = i % classes;
+            for (j, value) in point(class, &mut rng).into_iter().enumerate() {
+                x[[i, j]] = value + normal.sample(&mut rng);
+            }
+            match outputs {
+                1 => y[[i, 0]] = class as f64,
+                _ => y[[i, class]] = 1.0,
+            }
+        }
+
+        Ok((x, y))
+    }
+
+    fn generator(&self) -> Result<(StdRng, Normal<f64>)> {
+        let normal = match self.noise.is_finite() && self.noise >= 0.0 {
+            true => Normal::new(0.0, self.noise).ok(),
+            false => None,
+        };
+        let normal =
+            normal.ok_or_else(|| Error::InvalidData(format!("invalid noise {}", self.noise)))?;
+        Ok((StdRng::seed_from_u64(self.seed), normal))
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fmt, io};
+
+use crate::{
+    config::ConfigError,
+    neural_network::{builder::BuildError, divergence::Divergence},
+};
+
+#[derive(Debug)]
+pub enum Error {
+    // The number of columns of some data doesn't match the network
+    ShapeMismatch {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    EmptyNetwork,
+    EmptyDataset(String),
+    // Dynamic datasets generate samples on demand and have no full dataset
+    NoFullDataset(String),
+    EmptyHistory,
+    NonFiniteCost,
+    // Training produced a NaN or infinite value, see `DivergencePolicy`
+    Diverged(Divergence),
+    InvalidData(String),
+    Io(io::Error),
+    Plot(String),
+    Build(BuildError),
+    Config(ConfigError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ShapeMismatch {
+                what,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} has {} columns, but the network expects {}",
+                what, found, expected
+            ),
+            Error::EmptyNetwork => write!(f, "the network has no layers"),
+            Error::EmptyDataset(name) => write!(f, "dataset '{}' contains no samples", name),
+            Error::NoFullDataset(name) => write!(
+                f,
+                "dataset '{}' is generated on demand and can only be sampled in batches",
+                name
+            ),
+            Error::EmptyHistory => write!(f, "the cost history is empty"),
+            Error::NonFiniteCost => write!(f, "the cost history contains no finite values"),
+            Error::Diverged(divergence) => {
+                write!(f, "training diverged: non-finite {}", divergence)
+            }
+            Error::InvalidData(message) => write!(f, "invalid data: {}", message),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Plot(message) => write!(f, "could not plot: {}", message),
+            Error::Build(e) => write!(f, "{}", e),
+            Error::Config(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Build(e) => Some(e),
+            Error::Config(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Error {
+        Error::Build(e)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Error {
+        Error::Config(e)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(e: png::EncodingError) -> Error {
+        match e {
+            png::EncodingError::IoError(e) => Error::Io(e),
+            e => Error::Plot(e.to_string()),
+        }
+    }
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(e: png::DecodingError) -> Error {
+        match e {
+            png::DecodingError::IoError(e) => Error::Io(e),
+            e => Error::InvalidData(e.to_string()),
+        }
+    }
+}
+
+impl<E: std::error::Error + Send + Sync> From<plotters::drawing::DrawingAreaErrorKind<E>>
+    for Error
+{
+    fn from(e: plotters::drawing::DrawingAreaErrorKind<E>) -> Error {
+        Error::Plot(e.to_string())
+    }
+}
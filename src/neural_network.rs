@@ -1,29 +1,78 @@
 pub mod activation_function;
+pub mod builder;
 pub mod cost_function;
+pub mod divergence;
+pub mod gradient_check;
+pub mod graph;
 pub mod layer;
+pub mod monitor;
 pub mod optimizer;
+pub mod summary;
+pub mod workspace;
 
-use crate::dataset::Dataset;
+use crate::{
+    dataset::{image::Image, slice::FeatureSlice, Dataset, DatasetType},
+    error::{Error, Result},
+};
 
 use ndarray::Array2;
 
-use self::{cost_function::CostFunction, layer::Layer, optimizer::Optimizer};
+use self::{
+    builder::NetworkBuilder,
+    cost_function::CostFunction,
+    divergence::{log_step, DivergenceGuard, DivergencePolicy, Step},
+    layer::Layer,
+    optimizer::Optimizer,
+    summary::{ModelSummary, SummaryRow},
+    workspace::{ensure_shape, Workspace},
+};
+
+// The gradients of the biases and the weights, one entry per layer
+pub type Gradients = (Vec<Array2<f64>>, Vec<Array2<f64>>);
+
+// The image dimensions and one row of outputs per pixel
+pub type UnitSquarePrediction = ((usize, usize), Vec<Vec<f64>>);
+
+// The values logged during training, as (epoch, value) pairs
+#[derive(Debug, Clone, Default)]
+pub struct TrainingHistory {
+    pub cost: Vec<(i32, f64)>,
+    // Only logged by `train_and_validate`
+    pub validation: Vec<(i32, f64)>,
+    pub learning_rate: Vec<(i32, f64)>,
+}
+
+// Takes snapshots of a network at some epochs of the training, e.g. the
+// frames of a `TrainingAnimation` or the distributions of a
+// `DistributionMonitor`
+pub trait Recorder {
+    // Whether a snapshot is due at this epoch of a training of `epochs` epochs
+    fn is_due(&self, epoch: i32, epochs: i32) -> bool;
+    fn record(&mut self, network: &Network, epoch: i32, cost: f64) -> Result<()>;
+}
 
-pub struct Network<'a> {
+pub struct Network {
     input_size: usize,
     output_size: usize,
     layers: Vec<Box<dyn Layer>>,
-    optimizer: &'a mut dyn Optimizer,
-    cost_function: &'static dyn CostFunction,
+    optimizer: Box<dyn Optimizer>,
+    cost_function: Box<dyn CostFunction>,
+    workspace: Workspace,
+    guard: DivergenceGuard,
 }
 
 #[allow(non_snake_case)]
-impl Network<'_> {
-    pub fn new<'a>(
+impl Network {
+    pub fn new(
         mut layers: Vec<Box<dyn Layer>>,
-        optimizer: &'a mut dyn Optimizer,
-        cost_function: &'static dyn CostFunction,
-    ) -> Network<'a> {
+        optimizer: Box<dyn Optimizer>,
+        cost_function: Box<dyn CostFunction>,
+    ) -> Result<Network> {
+        // the last layer only describes the output size, so at least two are needed
+        if layers.len() < 2 {
+            return Err(Error::EmptyNetwork);
+        }
+
         // Initialize the layers
         let network_shape = layers.iter().map(|l| l.get_size()).collect::<Vec<_>>();
 
@@ -36,156 +85,339 @@ impl Network<'_> {
             layer.initialize(network_shape[i], network_shape[i + 1]);
         }
 
+        Ok(Network::from_layers(layers, optimizer, cost_function))
+    }
+
+    // Describes the network layer by layer, see `NetworkBuilder`
+    pub fn builder() -> NetworkBuilder {
+        NetworkBuilder::new()
+    }
+
+    // Creates a network from layers that are already initialized
+    pub(crate) fn from_layers(
+        layers: Vec<Box<dyn Layer>>,
+        mut optimizer: Box<dyn Optimizer>,
+        cost_function: Box<dyn CostFunction>,
+    ) -> Network {
         optimizer.initialize(&layers);
 
         Network {
-            input_size: network_shape[0],
-            output_size: network_shape[network_shape.len() - 1],
+            input_size: layers[0].get_size(),
+            output_size: layers[layers.len() - 1].get_output_size(),
             layers,
             optimizer,
             cost_function,
+            workspace: Workspace::new(),
+            guard: DivergenceGuard::default(),
         }
     }
 
+    pub fn layers(&self) -> &[Box<dyn Layer>] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [Box<dyn Layer>] {
+        &mut self.layers
+    }
+
+    pub fn divergence_policy(&self) -> DivergencePolicy {
+        self.guard.policy
+    }
+
+    pub fn set_divergence_policy(&mut self, policy: DivergencePolicy) {
+        self.guard.policy = policy;
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    pub fn learning_rate(&self) -> f64 {
+        self.optimizer.current_learning_rate()
+    }
+
     // Predicts the output of the network given an input
-    pub fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+    pub fn predict(&self, input: &Array2<f64>) -> Result<Array2<f64>> {
+        check_columns("input", input, self.input_size)?;
+        self.layers[0].check_input(input)?;
+
         let mut output = input.clone();
         for layer in &self.layers {
             output = layer.predict(&output);
         }
-        output
+        Ok(output)
     }
 
     // Calculates the needed adjustments to the weights and biases for a given input and expected output
-    pub fn backprop(
+    pub fn backprop(&self, X: &Array2<f64>, y: &Array2<f64>) -> Result<Gradients> {
+        let mut workspace = Workspace::new();
+        self.backprop_into(X, y, &mut workspace)?;
+        Ok(workspace.into_gradients())
+    }
+
+    // Same as `backprop`, but keeps all intermediate values and the resulting
+    // gradients in `workspace`, so repeated calls reuse the same buffers
+    pub fn backprop_into(
         &self,
         X: &Array2<f64>,
         y: &Array2<f64>,
-    ) -> (Vec<Array2<f64>>, Vec<Array2<f64>>) {
-        let mut nabla_bs = Vec::new();
-        let mut nabla_ws = Vec::new();
-
-        // Forward pass
-        let mut activation = X.clone();
-        let mut activations = vec![activation.clone()];
-        let mut zs = Vec::new();
-        for layer in &self.layers {
-            let z = layer.forward(&activation);
-            zs.push(z.clone());
-            activation = layer.get_activation().f_array(&z);
-            activations.push(activation.clone());
-        }
-
-        // Calculate the cost
-        let nabla_c = self.cost_function.cost_derivative(&activation, &y);
-
-        // Calculate sensitivity
-        let sig_prime = self.layers[self.layers.len() - 1]
-            .get_activation()
-            .d_array(&zs[zs.len() - 1]);
-
-        // Calculate delta for last layer
-        let mut delta = nabla_c * sig_prime;
-
-        // Calculate nabla_b and nabla_w for last layer
-        nabla_bs.push(delta.clone());
-        nabla_ws.push((&activations[activations.len() - 2]).t().dot(&delta));
-
-        // Loop backwards through the layers, calculating delta, nabla_b and nabla_w
-        for i in 2..self.layers.len() + 1 {
-            let sig_prime = self.layers[self.layers.len() - i]
-                .get_activation()
-                .d_array(&zs[zs.len() - i]);
+        workspace: &mut Workspace,
+    ) -> Result<()> {
+        self.check_batch(X, y)?;
 
-            let nabla_c = &delta.dot(&self.layers[self.layers.len() - i + 1].get_weights().t());
+        workspace.prepare(&self.layers);
 
-            delta = nabla_c * sig_prime;
+        let Workspace {
+            zs,
+            activations,
+            deltas,
+            nabla_ws,
+            nabla_bs,
+            sparse_rows,
+        } = workspace;
 
-            nabla_bs.push(delta.clone());
-            nabla_ws.push((&activations[activations.len() - i - 1].t()).dot(&delta));
+        // Forward pass
+        for (i, layer) in self.layers.iter().enumerate() {
+            let (previous, current) = activations.split_at_mut(i);
+            let input = previous.last().unwrap_or(X);
+            layer.forward_into(input, &mut zs[i], &mut current[0]);
         }
 
-        // restore correct ordering
-        nabla_bs.reverse();
-        nabla_ws.reverse();
-
-        //Adjust for batch size
-        let batch_size = X.nrows() as f64;
-        for (nabla_b, nabla_w) in nabla_bs.iter_mut().zip(nabla_ws.iter_mut()) {
-            *nabla_b = nabla_b
-                .sum_axis(ndarray::Axis(0))
-                .into_shape((1, nabla_b.ncols()))
-                .unwrap();
+        // Calculate the derivative of the cost, averaged over the batch
+        let last = self.layers.len() - 1;
+        ensure_shape(&mut deltas[last], activations[last].dim());
+        self.cost_function.d_into(
+            &activations[last],
+            y,
+            1.0 / X.nrows() as f64,
+            &mut deltas[last],
+        );
 
-            *nabla_b /= batch_size;
-            *nabla_w /= batch_size;
+        // Loop backwards through the layers, calculating delta, nabla_b and nabla_w
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let (previous, current) = deltas.split_at_mut(i);
+            let input = if i == 0 { X } else { &activations[i - 1] };
+
+            layer.backward_into(
+                input,
+                &zs[i],
+                &mut current[0],
+                &mut nabla_ws[i],
+                &mut nabla_bs[i],
+                previous.last_mut(),
+            );
+            // the rows of the last step were cleared by `prepare`, their
+            // buffer is reused
+            let mut rows = sparse_rows[i].take().unwrap_or_default();
+            rows.clear();
+            sparse_rows[i] = layer.sparse_rows(input, &mut rows).then_some(rows);
         }
 
-        (nabla_bs, nabla_ws)
+        Ok(())
     }
 
-    // Trains the network using a minibatch
-    pub fn train_minibatch(&mut self, (X, y): &(Array2<f64>, Array2<f64>)) {
-        //assert iput shape is the same as the data
-        assert_eq!(
-            X.ncols(),
-            self.input_size,
-            "Input shape does not match data"
-        );
-        assert_eq!(
-            y.ncols(),
-            self.output_size,
-            "Output shape does not match data"
-        );
+    // Trains the network using a minibatch. Non-finite costs, gradients or
+    // weights are handled according to the divergence policy.
+    pub fn train_minibatch(&mut self, (X, y): &(Array2<f64>, Array2<f64>)) -> Result<Step> {
+        let mut workspace = std::mem::take(&mut self.workspace);
+        let result = self.step(X, y, &mut workspace);
+        self.workspace = workspace;
+        result
+    }
 
-        let (nabla_bs, nabla_ws) = self.backprop(X, y);
+    fn step(
+        &mut self,
+        X: &Array2<f64>,
+        y: &Array2<f64>,
+        workspace: &mut Workspace,
+    ) -> Result<Step> {
+        self.backprop_into(X, y, workspace)?;
+
+        let cost = self.cost_function.cost(workspace.output(), y);
+        self.guard.update(
+            &mut self.layers,
+            self.optimizer.as_mut(),
+            cost,
+            workspace.nabla_bs(),
+            workspace.nabla_ws(),
+            workspace.sparse_rows(),
+        )
+    }
 
-        self.optimizer.pre_update();
+    // Trains the network using a dataset, records the cost for each epoch
+    pub fn train_and_log(
+        &mut self,
+        data: &Dataset,
+        batch_size: usize,
+        verification_samples: usize,
+        epochs: i32,
+    ) -> Result<Vec<(i32, f64)>> {
+        let history = self.train(data, None, batch_size, verification_samples, epochs, None)?;
+        Ok(history.cost)
+    }
 
-        self.optimizer
-            .update_params(&mut self.layers, &nabla_bs, &nabla_ws);
+    // Like `train_and_log`, also lets the recorder take snapshots, e.g. the
+    // frames of a training animation
+    pub fn train_and_record(
+        &mut self,
+        data: &Dataset,
+        batch_size: usize,
+        verification_samples: usize,
+        epochs: i32,
+        recorder: &mut dyn Recorder,
+    ) -> Result<Vec<(i32, f64)>> {
+        let history = self.train(
+            data,
+            None,
+            batch_size,
+            verification_samples,
+            epochs,
+            Some(recorder),
+        )?;
+        Ok(history.cost)
+    }
 
-        self.optimizer.post_update();
+    // Like `train_and_log`, also logs the cost on a validation dataset and the
+    // learning rate
+    pub fn train_and_validate(
+        &mut self,
+        data: &Dataset,
+        validation: &Dataset,
+        batch_size: usize,
+        verification_samples: usize,
+        epochs: i32,
+    ) -> Result<TrainingHistory> {
+        self.train(
+            data,
+            Some(validation),
+            batch_size,
+            verification_samples,
+            epochs,
+            None,
+        )
     }
 
-    // Trains the network using a dataset, records the cost for each epoch
-    pub fn train_and_log(
+    fn train(
         &mut self,
         data: &Dataset,
+        validation: Option<&Dataset>,
         batch_size: usize,
         verification_samples: usize,
         epochs: i32,
-    ) -> Vec<(i32, f64)> {
-        let mut cost_history = Vec::new();
+        mut recorder: Option<&mut dyn Recorder>,
+    ) -> Result<TrainingHistory> {
+        let mut history = TrainingHistory::default();
+
+        // static datasets are generated on every call, so they are generated
+        // once and batches are sampled from memory
+        let generated;
+        let data = match &data.dataset_type {
+            DatasetType::Static(generate) => {
+                let (x, y) = generate();
+                generated = Dataset::new(data.name, DatasetType::InMemory(x, y));
+                &generated
+            }
+            _ => data,
+        };
 
+        let mut batch = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
         for epoch in 0..epochs {
-            self.train_minibatch(&data.get_batch(batch_size));
-
-            if epoch % (epochs / 100 + 1) == 0 {
-                let cost = self.eval(data, verification_samples);
-                cost_history.push((epoch, cost));
+            data.get_batch_into(batch_size, &mut batch)?;
+            let step = self.train_minibatch(&batch)?;
+            log_step(epoch, step);
+
+            let log = epoch % (epochs / 100 + 1) == 0;
+            let snapshot = recorder
+                .as_ref()
+                .is_some_and(|recorder| recorder.is_due(epoch, epochs));
+            if !log && !snapshot {
+                continue;
+            }
 
-                println!("Epoch: {}, Cost: {:.8}", epoch, cost);
+            let cost = self.eval(data, verification_samples)?;
+            if log {
+                history.cost.push((epoch, cost));
+                history
+                    .learning_rate
+                    .push((epoch, self.optimizer.current_learning_rate()));
+
+                match validation {
+                    Some(validation) => {
+                        let validation_cost = self.eval(validation, verification_samples)?;
+                        history.validation.push((epoch, validation_cost));
+                        println!(
+                            "Epoch: {}, Cost: {:.8}, Validation: {:.8}",
+                            epoch, cost, validation_cost
+                        );
+                    }
+                    None => println!("Epoch: {}, Cost: {:.8}", epoch, cost),
+                }
+            }
+            if let Some(recorder) = recorder.as_deref_mut().filter(|_| snapshot) {
+                recorder.record(self, epoch, cost)?;
             }
         }
 
-        cost_history
+        Ok(history)
     }
 
     // Evaluates the network on a given dataset
-    pub fn eval(&self, data: &Dataset, sample_size: usize) -> f64 {
-        let (x, y) = data.get_batch(sample_size);
+    pub fn eval(&self, data: &Dataset, sample_size: usize) -> Result<f64> {
+        let (x, y) = data.get_batch(sample_size)?;
+        self.check_batch(&x, &y)?;
 
-        let prediction = self.predict(&x);
-        let cost = self.cost_function.cost(&prediction, &y);
-        cost
+        let prediction = self.predict(&x)?;
+        Ok(self.cost_function.cost(&prediction, &y))
+    }
+
+    // Checks that inputs and expected outputs fit the network and each other
+    fn check_batch(&self, X: &Array2<f64>, y: &Array2<f64>) -> Result<()> {
+        check_columns("input", X, self.input_size)?;
+        check_columns("expected output", y, self.output_size)?;
+        self.layers[0].check_input(X)?;
+
+        if X.nrows() != y.nrows() {
+            return Err(Error::InvalidData(format!(
+                "{} inputs, but {} expected outputs",
+                X.nrows(),
+                y.nrows()
+            )));
+        }
+
+        Ok(())
     }
 
     // evaluates the prediction-results for the unit-square, returns a list
     // containing the result for each point in a row by row fashion
-    pub fn predict_unit_square(&self, resolution: usize) -> ((usize, usize), Vec<Vec<f64>>) {
+    pub fn predict_unit_square(&self, resolution: usize) -> Result<UnitSquarePrediction> {
         let unit_square = Dataset::get_2d_unit_square(resolution);
-        let pred = self.predict(&unit_square);
+        self.predict_grid(&unit_square, resolution)
+    }
+
+    // Like `predict_unit_square`, sweeping two of any number of inputs over
+    // their ranges while the others stay fixed
+    pub fn predict_slice(
+        &self,
+        slice: &FeatureSlice,
+        resolution: usize,
+    ) -> Result<UnitSquarePrediction> {
+        let grid = slice.grid(resolution)?;
+        self.predict_grid(&grid, resolution)
+    }
+
+    // Draws every pixel of the image from its coordinates, in the layout of
+    // `Image::pixels`
+    pub fn predict_image(&self, image: &Image) -> Result<Vec<Vec<f64>>> {
+        let pred = self.predict(&image.coordinates())?;
+        Ok(pred.rows().into_iter().map(|x| x.to_vec()).collect())
+    }
+
+    fn predict_grid(&self, grid: &Array2<f64>, resolution: usize) -> Result<UnitSquarePrediction> {
+        let pred = self.predict(grid)?;
 
         let res = pred
             .lanes(ndarray::Axis(1))
@@ -193,18 +425,64 @@ impl Network<'_> {
             .map(|x| x.to_vec())
             .collect();
 
-        ((resolution, resolution), res)
+        Ok(((resolution, resolution), res))
+    }
+
+    // The outputs of every layer for the input, the last one being the
+    // prediction, e.g. to project the hidden representation with `Pca`
+    pub fn activations(&self, input: &Array2<f64>) -> Result<Vec<Array2<f64>>> {
+        check_columns("input", input, self.input_size)?;
+        self.layers[0].check_input(input)?;
+
+        let mut activations: Vec<Array2<f64>> = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let output = layer.predict(activations.last().unwrap_or(input));
+            activations.push(output);
+        }
+        Ok(activations)
+    }
+}
+
+fn check_columns(what: &'static str, data: &Array2<f64>, expected: usize) -> Result<()> {
+    if data.ncols() != expected {
+        return Err(Error::ShapeMismatch {
+            what,
+            expected,
+            found: data.ncols(),
+        });
     }
+
+    Ok(())
 }
 
 pub trait Summary {
     fn summerize(&self) -> String;
 }
 
-impl Summary for Network<'_> {
+impl Summary for Network {
     fn summerize(&self) -> String {
         let shape = self.layers.iter().map(|x| x.get_size()).collect::<Vec<_>>();
 
         format!("{}_{:?}", self.optimizer.summerize(), shape).replace(" ", "")
     }
 }
+
+impl Network {
+    // The input and every layer with its output shape, activation and
+    // parameter count
+    pub fn summary(&self) -> ModelSummary {
+        let layers = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| SummaryRow::layer(layer.as_ref(), i));
+
+        ModelSummary {
+            title: self.summerize(),
+            rows: std::iter::once(SummaryRow::input(self.input_size))
+                .chain(layers)
+                .collect(),
+            outputs: vec![self.layers.len()],
+        }
+    }
+}
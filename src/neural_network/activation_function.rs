@@ -1,9 +1,13 @@
-use ndarray::Array;
-//Sized
+use ndarray::{Array, Zip};
 
-pub trait ActivationFunction {
+pub trait ActivationFunction: Send + Sync {
     fn f(&self, x: f64) -> f64;
     fn d(&self, x: f64) -> f64;
+
+    // The name used in configs and model summaries
+    fn name(&self) -> &'static str {
+        "custom"
+    }
 }
 
 impl dyn ActivationFunction {
@@ -14,11 +18,27 @@ impl dyn ActivationFunction {
     pub fn d_array<D: ndarray::Dimension>(&self, x: &Array<f64, D>) -> Array<f64, D> {
         x.mapv(|x| self.d(x))
     }
+
+    // Writes f(x) into an existing array of the same shape
+    pub fn f_into<D: ndarray::Dimension>(&self, x: &Array<f64, D>, out: &mut Array<f64, D>) {
+        Zip::from(out).and(x).for_each(|out, &x| *out = self.f(x));
+    }
+
+    // Multiplies `delta` element-wise with d(x), used to chain gradients in place
+    pub fn mul_d<D: ndarray::Dimension>(&self, x: &Array<f64, D>, delta: &mut Array<f64, D>) {
+        Zip::from(delta)
+            .and(x)
+            .for_each(|delta, &x| *delta *= self.d(x));
+    }
 }
 
 pub struct Relu;
 
 impl ActivationFunction for Relu {
+    fn name(&self) -> &'static str {
+        "relu"
+    }
+
     fn f(&self, x: f64) -> f64 {
         x.max(0.0)
     }
@@ -35,6 +55,10 @@ impl ActivationFunction for Relu {
 pub struct Sigmoid;
 
 impl ActivationFunction for Sigmoid {
+    fn name(&self) -> &'static str {
+        "sigmoid"
+    }
+
     fn f(&self, x: f64) -> f64 {
         if x > 45.0 {
             1.0
@@ -54,6 +78,10 @@ impl ActivationFunction for Sigmoid {
 pub struct Linear;
 
 impl ActivationFunction for Linear {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
     fn f(&self, x: f64) -> f64 {
         x
     }
@@ -62,3 +90,19 @@ impl ActivationFunction for Linear {
         1.0
     }
 }
+
+pub struct Tanh;
+
+impl ActivationFunction for Tanh {
+    fn name(&self) -> &'static str {
+        "tanh"
+    }
+
+    fn f(&self, x: f64) -> f64 {
+        x.tanh()
+    }
+
+    fn d(&self, x: f64) -> f64 {
+        1.0 - x.tanh().powi(2)
+    }
+}
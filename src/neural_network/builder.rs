@@ -0,0 +1,327 @@
+use std::fmt;
+
+use super::{
+    activation_function::{ActivationFunction, Tanh},
+    cost_function::CostFunction,
+    divergence::DivergencePolicy,
+    layer::{
+        embedding::EmbeddingLayer,
+        recurrent::{Cell, RecurrentLayer},
+        DenseLayer, Layer,
+    },
+    optimizer::Optimizer,
+    Network,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    MissingInput,
+    NoLayers,
+    // Layer 0 is the input, 1 the first layer added after it, ...
+    ZeroSizedLayer(usize),
+    MissingOptimizer,
+    MissingCostFunction,
+    // A sequence layer whose input is not a sequence, see `sequence_input`
+    NotASequence(usize),
+    // A sequence input without time steps
+    ZeroSteps,
+    // Embeddings look up indices, so they have to come first
+    EmbeddingNotFirst(usize),
+    // A graph node id that belongs to another graph
+    UnknownNode(usize),
+    // A graph built without outputs
+    NoOutputs,
+    // A graph merge without inputs
+    EmptyMerge(usize),
+    // A graph node whose input has the wrong size
+    WidthMismatch {
+        node: usize,
+        expected: usize,
+        found: usize,
+    },
+    // Attention whose step size can't be split evenly into heads
+    IndivisibleHeads {
+        node: usize,
+        dimension: usize,
+        heads: usize,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingInput => write!(f, "the input size of the network is not set"),
+            BuildError::NoLayers => write!(f, "the network has no layers"),
+            BuildError::ZeroSizedLayer(0) => write!(f, "the input size must not be zero"),
+            BuildError::ZeroSizedLayer(i) => write!(f, "layer {} must not have size zero", i),
+            BuildError::MissingOptimizer => write!(f, "no optimizer was set"),
+            BuildError::MissingCostFunction => write!(f, "no cost function was set"),
+            BuildError::NotASequence(i) => write!(
+                f,
+                "layer {} works on sequences, but its input is not a sequence",
+                i
+            ),
+            BuildError::ZeroSteps => write!(f, "the input sequence must have at least one step"),
+            BuildError::EmbeddingNotFirst(i) => write!(
+                f,
+                "layer {} is an embedding, which must be the first layer",
+                i
+            ),
+            BuildError::UnknownNode(i) => write!(f, "node {} is not part of the graph", i),
+            BuildError::NoOutputs => write!(f, "the graph has no outputs"),
+            BuildError::EmptyMerge(i) => write!(f, "the merge at node {} has no inputs", i),
+            BuildError::WidthMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "node {} expects inputs of size {}, but got {}",
+                node, expected, found
+            ),
+            BuildError::IndivisibleHeads {
+                node,
+                dimension,
+                heads,
+            } => write!(
+                f,
+                "node {} can't split {} values per step into {} heads",
+                node, dimension, heads
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+// Builds a network layer by layer, every layer is described by its output size
+// and the activation applied to its output:
+//
+//     Network::builder()
+//         .input(2)
+//         .dense(32, Relu)
+//         .dense(3, Linear)
+//         .optimizer(ADAM::default())
+//         .cost(QuadraticCost)
+//         .build()?
+//
+// Recurrent layers need a sequence as input, given by `sequence_input`, an
+// embedding or a recurrent layer that returns sequences.
+#[derive(Default)]
+pub struct NetworkBuilder {
+    input_size: Option<usize>,
+    steps: Option<usize>,
+    layers: Vec<LayerSpec>,
+    optimizer: Option<Box<dyn Optimizer>>,
+    cost_function: Option<Box<dyn CostFunction>>,
+    divergence_policy: DivergencePolicy,
+}
+
+enum LayerSpec {
+    Dense(usize, Box<dyn ActivationFunction>),
+    Recurrent {
+        cell: Cell,
+        hidden: usize,
+        activation: Box<dyn ActivationFunction>,
+        return_sequences: bool,
+    },
+    Embedding {
+        vocabulary_size: usize,
+        dimension: usize,
+    },
+}
+
+impl LayerSpec {
+    fn size(&self) -> usize {
+        match self {
+            LayerSpec::Dense(size, _) => *size,
+            LayerSpec::Recurrent { hidden, .. } => *hidden,
+            LayerSpec::Embedding {
+                vocabulary_size,
+                dimension,
+            } => *vocabulary_size.min(dimension),
+        }
+    }
+}
+
+impl NetworkBuilder {
+    pub fn new() -> NetworkBuilder {
+        NetworkBuilder::default()
+    }
+
+    pub fn input(mut self, size: usize) -> NetworkBuilder {
+        self.input_size = Some(size);
+        self.steps = None;
+        self
+    }
+
+    // Sequences of `steps` time steps with `features` values each, passed as
+    // rows of `steps * features` values
+    pub fn sequence_input(mut self, steps: usize, features: usize) -> NetworkBuilder {
+        self.input_size = Some(steps * features);
+        self.steps = Some(steps);
+        self
+    }
+
+    pub fn dense(
+        self,
+        size: usize,
+        activation: impl ActivationFunction + 'static,
+    ) -> NetworkBuilder {
+        self.dense_boxed(size, Box::new(activation))
+    }
+
+    pub fn dense_boxed(
+        mut self,
+        size: usize,
+        activation: Box<dyn ActivationFunction>,
+    ) -> NetworkBuilder {
+        self.layers.push(LayerSpec::Dense(size, activation));
+        self
+    }
+
+    // Looks up a vector of `dimension` values for each of the indices in the
+    // input, which have to be below `vocabulary_size`. Only allowed as the
+    // first layer, the vectors form a sequence with one step per index.
+    pub fn embedding(mut self, vocabulary_size: usize, dimension: usize) -> NetworkBuilder {
+        self.layers.push(LayerSpec::Embedding {
+            vocabulary_size,
+            dimension,
+        });
+        self
+    }
+
+    pub fn simple_rnn(self, hidden: usize, return_sequences: bool) -> NetworkBuilder {
+        self.recurrent(Cell::SimpleRnn, hidden, Box::new(Tanh), return_sequences)
+    }
+
+    pub fn lstm(self, hidden: usize, return_sequences: bool) -> NetworkBuilder {
+        self.recurrent(Cell::Lstm, hidden, Box::new(Tanh), return_sequences)
+    }
+
+    pub fn gru(self, hidden: usize, return_sequences: bool) -> NetworkBuilder {
+        self.recurrent(Cell::Gru, hidden, Box::new(Tanh), return_sequences)
+    }
+
+    // A recurrent layer with `hidden` units. It outputs the last hidden state,
+    // or the hidden states of all steps if `return_sequences` is set.
+    pub fn recurrent(
+        mut self,
+        cell: Cell,
+        hidden: usize,
+        activation: Box<dyn ActivationFunction>,
+        return_sequences: bool,
+    ) -> NetworkBuilder {
+        self.layers.push(LayerSpec::Recurrent {
+            cell,
+            hidden,
+            activation,
+            return_sequences,
+        });
+        self
+    }
+
+    pub fn optimizer(self, optimizer: impl Optimizer + 'static) -> NetworkBuilder {
+        self.optimizer_boxed(Box::new(optimizer))
+    }
+
+    pub fn optimizer_boxed(mut self, optimizer: Box<dyn Optimizer>) -> NetworkBuilder {
+        self.optimizer = Some(optimizer);
+        self
+    }
+
+    pub fn cost(self, cost_function: impl CostFunction + 'static) -> NetworkBuilder {
+        self.cost_boxed(Box::new(cost_function))
+    }
+
+    pub fn cost_boxed(mut self, cost_function: Box<dyn CostFunction>) -> NetworkBuilder {
+        self.cost_function = Some(cost_function);
+        self
+    }
+
+    pub fn divergence_policy(mut self, policy: DivergencePolicy) -> NetworkBuilder {
+        self.divergence_policy = policy;
+        self
+    }
+
+    // Checks the description and initializes the layers
+    pub fn build(self) -> Result<Network, BuildError> {
+        let input_size = self.input_size.ok_or(BuildError::MissingInput)?;
+
+        if self.layers.is_empty() {
+            return Err(BuildError::NoLayers);
+        }
+        if self.steps == Some(0) {
+            return Err(BuildError::ZeroSteps);
+        }
+
+        let sizes = std::iter::once(input_size)
+            .chain(self.layers.iter().map(LayerSpec::size))
+            .collect::<Vec<_>>();
+        if let Some(i) = sizes.iter().position(|&size| size == 0) {
+            return Err(BuildError::ZeroSizedLayer(i));
+        }
+
+        let optimizer = self.optimizer.ok_or(BuildError::MissingOptimizer)?;
+        let cost_function = self.cost_function.ok_or(BuildError::MissingCostFunction)?;
+
+        // the number of inputs of the next layer and, if they form a sequence,
+        // its number of steps
+        let mut width = input_size;
+        let mut steps = self.steps;
+
+        let mut layers: Vec<Box<dyn Layer>> = Vec::with_capacity(self.layers.len());
+        for (i, spec) in self.layers.into_iter().enumerate() {
+            let layer: Box<dyn Layer> = match spec {
+                LayerSpec::Dense(size, activation) => {
+                    steps = None;
+                    let mut layer = DenseLayer::new(width, activation);
+                    layer.initialize(width, size);
+                    Box::new(layer)
+                }
+                LayerSpec::Recurrent {
+                    cell,
+                    hidden,
+                    activation,
+                    return_sequences,
+                } => {
+                    let sequence_steps = steps.ok_or(BuildError::NotASequence(i + 1))?;
+                    if !return_sequences {
+                        steps = None;
+                    }
+                    let mut layer = RecurrentLayer::new(
+                        cell,
+                        sequence_steps,
+                        width / sequence_steps,
+                        hidden,
+                        activation,
+                        return_sequences,
+                    )
+                    .map_err(|_| BuildError::ZeroSteps)?;
+                    layer.initialize(layer.get_size(), layer.get_output_size());
+                    Box::new(layer)
+                }
+                LayerSpec::Embedding {
+                    vocabulary_size,
+                    dimension,
+                } => {
+                    if i > 0 {
+                        return Err(BuildError::EmbeddingNotFirst(i + 1));
+                    }
+                    steps = Some(width);
+                    let mut layer = EmbeddingLayer::new(vocabulary_size, dimension, width);
+                    layer.initialize(layer.get_size(), layer.get_output_size());
+                    Box::new(layer)
+                }
+            };
+
+            width = layer.get_output_size();
+            layers.push(layer);
+        }
+
+        let mut network = Network::from_layers(layers, optimizer, cost_function);
+        network.set_divergence_policy(self.divergence_policy);
+
+        Ok(network)
+    }
+}
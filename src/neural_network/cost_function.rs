@@ -1,40 +1,368 @@
-use ndarray::{Array1, Array2};
+use ndarray::{Array1, Array2, Axis, Zip};
 
-pub trait CostFunction {
-    fn f(&self, a: &Array1<f64>, expected: &Array1<f64>) -> f64;
-    fn d(&self, a: &Array1<f64>, expected: &Array1<f64>) -> Array1<f64>;
+use crate::error::{Error, Result};
+
+// Cost functions operate on whole batches, where every row of `a` and
+// `expected` is one sample
+pub trait CostFunction: Send + Sync {
+    // The cost of every sample in the batch
+    fn f(&self, a: &Array2<f64>, expected: &Array2<f64>) -> Array1<f64>;
+
+    // The derivative of the cost of every sample with respect to `a`
+    fn d(&self, a: &Array2<f64>, expected: &Array2<f64>) -> Array2<f64>;
+
+    // The summed cost of all samples. Together with `d_into` this is what a
+    // training step uses, so neither may allocate.
+    fn total(&self, a: &Array2<f64>, expected: &Array2<f64>) -> f64;
+
+    // Writes the derivative scaled by `scale` into `out`, which has the shape
+    // of `a`
+    fn d_into(&self, a: &Array2<f64>, expected: &Array2<f64>, scale: f64, out: &mut Array2<f64>);
+}
+
+// How the costs of the individual samples are combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduction {
+    Mean,
+    Sum,
+    None,
+}
+
+// The cost of a batch after a `Reduction`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReducedCost {
+    // The result of `Mean` and `Sum`
+    Total(f64),
+    // The result of `None`, one cost per sample
+    PerSample(Array1<f64>),
+}
+
+impl ReducedCost {
+    // The single value of `Mean` and `Sum`, the sum for `None`
+    pub fn total(&self) -> f64 {
+        match self {
+            ReducedCost::Total(total) => *total,
+            ReducedCost::PerSample(costs) => costs.sum(),
+        }
+    }
 }
 
 impl dyn CostFunction {
+    // The mean cost over the batch
     pub fn cost(&self, a: &Array2<f64>, expected: &Array2<f64>) -> f64 {
-        let mut cost = 0.0;
-        for (a, expected) in a.outer_iter().zip(expected.outer_iter()) {
-            cost += self.f(&a.to_owned(), &expected.to_owned());
+        match a.nrows() {
+            0 => 0.0,
+            samples => self.total(a, expected) / samples as f64,
         }
-        cost / a.nrows() as f64
     }
 
     pub fn cost_derivative(&self, a: &Array2<f64>, expected: &Array2<f64>) -> Array2<f64> {
-        let mut cost_derivative = Array2::zeros(a.raw_dim());
+        self.d(a, expected)
+    }
 
-        for i in 0..a.ncols() {
-            cost_derivative
-                .column_mut(i)
-                .assign(&(self.d(&a.column(i).to_owned(), &expected.column(i).to_owned())));
+    // The cost with optional per-sample weights. A weighted mean is
+    // normalized by the sum of the weights.
+    pub fn cost_with(
+        &self,
+        a: &Array2<f64>,
+        expected: &Array2<f64>,
+        weights: Option<&Array1<f64>>,
+        reduction: Reduction,
+    ) -> Result<ReducedCost> {
+        check_weights(a, expected, weights)?;
+
+        let mut costs = self.f(a, expected);
+        if let Some(weights) = weights {
+            costs *= weights;
         }
 
-        cost_derivative
+        Ok(match reduction {
+            Reduction::Mean => ReducedCost::Total(costs.sum() / total_weight(a, weights)),
+            Reduction::Sum => ReducedCost::Total(costs.sum()),
+            Reduction::None => ReducedCost::PerSample(costs),
+        })
     }
+
+    // The derivative matching `cost_with`. For `None` every row is the
+    // derivative of the weighted cost of that sample.
+    pub fn cost_derivative_with(
+        &self,
+        a: &Array2<f64>,
+        expected: &Array2<f64>,
+        weights: Option<&Array1<f64>>,
+        reduction: Reduction,
+    ) -> Result<Array2<f64>> {
+        check_weights(a, expected, weights)?;
+
+        let mut derivative = self.d(a, expected);
+        if let Some(weights) = weights {
+            derivative *= &weights.view().insert_axis(Axis(1));
+        }
+
+        if reduction == Reduction::Mean {
+            derivative /= total_weight(a, weights);
+        }
+
+        Ok(derivative)
+    }
+}
+
+// The expected values must match the prediction, and there must be one
+// non-negative weight per sample with a positive sum
+fn check_weights(
+    a: &Array2<f64>,
+    expected: &Array2<f64>,
+    weights: Option<&Array1<f64>>,
+) -> Result<()> {
+    if a.dim() != expected.dim() {
+        return Err(Error::InvalidData(format!(
+            "the prediction has shape {:?}, but the expected values {:?}",
+            a.dim(),
+            expected.dim()
+        )));
+    }
+
+    let Some(weights) = weights else {
+        return Ok(());
+    };
+    if weights.len() != a.nrows() {
+        return Err(Error::InvalidData(format!(
+            "{} sample weights for {} samples",
+            weights.len(),
+            a.nrows()
+        )));
+    }
+    if weights
+        .iter()
+        .any(|weight| !weight.is_finite() || *weight < 0.0)
+        || weights.sum() <= 0.0
+    {
+        return Err(Error::InvalidData(String::from(
+            "sample weights must be finite, non-negative and not all zero",
+        )));
+    }
+
+    Ok(())
+}
+
+fn total_weight(a: &Array2<f64>, weights: Option<&Array1<f64>>) -> f64 {
+    match weights {
+        Some(weights) => weights.sum(),
+        None => a.nrows() as f64,
+    }
+}
+
+// Sums an element-wise loss of the residual `a - expected` over the outputs of every sample
+fn elementwise_f(
+    a: &Array2<f64>,
+    expected: &Array2<f64>,
+    loss: impl Fn(f64) -> f64,
+) -> Array1<f64> {
+    Zip::from(a.rows())
+        .and(expected.rows())
+        .map_collect(|a, expected| {
+            a.iter()
+                .zip(expected)
+                .map(|(a, expected)| loss(a - expected))
+                .sum::<f64>()
+        })
+}
+
+fn elementwise_total(a: &Array2<f64>, expected: &Array2<f64>, loss: impl Fn(f64) -> f64) -> f64 {
+    Zip::from(a)
+        .and(expected)
+        .fold(0.0, |total, &a, &expected| total + loss(a - expected))
+}
+
+fn elementwise_d(a: &Array2<f64>, expected: &Array2<f64>, d: impl Fn(f64) -> f64) -> Array2<f64> {
+    Zip::from(a)
+        .and(expected)
+        .map_collect(|&a, &expected| d(a - expected))
+}
+
+fn elementwise_d_into(
+    a: &Array2<f64>,
+    expected: &Array2<f64>,
+    scale: f64,
+    out: &mut Array2<f64>,
+    d: impl Fn(f64) -> f64,
+) {
+    Zip::from(out)
+        .and(a)
+        .and(expected)
+        .for_each(|out, &a, &expected| *out = scale * d(a - expected));
+}
+
+// Implements `CostFunction` for a cost described by an element-wise loss `f`
+// and its derivative `d`, both functions of the residual `a - expected`
+macro_rules! elementwise_cost {
+    ($cost:ty, |$self_:ident, $u:ident| $f:expr, $d:expr) => {
+        impl CostFunction for $cost {
+            fn f(&$self_, a: &Array2<f64>, expected: &Array2<f64>) -> Array1<f64> {
+                elementwise_f(a, expected, |$u| $f)
+            }
+
+            fn d(&$self_, a: &Array2<f64>, expected: &Array2<f64>) -> Array2<f64> {
+                elementwise_d(a, expected, |$u| $d)
+            }
+
+            fn total(&$self_, a: &Array2<f64>, expected: &Array2<f64>) -> f64 {
+                elementwise_total(a, expected, |$u| $f)
+            }
+
+            fn d_into(
+                &$self_,
+                a: &Array2<f64>,
+                expected: &Array2<f64>,
+                scale: f64,
+                out: &mut Array2<f64>,
+            ) {
+                elementwise_d_into(a, expected, scale, out, |$u| $d)
+            }
+        }
+    };
 }
 
 pub struct QuadraticCost;
 
-impl CostFunction for QuadraticCost {
-    fn f(&self, a: &Array1<f64>, expected: &Array1<f64>) -> f64 {
-        0.5 * (a - expected).mapv(|x| x.powi(2)).sum()
+elementwise_cost!(QuadraticCost, |self, u| 0.5 * u * u, u);
+
+// Mean absolute error (L1 loss)
+pub struct AbsoluteCost;
+
+elementwise_cost!(AbsoluteCost, |self, u| u.abs(), signum(u));
+
+// Quadratic for residuals up to `delta`, linear beyond that
+pub struct HuberCost {
+    delta: f64,
+}
+
+impl HuberCost {
+    // `delta` must be positive
+    pub fn new(delta: f64) -> Result<HuberCost> {
+        check_parameter("huber", "delta", delta, delta > 0.0, "positive")?;
+        Ok(HuberCost { delta })
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+}
+
+elementwise_cost!(
+    HuberCost,
+    |self, u| if u.abs() <= self.delta {
+        0.5 * u * u
+    } else {
+        self.delta * (u.abs() - 0.5 * self.delta)
+    },
+    u.clamp(-self.delta, self.delta)
+);
+
+// Huber loss divided by `beta`, so the linear part always has slope one
+pub struct SmoothL1Cost {
+    beta: f64,
+}
+
+impl SmoothL1Cost {
+    // `beta` must be finite and not negative, zero gives the absolute cost
+    pub fn new(beta: f64) -> Result<SmoothL1Cost> {
+        let valid = beta >= 0.0 && beta.is_finite();
+        check_parameter("smooth_l1", "beta", beta, valid, "finite and at least 0")?;
+        Ok(SmoothL1Cost { beta })
     }
 
-    fn d(&self, a: &Array1<f64>, expected: &Array1<f64>) -> Array1<f64> {
-        a - expected
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+}
+
+elementwise_cost!(
+    SmoothL1Cost,
+    |self, u| if u.abs() < self.beta {
+        0.5 * u * u / self.beta
+    } else {
+        u.abs() - 0.5 * self.beta
+    },
+    if u.abs() < self.beta {
+        u / self.beta
+    } else {
+        signum(u)
+    }
+);
+
+// log(cosh(u)), behaves like the quadratic cost for small and like the
+// absolute cost for large residuals
+pub struct LogCoshCost;
+
+elementwise_cost!(
+    LogCoshCost,
+    |self, u| u.abs() + (-2.0 * u.abs()).exp().ln_1p() - std::f64::consts::LN_2,
+    u.tanh()
+);
+
+// Pinball loss, fits the `tau`-quantile of the expected values, e.g. 0.5 for
+// the median or 0.05 and 0.95 for a 90% prediction interval
+pub struct QuantileCost {
+    tau: f64,
+}
+
+impl QuantileCost {
+    // `tau` must be between 0 and 1
+    pub fn new(tau: f64) -> Result<QuantileCost> {
+        check_parameter(
+            "quantile",
+            "tau",
+            tau,
+            tau > 0.0 && tau < 1.0,
+            "between 0 and 1",
+        )?;
+        Ok(QuantileCost { tau })
+    }
+
+    pub fn tau(&self) -> f64 {
+        self.tau
+    }
+}
+
+elementwise_cost!(
+    QuantileCost,
+    |self, u| if u > 0.0 {
+        (1.0 - self.tau) * u
+    } else {
+        -self.tau * u
+    },
+    if u > 0.0 {
+        1.0 - self.tau
+    } else if u < 0.0 {
+        -self.tau
+    } else {
+        0.0
+    }
+);
+
+// NaN fails every check
+fn check_parameter(
+    cost: &str,
+    parameter: &str,
+    value: f64,
+    valid: bool,
+    expected: &str,
+) -> Result<()> {
+    if valid {
+        return Ok(());
+    }
+    Err(Error::InvalidData(format!(
+        "the {} of the {} cost must be {}, found {}",
+        parameter, cost, expected, value
+    )))
+}
+
+// Like f64::signum, but zero for zero
+fn signum(u: f64) -> f64 {
+    if u == 0.0 {
+        0.0
+    } else {
+        u.signum()
     }
 }
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use itertools::izip;
+use ndarray::{s, Array2};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    layer::Layer,
+    optimizer::{row_ranges, Optimizer, OptimizerState},
+};
+use crate::error::{Error, Result};
+
+// What to do when a training step produces a non-finite cost, gradient or weight
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergencePolicy {
+    // Stop training with `Error::Diverged`
+    #[default]
+    Abort,
+    // Discard the step and continue with the next batch. If the previous step
+    // led to the non-finite values, it is discarded as well.
+    SkipStep,
+    // Like `SkipStep`, and halve the learning rate
+    Rollback,
+}
+
+// The quantity that became NaN or infinite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    Cost,
+    Gradients,
+    Weights,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::Cost => write!(f, "cost"),
+            Divergence::Gradients => write!(f, "gradients"),
+            Divergence::Weights => write!(f, "weights"),
+        }
+    }
+}
+
+// The result of a single training step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Applied,
+    Skipped(Divergence),
+    // The learning rate has been halved
+    RolledBack(Divergence),
+}
+
+// Applies the optimizer update of a training step and handles non-finite
+// values according to the policy
+#[derive(Default)]
+pub(crate) struct DivergenceGuard {
+    pub(crate) policy: DivergencePolicy,
+    checkpoint: Checkpoint,
+}
+
+impl DivergenceGuard {
+    pub(crate) fn update(
+        &mut self,
+        layers: &mut [Box<dyn Layer>],
+        optimizer: &mut dyn Optimizer,
+        cost: f64,
+        nabla_bs: &[Array2<f64>],
+        nabla_ws: &[Array2<f64>],
+        sparse_rows: &[Option<Vec<usize>>],
+    ) -> Result<Step> {
+        if !cost.is_finite() {
+            return self.diverged(Divergence::Cost, layers, optimizer);
+        }
+        if !all_finite(nabla_ws.iter().chain(nabla_bs)) {
+            return self.diverged(Divergence::Gradients, layers, optimizer);
+        }
+
+        // the current weights produced a finite cost and gradients
+        if self.policy != DivergencePolicy::Abort {
+            self.checkpoint
+                .save(layers, optimizer.state_mut(), sparse_rows);
+        }
+
+        optimizer.pre_update();
+        optimizer.update_params(layers, nabla_bs, nabla_ws, sparse_rows);
+        optimizer.post_update();
+
+        let parameters = layers
+            .iter()
+            .flat_map(|layer| [layer.get_weights(), layer.get_bias()]);
+        if !all_finite(parameters) {
+            return self.diverged(Divergence::Weights, layers, optimizer);
+        }
+
+        Ok(Step::Applied)
+    }
+
+    fn diverged(
+        &self,
+        divergence: Divergence,
+        layers: &mut [Box<dyn Layer>],
+        optimizer: &mut dyn Optimizer,
+    ) -> Result<Step> {
+        match self.policy {
+            DivergencePolicy::Abort => Err(Error::Diverged(divergence)),
+            DivergencePolicy::SkipStep => {
+                // a non-finite cost or gradient can come from a huge but
+                // finite update in the previous step, which is undone too
+                self.checkpoint.restore(layers, optimizer.state_mut());
+                Ok(Step::Skipped(divergence))
+            }
+            DivergencePolicy::Rollback => {
+                self.checkpoint.restore(layers, optimizer.state_mut());
+                optimizer.scale_learning_rate(0.5);
+                Ok(Step::RolledBack(divergence))
+            }
+        }
+    }
+}
+
+// Prints what happened to a step that wasn't applied
+pub(crate) fn log_step(epoch: i32, step: Step) {
+    match step {
+        Step::Applied => {}
+        Step::Skipped(divergence) => {
+            println!("Epoch: {}, non-finite {}, skipped step", epoch, divergence)
+        }
+        Step::RolledBack(divergence) => println!(
+            "Epoch: {}, non-finite {}, rolled back and halved learning rate",
+            epoch, divergence
+        ),
+    }
+}
+
+// Copy of the parameters and the optimizer state after the last good step.
+// The buffers are reused, so saving only allocates once. Of layers with sparse
+// gradients only the rows the next update changes are copied.
+#[derive(Default)]
+struct Checkpoint {
+    weights: Vec<Array2<f64>>,
+    biases: Vec<Array2<f64>>,
+    iteration: usize,
+    optimizer_weights: Vec<Array2<f64>>,
+    optimizer_biases: Vec<Array2<f64>>,
+    sparse_rows: Vec<Option<Vec<usize>>>,
+}
+
+impl Checkpoint {
+    fn save(
+        &mut self,
+        layers: &[Box<dyn Layer>],
+        optimizer: OptimizerState,
+        sparse_rows: &[Option<Vec<usize>>],
+    ) {
+        self.sparse_rows.resize(sparse_rows.len(), None);
+        self.sparse_rows.clone_from_slice(sparse_rows);
+        let rows = |layer: usize| self.sparse_rows[layer].as_deref();
+
+        self.weights.resize_with(layers.len(), Default::default);
+        self.biases.resize_with(layers.len(), Default::default);
+        for (i, (layer, weights, biases)) in
+            izip!(layers, &mut self.weights, &mut self.biases).enumerate()
+        {
+            copy_into(layer.get_weights(), weights, rows(i));
+            copy_into(layer.get_bias(), biases, None);
+        }
+
+        self.iteration = *optimizer.iteration;
+        let count = |sets: &[&mut [Array2<f64>]]| sets.iter().map(|set| set.len()).sum();
+        self.optimizer_weights
+            .resize_with(count(&optimizer.weights), Default::default);
+        let weights = optimizer
+            .weights
+            .iter()
+            .flat_map(|set| set.iter().enumerate());
+        for ((layer, source), target) in weights.zip(&mut self.optimizer_weights) {
+            copy_into(source, target, rows(layer));
+        }
+        self.optimizer_biases
+            .resize_with(count(&optimizer.biases), Default::default);
+        let biases = optimizer.biases.iter().flat_map(|set| set.iter());
+        for (source, target) in biases.zip(&mut self.optimizer_biases) {
+            copy_into(source, target, None);
+        }
+    }
+
+    fn restore(&self, layers: &mut [Box<dyn Layer>], optimizer: OptimizerState) {
+        // nothing to undo before the first good step
+        if self.weights.is_empty() {
+            return;
+        }
+        let rows = |layer: usize| self.sparse_rows[layer].as_deref();
+
+        for (i, (layer, weights, biases)) in izip!(layers, &self.weights, &self.biases).enumerate()
+        {
+            copy_rows(weights, layer.get_weights_mut(), rows(i));
+            layer.get_bias_mut().assign(biases);
+        }
+
+        *optimizer.iteration = self.iteration;
+        let weights = optimizer
+            .weights
+            .into_iter()
+            .flat_map(|set| set.iter_mut().enumerate());
+        for ((layer, target), source) in weights.zip(&self.optimizer_weights) {
+            copy_rows(source, target, rows(layer));
+        }
+        let biases = optimizer.biases.into_iter().flatten();
+        for (target, source) in biases.zip(&self.optimizer_biases) {
+            target.assign(source);
+        }
+    }
+}
+
+// Copies `source`, only `sparse_rows` of it if `target` already has its shape
+fn copy_into(source: &Array2<f64>, target: &mut Array2<f64>, sparse_rows: Option<&[usize]>) {
+    if target.dim() == source.dim() {
+        copy_rows(source, target, sparse_rows);
+    } else {
+        *target = source.clone();
+    }
+}
+
+fn copy_rows(source: &Array2<f64>, target: &mut Array2<f64>, sparse_rows: Option<&[usize]>) {
+    for rows in row_ranges(sparse_rows, source.nrows()) {
+        target
+            .slice_mut(s![rows.clone(), ..])
+            .assign(&source.slice(s![rows, ..]));
+    }
+}
+
+fn all_finite<'a>(arrays: impl IntoIterator<Item = &'a Array2<f64>>) -> bool {
+    arrays
+        .into_iter()
+        .all(|array| array.iter().all(|value| value.is_finite()))
+}
@@ -0,0 +1,162 @@
+use ndarray::Array2;
+
+use super::{graph::Graph, layer::Layer, Gradients, Network};
+use crate::error::Result;
+
+// Relative error between the analytic and the numerical gradients of one layer
+#[derive(Debug, Clone, Copy)]
+pub struct GradientError {
+    pub weights: f64,
+    pub biases: f64,
+}
+
+impl GradientError {
+    pub fn max(&self) -> f64 {
+        self.weights.max(self.biases)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Parameter {
+    Weights,
+    Biases,
+}
+
+// Compares the gradients computed by `backprop` against central finite
+// differences of the cost, returns the relative error for every layer
+#[allow(non_snake_case)]
+pub fn check_gradients(
+    network: &mut Network,
+    X: &Array2<f64>,
+    y: &Array2<f64>,
+    epsilon: f64,
+) -> Result<Vec<GradientError>> {
+    let gradients = network.backprop(X, y)?;
+    compare(&mut NetworkBatch { network, X, y }, gradients, epsilon)
+}
+
+// The same check for a graph and a batch of all its inputs and targets
+pub fn check_graph_gradients(
+    graph: &mut Graph,
+    inputs: &[Array2<f64>],
+    targets: &[Array2<f64>],
+    epsilon: f64,
+) -> Result<Vec<GradientError>> {
+    let gradients = graph.backprop(inputs, targets)?;
+    compare(
+        &mut GraphBatch {
+            graph,
+            inputs,
+            targets,
+        },
+        gradients,
+        epsilon,
+    )
+}
+
+// A model together with the batch its gradients are checked on
+trait Batch {
+    fn parameter_mut(&mut self, layer: usize, parameter: Parameter) -> &mut Array2<f64>;
+    fn cost(&self) -> Result<f64>;
+}
+
+#[allow(non_snake_case)]
+struct NetworkBatch<'a> {
+    network: &'a mut Network,
+    X: &'a Array2<f64>,
+    y: &'a Array2<f64>,
+}
+
+impl Batch for NetworkBatch<'_> {
+    fn parameter_mut(&mut self, layer: usize, parameter: Parameter) -> &mut Array2<f64> {
+        parameter.of(self.network.layers[layer].as_mut())
+    }
+
+    fn cost(&self) -> Result<f64> {
+        let prediction = self.network.predict(self.X)?;
+        Ok(self.network.cost_function.cost(&prediction, self.y))
+    }
+}
+
+struct GraphBatch<'a> {
+    graph: &'a mut Graph,
+    inputs: &'a [Array2<f64>],
+    targets: &'a [Array2<f64>],
+}
+
+impl Batch for GraphBatch<'_> {
+    fn parameter_mut(&mut self, layer: usize, parameter: Parameter) -> &mut Array2<f64> {
+        parameter.of(self.graph.layers_mut()[layer].as_mut())
+    }
+
+    fn cost(&self) -> Result<f64> {
+        self.graph.cost(self.inputs, self.targets)
+    }
+}
+
+impl Parameter {
+    fn of(self, layer: &mut dyn Layer) -> &mut Array2<f64> {
+        match self {
+            Parameter::Weights => layer.get_weights_mut(),
+            Parameter::Biases => layer.get_bias_mut(),
+        }
+    }
+}
+
+fn compare(
+    batch: &mut dyn Batch,
+    (nabla_bs, nabla_ws): Gradients,
+    epsilon: f64,
+) -> Result<Vec<GradientError>> {
+    (0..nabla_ws.len())
+        .map(|i| {
+            Ok(GradientError {
+                weights: relative_error(
+                    &nabla_ws[i],
+                    &numerical_gradient(batch, epsilon, i, Parameter::Weights)?,
+                ),
+                biases: relative_error(
+                    &nabla_bs[i],
+                    &numerical_gradient(batch, epsilon, i, Parameter::Biases)?,
+                ),
+            })
+        })
+        .collect()
+}
+
+fn numerical_gradient(
+    batch: &mut dyn Batch,
+    epsilon: f64,
+    layer: usize,
+    parameter: Parameter,
+) -> Result<Array2<f64>> {
+    let shape = batch.parameter_mut(layer, parameter).dim();
+    let mut gradient = Array2::zeros(shape);
+
+    for index in ndarray::indices(shape) {
+        let original = batch.parameter_mut(layer, parameter)[index];
+
+        batch.parameter_mut(layer, parameter)[index] = original + epsilon;
+        let cost_plus = batch.cost()?;
+
+        batch.parameter_mut(layer, parameter)[index] = original - epsilon;
+        let cost_minus = batch.cost()?;
+
+        batch.parameter_mut(layer, parameter)[index] = original;
+
+        gradient[index] = (cost_plus - cost_minus) / (2.0 * epsilon);
+    }
+
+    Ok(gradient)
+}
+
+// |a - b| / (|a| + |b|), using the euclidean norm over all entries. The
+// denominator has a lower bound, so gradients that are zero up to rounding
+// errors don't show up as a large relative error.
+fn relative_error(analytic: &Array2<f64>, numerical: &Array2<f64>) -> f64 {
+    let norm = |x: &Array2<f64>| x.mapv(|x| x * x).sum().sqrt();
+
+    let denominator = (norm(analytic) + norm(numerical)).max(1e-6);
+
+    norm(&(analytic - numerical)) / denominator
+}
@@ -0,0 +1,642 @@
+use ndarray::{concatenate, s, Array2, ArrayView2, Axis};
+
+mod transformer;
+
+use super::{
+    activation_function::ActivationFunction,
+    builder::BuildError,
+    check_columns,
+    cost_function::CostFunction,
+    divergence::{log_step, DivergenceGuard, DivergencePolicy, Step},
+    layer::{DenseLayer, Layer},
+    optimizer::Optimizer,
+    summary::{ModelSummary, SummaryRow},
+    Gradients, Summary,
+};
+use crate::{
+    dataset::Dataset,
+    error::{Error, Result},
+};
+
+// A node of a `Graph`, handed out by the `GraphBuilder`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+// How the outputs of several nodes are combined into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Merge {
+    // Element-wise sum, all inputs need the same size
+    Add,
+    // The inputs side by side
+    Concatenate,
+    // Element-wise product, all inputs need the same size
+    Multiply,
+}
+
+enum Node {
+    // The n-th input of the graph
+    Input(usize),
+    Layer { layer: usize, input: NodeId },
+    Merge { merge: Merge, inputs: Vec<NodeId> },
+}
+
+// A model whose layers form a directed acyclic graph instead of a chain, so
+// layers can branch and merge again, as in residual blocks, and the model can
+// have several inputs and outputs:
+//
+//     let mut graph = Graph::builder();
+//     let x = graph.input(2);
+//     let h = graph.dense(x, 16, Relu);
+//     let r = graph.dense(h, 16, Relu);
+//     let h = graph.add(&[h, r]);
+//     let y = graph.dense(h, 1, Linear);
+//     let model = graph.build(&[y], ADAM::default(), QuadraticCost)?;
+//
+// Inputs and outputs are passed in the order they were created and listed.
+// Every output is compared with its target by the cost function, the cost of
+// the model is the sum over the outputs.
+pub struct Graph {
+    nodes: Vec<Node>,
+    widths: Vec<usize>,
+    outputs: Vec<NodeId>,
+    layers: Vec<Box<dyn Layer>>,
+    optimizer: Box<dyn Optimizer>,
+    cost_function: Box<dyn CostFunction>,
+    guard: DivergenceGuard,
+}
+
+// The values computed by a forward pass, per node and per layer
+struct Forward {
+    activations: Vec<Array2<f64>>,
+    zs: Vec<Array2<f64>>,
+}
+
+impl Graph {
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::new()
+    }
+
+    pub fn layers(&self) -> &[Box<dyn Layer>] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [Box<dyn Layer>] {
+        &mut self.layers
+    }
+
+    pub fn divergence_policy(&self) -> DivergencePolicy {
+        self.guard.policy
+    }
+
+    pub fn set_divergence_policy(&mut self, policy: DivergencePolicy) {
+        self.guard.policy = policy;
+    }
+
+    // Predicts every output of the graph
+    pub fn predict(&self, inputs: &[Array2<f64>]) -> Result<Vec<Array2<f64>>> {
+        let forward = self.forward(inputs)?;
+        Ok(self.collect_outputs(forward.activations))
+    }
+
+    // The summed cost of all outputs
+    pub fn cost(&self, inputs: &[Array2<f64>], targets: &[Array2<f64>]) -> Result<f64> {
+        self.check_targets(inputs, targets)?;
+        let forward = self.forward(inputs)?;
+        Ok(self.output_cost(&forward, targets))
+    }
+
+    // Calculates the gradients of the biases and weights of every layer
+    pub fn backprop(&self, inputs: &[Array2<f64>], targets: &[Array2<f64>]) -> Result<Gradients> {
+        self.check_targets(inputs, targets)?;
+        let forward = self.forward(inputs)?;
+        let (gradients, _) = self.backward(&forward, targets);
+        Ok(gradients)
+    }
+
+    // Trains the graph using a minibatch, see `Network::train_minibatch`
+    pub fn train_minibatch(
+        &mut self,
+        inputs: &[Array2<f64>],
+        targets: &[Array2<f64>],
+    ) -> Result<Step> {
+        self.check_targets(inputs, targets)?;
+        let forward = self.forward(inputs)?;
+        let cost = self.output_cost(&forward, targets);
+        let ((nabla_bs, nabla_ws), sparse_rows) = self.backward(&forward, targets);
+
+        self.guard.update(
+            &mut self.layers,
+            self.optimizer.as_mut(),
+            cost,
+            &nabla_bs,
+            &nabla_ws,
+            &sparse_rows,
+        )
+    }
+
+    // Trains a graph with one input and one output on a dataset, records the
+    // cost like `Network::train_and_log`
+    pub fn train_and_log(
+        &mut self,
+        data: &Dataset,
+        batch_size: usize,
+        verification_samples: usize,
+        epochs: i32,
+    ) -> Result<Vec<(i32, f64)>> {
+        let mut cost_history = Vec::new();
+
+        for epoch in 0..epochs {
+            let (x, y) = data.get_batch(batch_size)?;
+            let step = self.train_minibatch(&[x], &[y])?;
+            log_step(epoch, step);
+
+            if epoch % (epochs / 100 + 1) == 0 {
+                let (x, y) = data.get_batch(verification_samples)?;
+                let cost = self.cost(&[x], &[y])?;
+                cost_history.push((epoch, cost));
+
+                println!("Epoch: {}, Cost: {:.8}", epoch, cost);
+            }
+        }
+
+        Ok(cost_history)
+    }
+
+    fn forward(&self, inputs: &[Array2<f64>]) -> Result<Forward> {
+        self.check_inputs(inputs)?;
+
+        let mut activations: Vec<Array2<f64>> = Vec::with_capacity(self.nodes.len());
+        let mut zs = vec![Array2::zeros((0, 0)); self.layers.len()];
+
+        for node in &self.nodes {
+            let activation = match node {
+                Node::Input(i) => inputs[*i].clone(),
+                Node::Layer { layer, input } => {
+                    let mut output = Array2::zeros((0, 0));
+                    self.layers[*layer].forward_into(
+                        &activations[input.0],
+                        &mut zs[*layer],
+                        &mut output,
+                    );
+                    output
+                }
+                Node::Merge { merge, inputs } => {
+                    let inputs = inputs
+                        .iter()
+                        .map(|input| activations[input.0].view())
+                        .collect::<Vec<_>>();
+                    merge.forward(&inputs)
+                }
+            };
+            activations.push(activation);
+        }
+
+        Ok(Forward { activations, zs })
+    }
+
+    // Propagates the derivative of the cost from the outputs back through the
+    // nodes, every node sums the derivatives of all nodes using it
+    fn backward(
+        &self,
+        forward: &Forward,
+        targets: &[Array2<f64>],
+    ) -> (Gradients, Vec<Option<Vec<usize>>>) {
+        let Forward { activations, zs } = forward;
+        let batch_size = targets[0].nrows();
+
+        let mut deltas = self
+            .widths
+            .iter()
+            .map(|&width| Array2::zeros((batch_size, width)))
+            .collect::<Vec<_>>();
+        let mut nabla_bs = self
+            .layers
+            .iter()
+            .map(|layer| Array2::zeros(layer.get_bias().dim()))
+            .collect::<Vec<_>>();
+        let mut nabla_ws = self
+            .layers
+            .iter()
+            .map(|layer| Array2::zeros(layer.get_weights().dim()))
+            .collect::<Vec<_>>();
+        let mut sparse_rows = vec![None; self.layers.len()];
+
+        // the derivative of the cost, averaged over the batch
+        for (output, target) in self.outputs.iter().zip(targets) {
+            let mut delta = Array2::zeros(target.dim());
+            self.cost_function.d_into(
+                &activations[output.0],
+                target,
+                1.0 / batch_size as f64,
+                &mut delta,
+            );
+            deltas[output.0] += &delta;
+        }
+
+        for (i, node) in self.nodes.iter().enumerate().rev() {
+            let mut delta = std::mem::take(&mut deltas[i]);
+
+            match node {
+                Node::Input(_) => {}
+                Node::Layer { layer, input } => {
+                    let input_activation = &activations[input.0];
+                    let mut input_delta = match self.nodes[input.0] {
+                        Node::Input(_) => None,
+                        _ => Some(Array2::zeros((0, 0))),
+                    };
+
+                    self.layers[*layer].backward_into(
+                        input_activation,
+                        &zs[*layer],
+                        &mut delta,
+                        &mut nabla_ws[*layer],
+                        &mut nabla_bs[*layer],
+                        input_delta.as_mut(),
+                    );
+                    let mut rows = Vec::new();
+                    sparse_rows[*layer] = self.layers[*layer]
+                        .sparse_rows(input_activation, &mut rows)
+                        .then_some(rows);
+
+                    if let Some(input_delta) = input_delta {
+                        deltas[input.0] += &input_delta;
+                    }
+                }
+                Node::Merge { merge, inputs } => {
+                    let input_activations = inputs
+                        .iter()
+                        .map(|input| activations[input.0].view())
+                        .collect::<Vec<_>>();
+
+                    for (k, input) in inputs.iter().enumerate() {
+                        let input_delta = merge.backward(&input_activations, k, &delta);
+                        deltas[input.0] += &input_delta;
+                    }
+                }
+            }
+        }
+
+        ((nabla_bs, nabla_ws), sparse_rows)
+    }
+
+    fn output_cost(&self, forward: &Forward, targets: &[Array2<f64>]) -> f64 {
+        self.outputs
+            .iter()
+            .zip(targets)
+            .map(|(output, target)| {
+                self.cost_function
+                    .cost(&forward.activations[output.0], target)
+            })
+            .sum()
+    }
+
+    fn collect_outputs(&self, mut activations: Vec<Array2<f64>>) -> Vec<Array2<f64>> {
+        self.outputs
+            .iter()
+            .map(|output| std::mem::take(&mut activations[output.0]))
+            .collect()
+    }
+
+    fn input_nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| matches!(node, Node::Input(_)))
+            .map(|(i, _)| i)
+    }
+
+    fn check_inputs(&self, inputs: &[Array2<f64>]) -> Result<()> {
+        let input_nodes = self.input_nodes().collect::<Vec<_>>();
+        if inputs.len() != input_nodes.len() {
+            return Err(Error::InvalidData(format!(
+                "the graph has {} inputs, but {} were given",
+                input_nodes.len(),
+                inputs.len()
+            )));
+        }
+
+        for (input, &node) in inputs.iter().zip(&input_nodes) {
+            check_columns("input", input, self.widths[node])?;
+            if input.nrows() != inputs[0].nrows() {
+                return Err(Error::InvalidData(String::from(
+                    "all inputs need the same number of rows",
+                )));
+            }
+        }
+
+        // only layers reading an input directly may reject some values, see
+        // `Layer::needs_raw_input`
+        for node in &self.nodes {
+            if let Node::Layer { layer, input } = node {
+                if let Node::Input(i) = self.nodes[input.0] {
+                    self.layers[*layer].check_input(&inputs[i])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_targets(&self, inputs: &[Array2<f64>], targets: &[Array2<f64>]) -> Result<()> {
+        if targets.len() != self.outputs.len() {
+            return Err(Error::InvalidData(format!(
+                "the graph has {} outputs, but {} targets were given",
+                self.outputs.len(),
+                targets.len()
+            )));
+        }
+
+        for (target, output) in targets.iter().zip(&self.outputs) {
+            check_columns("expected output", target, self.widths[output.0])?;
+            if inputs.first().map(|input| input.nrows()) != Some(target.nrows()) {
+                return Err(Error::InvalidData(String::from(
+                    "inputs and expected outputs need the same number of rows",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Merge {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Merge::Add => "add",
+            Merge::Concatenate => "concatenate",
+            Merge::Multiply => "multiply",
+        }
+    }
+
+    fn forward(&self, inputs: &[ArrayView2<f64>]) -> Array2<f64> {
+        match self {
+            Merge::Add => inputs[1..]
+                .iter()
+                .fold(inputs[0].to_owned(), |sum, input| sum + input),
+            Merge::Multiply => inputs[1..]
+                .iter()
+                .fold(inputs[0].to_owned(), |product, input| product * input),
+            Merge::Concatenate => concatenate(Axis(1), inputs).unwrap(),
+        }
+    }
+
+    // The derivative with respect to the k-th input
+    fn backward(&self, inputs: &[ArrayView2<f64>], k: usize, delta: &Array2<f64>) -> Array2<f64> {
+        match self {
+            Merge::Add => delta.clone(),
+            Merge::Multiply => inputs
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != k)
+                .fold(delta.clone(), |product, (_, input)| product * input),
+            Merge::Concatenate => {
+                let start = inputs[..k].iter().map(|input| input.ncols()).sum::<usize>();
+                delta
+                    .slice(s![.., start..start + inputs[k].ncols()])
+                    .to_owned()
+            }
+        }
+    }
+}
+
+impl Summary for Graph {
+    fn summerize(&self) -> String {
+        let shape = self.layers.iter().map(|x| x.get_size()).collect::<Vec<_>>();
+
+        format!("{}_graph{:?}", self.optimizer.summerize(), shape).replace(" ", "")
+    }
+}
+
+impl Graph {
+    // Every node with its output shape, activation, parameter count and inputs
+    pub fn summary(&self) -> ModelSummary {
+        let rows = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| match node {
+                Node::Input(_) => SummaryRow::input(self.widths[i]),
+                Node::Layer { layer, input } => {
+                    SummaryRow::layer(self.layers[*layer].as_ref(), input.0)
+                }
+                Node::Merge { merge, inputs } => SummaryRow {
+                    kind: merge.name().to_string(),
+                    output_shape: vec![self.widths[i]],
+                    activation: None,
+                    params: 0,
+                    inputs: inputs.iter().map(|input| input.0).collect(),
+                },
+            })
+            .collect();
+
+        ModelSummary {
+            title: self.summerize(),
+            rows,
+            outputs: self.outputs.iter().map(|output| output.0).collect(),
+        }
+    }
+}
+
+// Describes a graph node by node. Every method returns the id of the new node,
+// which later nodes use as their input. Mistakes are reported by `build`.
+#[derive(Default)]
+pub struct GraphBuilder {
+    nodes: Vec<Node>,
+    widths: Vec<usize>,
+    layers: Vec<Box<dyn Layer>>,
+    inputs: usize,
+    error: Option<BuildError>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> GraphBuilder {
+        GraphBuilder::default()
+    }
+
+    pub fn input(&mut self, size: usize) -> NodeId {
+        if size == 0 {
+            self.fail(BuildError::ZeroSizedLayer(self.nodes.len()));
+        }
+
+        self.inputs += 1;
+        self.push(Node::Input(self.inputs - 1), size)
+    }
+
+    pub fn dense(
+        &mut self,
+        input: NodeId,
+        size: usize,
+        activation: impl ActivationFunction + 'static,
+    ) -> NodeId {
+        self.dense_boxed(input, size, Box::new(activation))
+    }
+
+    pub fn dense_boxed(
+        &mut self,
+        input: NodeId,
+        size: usize,
+        activation: Box<dyn ActivationFunction>,
+    ) -> NodeId {
+        if size == 0 {
+            self.fail(BuildError::ZeroSizedLayer(self.nodes.len()));
+        }
+
+        let width = self.width(input);
+        let mut layer = DenseLayer::new(width, activation);
+        layer.initialize(width, size);
+        self.push_layer(input, Box::new(layer))
+    }
+
+    // Adds a layer, e.g. a recurrent layer or an embedding, and initializes it.
+    // Any weights it already had are replaced.
+    pub fn layer(&mut self, input: NodeId, mut layer: Box<dyn Layer>) -> NodeId {
+        let (input_size, output_size) = (layer.get_size(), layer.get_output_size());
+        layer.initialize(input_size, output_size);
+        self.push_layer(input, layer)
+    }
+
+    // Adds a layer that is already initialized
+    fn push_layer(&mut self, input: NodeId, layer: Box<dyn Layer>) -> NodeId {
+        let width = self.width(input);
+        if layer.get_size() != width {
+            self.fail(BuildError::WidthMismatch {
+                node: self.nodes.len(),
+                expected: width,
+                found: layer.get_size(),
+            });
+        }
+        if layer.get_output_size() == 0 {
+            self.fail(BuildError::ZeroSizedLayer(self.nodes.len()));
+        }
+        // the graph version of an embedding that isn't the first layer
+        if layer.needs_raw_input() && !matches!(self.nodes.get(input.0), Some(Node::Input(_))) {
+            self.fail(BuildError::EmbeddingNotFirst(self.nodes.len()));
+        }
+
+        let size = layer.get_output_size();
+        self.layers.push(layer);
+        self.push(
+            Node::Layer {
+                layer: self.layers.len() - 1,
+                input,
+            },
+            size,
+        )
+    }
+
+    pub fn merge(&mut self, merge: Merge, inputs: &[NodeId]) -> NodeId {
+        let node = self.nodes.len();
+        if inputs.is_empty() {
+            self.fail(BuildError::EmptyMerge(node));
+            return self.push(
+                Node::Merge {
+                    merge,
+                    inputs: Vec::new(),
+                },
+                0,
+            );
+        }
+
+        let widths = inputs
+            .iter()
+            .map(|&input| self.width(input))
+            .collect::<Vec<_>>();
+        let width = match merge {
+            Merge::Concatenate => widths.iter().sum(),
+            Merge::Add | Merge::Multiply => {
+                if let Some(&found) = widths.iter().find(|&&width| width != widths[0]) {
+                    self.fail(BuildError::WidthMismatch {
+                        node,
+                        expected: widths[0],
+                        found,
+                    });
+                }
+                widths[0]
+            }
+        };
+
+        self.push(
+            Node::Merge {
+                merge,
+                inputs: inputs.to_vec(),
+            },
+            width,
+        )
+    }
+
+    pub fn add(&mut self, inputs: &[NodeId]) -> NodeId {
+        self.merge(Merge::Add, inputs)
+    }
+
+    pub fn concatenate(&mut self, inputs: &[NodeId]) -> NodeId {
+        self.merge(Merge::Concatenate, inputs)
+    }
+
+    pub fn multiply(&mut self, inputs: &[NodeId]) -> NodeId {
+        self.merge(Merge::Multiply, inputs)
+    }
+
+    // Finishes the graph, predictions and targets follow the order of `outputs`
+    pub fn build(
+        self,
+        outputs: &[NodeId],
+        optimizer: impl Optimizer + 'static,
+        cost_function: impl CostFunction + 'static,
+    ) -> std::result::Result<Graph, BuildError> {
+        self.build_boxed(outputs, Box::new(optimizer), Box::new(cost_function))
+    }
+
+    pub fn build_boxed(
+        self,
+        outputs: &[NodeId],
+        mut optimizer: Box<dyn Optimizer>,
+        cost_function: Box<dyn CostFunction>,
+    ) -> std::result::Result<Graph, BuildError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if self.inputs == 0 {
+            return Err(BuildError::MissingInput);
+        }
+        if outputs.is_empty() {
+            return Err(BuildError::NoOutputs);
+        }
+        if let Some(output) = outputs.iter().find(|output| output.0 >= self.nodes.len()) {
+            return Err(BuildError::UnknownNode(output.0));
+        }
+
+        optimizer.initialize(&self.layers);
+
+        Ok(Graph {
+            nodes: self.nodes,
+            widths: self.widths,
+            outputs: outputs.to_vec(),
+            layers: self.layers,
+            optimizer,
+            cost_function,
+            guard: DivergenceGuard::default(),
+        })
+    }
+
+    fn push(&mut self, node: Node, width: usize) -> NodeId {
+        self.nodes.push(node);
+        self.widths.push(width);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    // The width of a node, ids of other builders are recorded as an error
+    fn width(&mut self, node: NodeId) -> usize {
+        match self.widths.get(node.0) {
+            Some(&width) => width,
+            None => {
+                self.fail(BuildError::UnknownNode(node.0));
+                0
+            }
+        }
+    }
+
+    // Keeps the first error
+    fn fail(&mut self, error: BuildError) {
+        self.error.get_or_insert(error);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{GraphBuilder, NodeId};
+use crate::neural_network::{
+    activation_function::{ActivationFunction, Linear, Relu},
+    builder::BuildError,
+    layer::{
+        attention::MultiHeadAttention, layer_norm::LayerNorm,
+        positional_encoding::PositionalEncoding, time_distributed::TimeDistributed,
+    },
+};
+
+// Sequence layers. The input of each of them is a flattened sequence of
+// `steps` vectors, the size of the vectors follows from the input width.
+impl GraphBuilder {
+    pub fn positional_encoding(&mut self, input: NodeId, steps: usize) -> NodeId {
+        let dimension = self.step_size(input, steps);
+        self.layer(input, Box::new(PositionalEncoding::new(steps, dimension)))
+    }
+
+    // A dense layer applied to every step on its own
+    pub fn time_distributed(
+        &mut self,
+        input: NodeId,
+        steps: usize,
+        size: usize,
+        activation: impl ActivationFunction + 'static,
+    ) -> NodeId {
+        if size == 0 {
+            self.fail(BuildError::ZeroSizedLayer(self.nodes.len()));
+        }
+
+        let features = self.step_size(input, steps);
+        let layer = TimeDistributed::new(steps, features, size, Box::new(activation));
+        self.push_layer(input, Box::new(layer))
+    }
+
+    pub fn attention(&mut self, input: NodeId, steps: usize, heads: usize) -> NodeId {
+        let dimension = self.step_size(input, steps);
+        match MultiHeadAttention::new(steps, dimension, heads) {
+            Ok(layer) => self.layer(input, Box::new(layer)),
+            Err(_) => {
+                self.fail(BuildError::IndivisibleHeads {
+                    node: self.nodes.len(),
+                    dimension,
+                    heads,
+                });
+                // keeps the graph consistent until `build` reports the error
+                let placeholder = PositionalEncoding::new(steps, dimension);
+                self.layer(input, Box::new(placeholder))
+            }
+        }
+    }
+
+    pub fn layer_norm(&mut self, input: NodeId, steps: usize) -> NodeId {
+        let dimension = self.step_size(input, steps);
+        self.layer(input, Box::new(LayerNorm::new(steps, dimension)))
+    }
+
+    // A transformer encoder block: self-attention and a feed-forward network
+    // with `hidden` units per step, each wrapped in a residual connection
+    // followed by layer normalization
+    pub fn transformer_encoder(
+        &mut self,
+        input: NodeId,
+        steps: usize,
+        heads: usize,
+        hidden: usize,
+    ) -> NodeId {
+        let dimension = self.step_size(input, steps);
+
+        let attention = self.attention(input, steps, heads);
+        let x = self.add(&[input, attention]);
+        let x = self.layer_norm(x, steps);
+
+        let h = self.time_distributed(x, steps, hidden, Relu);
+        let h = self.time_distributed(h, steps, dimension, Linear);
+        let x = self.add(&[x, h]);
+        self.layer_norm(x, steps)
+    }
+
+    // The number of values per step of a node
+    fn step_size(&mut self, input: NodeId, steps: usize) -> usize {
+        let width = self.width(input);
+        if steps == 0 || !width.is_multiple_of(steps) {
+            self.fail(BuildError::NotASequence(self.nodes.len()));
+            return 0;
+        }
+        width / steps
+    }
+}
@@ -1,23 +1,84 @@
-use ndarray::Array2;
+use ndarray::{linalg::general_mat_mul, Array2};
 use ndarray_rand::{rand_distr::Normal, RandomExt};
 
-use super::activation_function::ActivationFunction;
+pub mod attention;
+pub mod embedding;
+pub mod layer_norm;
+pub mod positional_encoding;
+pub mod recurrent;
+pub mod time_distributed;
 
-pub trait Layer {
-    fn new(input_size: usize, activation: &'static dyn ActivationFunction) -> Self
-    where
-        Self: Sized;
+use super::{activation_function::ActivationFunction, workspace::ensure_shape};
+use crate::error::Result;
 
+pub trait Layer: Send + Sync {
     fn initialize(&mut self, input_size: usize, output_size: usize);
 
     fn predict(&self, input: &Array2<f64>) -> Array2<f64>;
     fn forward(&self, input: &Array2<f64>) -> Array2<f64>;
 
+    // Training forward pass, writes the weighted sum into `z` and the
+    // activation into `output`, reusing their memory when the shapes match
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>);
+
+    // Training backward pass. `delta` holds the derivative of the cost with
+    // respect to the output of the layer and may be overwritten, dense layers
+    // turn it into the derivative with respect to `z`. The gradients of the
+    // parameters are written into `nabla_w` and `nabla_b`, the derivative with
+    // respect to the input into `input_delta` if one is given.
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        input_delta: Option<&mut Array2<f64>>,
+    );
+
+    // Layers whose weight gradient is zero except for a few rows, like
+    // embeddings, write those rows to the empty `rows` and return true, so the
+    // optimizers only update them. Their `backward_into` gets an all-zero
+    // `nabla_w` and only writes those rows.
+    fn sparse_rows(&self, _input: &Array2<f64>, _rows: &mut Vec<usize>) -> bool {
+        false
+    }
+
+    // Checks input values the layer can't handle, only called for the first layer
+    fn check_input(&self, _input: &Array2<f64>) -> Result<()> {
+        Ok(())
+    }
+
+    // Layers that check their input or have sparse gradients, like embeddings,
+    // can only read the input of the network and not the output of a layer
+    fn needs_raw_input(&self) -> bool {
+        false
+    }
+
+    // The kind of layer, e.g. in a model summary
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    // The activation shown in a model summary, if the layer applies one
+    fn activation_name(&self) -> Option<&'static str> {
+        Some(self.get_activation().name())
+    }
+
+    // The shape of the output of one sample, (steps, size) for sequences
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.get_output_size()]
+    }
+
+    // The number of inputs
     fn get_size(&self) -> usize;
-    fn get_activation(&self) -> &'static dyn ActivationFunction;
+    fn get_output_size(&self) -> usize;
+    fn get_activation(&self) -> &dyn ActivationFunction;
     fn get_weights(&self) -> &Array2<f64>;
+    fn get_weights_mut(&mut self) -> &mut Array2<f64>;
     fn set_weights(&mut self, weights: Array2<f64>);
     fn get_bias(&self) -> &Array2<f64>;
+    fn get_bias_mut(&mut self) -> &mut Array2<f64>;
     fn set_bias(&mut self, biases: Array2<f64>);
 }
 
@@ -25,11 +86,11 @@ pub struct DenseLayer {
     pub input_size: usize,
     pub weights: Array2<f64>,
     pub biases: Array2<f64>,
-    pub activation: &'static dyn ActivationFunction,
+    pub activation: Box<dyn ActivationFunction>,
 }
 
-impl Layer for DenseLayer {
-    fn new(input_size: usize, activation: &'static dyn ActivationFunction) -> DenseLayer {
+impl DenseLayer {
+    pub fn new(input_size: usize, activation: Box<dyn ActivationFunction>) -> DenseLayer {
         DenseLayer {
             input_size,
             activation,
@@ -37,6 +98,12 @@ impl Layer for DenseLayer {
             biases: Array2::zeros((0, 0)),
         }
     }
+}
+
+impl Layer for DenseLayer {
+    fn name(&self) -> &'static str {
+        "dense"
+    }
 
     fn initialize(&mut self, input_size: usize, output_size: usize) {
         self.weights = Array2::random((input_size, output_size), Normal::new(0.0, 1.0).unwrap())
@@ -48,8 +115,12 @@ impl Layer for DenseLayer {
         self.input_size
     }
 
-    fn get_activation(&self) -> &'static dyn ActivationFunction {
-        self.activation
+    fn get_output_size(&self) -> usize {
+        self.weights.ncols()
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        self.activation.as_ref()
     }
 
     fn get_weights(&self) -> &Array2<f64> {
@@ -60,6 +131,14 @@ impl Layer for DenseLayer {
         &self.biases
     }
 
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
     fn set_weights(&mut self, weights: Array2<f64>) {
         self.weights = weights;
     }
@@ -78,4 +157,39 @@ impl Layer for DenseLayer {
     fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
         input.dot(&self.weights) + &self.biases
     }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let shape = (input.nrows(), self.weights.ncols());
+        ensure_shape(z, shape);
+        ensure_shape(output, shape);
+
+        z.assign(&self.biases);
+        general_mat_mul(1.0, input, &self.weights, 1.0, z);
+
+        self.activation.f_into(z, output);
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        input_delta: Option<&mut Array2<f64>>,
+    ) {
+        self.activation.mul_d(z, delta);
+
+        general_mat_mul(1.0, &input.t(), delta, 0.0, nabla_w);
+
+        nabla_b.fill(0.0);
+        for row in delta.rows() {
+            nabla_b.row_mut(0).scaled_add(1.0, &row);
+        }
+
+        if let Some(input_delta) = input_delta {
+            ensure_shape(input_delta, input.dim());
+            general_mat_mul(1.0, delta, &self.weights.t(), 0.0, input_delta);
+        }
+    }
 }
@@ -0,0 +1,305 @@
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, Axis};
+use ndarray_rand::{rand_distr::Normal, RandomExt};
+
+use super::Layer;
+use crate::{
+    error::{Error, Result},
+    neural_network::{
+        activation_function::{ActivationFunction, Linear},
+        workspace::ensure_shape,
+    },
+};
+
+// Multi-head scaled dot-product self-attention over a sequence.
+//
+// Sequences are flattened like for recurrent layers: every row holds `steps`
+// vectors of `dimension` values. Each step is projected to queries, keys and
+// values, which are split into `heads` parts of `dimension / heads` values.
+// Every head mixes the values of all steps, weighted by the softmax of the
+// scaled query-key products, and the heads are projected back to `dimension`
+// values per step.
+//
+// The query, key, value and output projections are stacked along the columns
+// of one `dimension x 4 * dimension` matrix, so the optimizers treat attention
+// like any other layer.
+pub struct MultiHeadAttention {
+    pub steps: usize,
+    pub dimension: usize,
+    pub heads: usize,
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+}
+
+impl MultiHeadAttention {
+    // The dimension has to be divisible by the number of heads
+    pub fn new(steps: usize, dimension: usize, heads: usize) -> Result<MultiHeadAttention> {
+        if heads == 0 || !dimension.is_multiple_of(heads) {
+            return Err(Error::InvalidData(format!(
+                "the dimension {} is not divisible by {} heads",
+                dimension, heads
+            )));
+        }
+
+        Ok(MultiHeadAttention {
+            steps,
+            dimension,
+            heads,
+            weights: Array2::zeros((0, 0)),
+            biases: Array2::zeros((0, 0)),
+        })
+    }
+
+    fn head_size(&self) -> usize {
+        self.dimension / self.heads
+    }
+
+    // Per sample `z` caches the queries, keys and values, the concatenated
+    // heads and the attention weights of every head
+    fn cache_width(&self) -> usize {
+        let (t, d) = (self.steps, self.dimension);
+        4 * t * d + self.heads * t * t
+    }
+
+    // The cached queries, keys and values, heads and attention weights of one sample
+    fn split_cache<'a>(
+        &self,
+        cache: ArrayView1<'a, f64>,
+    ) -> (
+        ArrayView2<'a, f64>,
+        ArrayView2<'a, f64>,
+        ArrayView2<'a, f64>,
+    ) {
+        let (t, d) = (self.steps, self.dimension);
+
+        let qkv = cache.slice_move(s![..3 * t * d]);
+        let heads = cache.slice_move(s![3 * t * d..4 * t * d]);
+        let attention = cache.slice_move(s![4 * t * d..]);
+
+        (
+            qkv.into_shape((t, 3 * d)).unwrap(),
+            heads.into_shape((t, d)).unwrap(),
+            attention.into_shape((self.heads * t, t)).unwrap(),
+        )
+    }
+
+    // The columns of head `h` in the queries (0), keys (1) or values (2)
+    fn head_columns(&self, part: usize, h: usize) -> std::ops::Range<usize> {
+        let start = part * self.dimension + h * self.head_size();
+        start..start + self.head_size()
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    fn name(&self) -> &'static str {
+        "attention"
+    }
+
+    fn activation_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.steps, self.dimension]
+    }
+
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+
+        let d = self.dimension;
+        self.weights =
+            Array2::random((d, 4 * d), Normal::new(0.0, 1.0).unwrap()) / (d as f64).sqrt();
+        self.biases = Array2::zeros((1, 4 * d));
+    }
+
+    fn get_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_output_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        &Linear
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        &self.biases
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.weights = weights;
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.biases = biases;
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.forward(input)
+    }
+
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        let mut z = Array2::zeros((0, 0));
+        let mut output = Array2::zeros((0, 0));
+        self.forward_into(input, &mut z, &mut output);
+        output
+    }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let (t, d) = (self.steps, self.dimension);
+        let scale = 1.0 / (self.head_size() as f64).sqrt();
+        let input = input.as_standard_layout();
+        ensure_shape(z, (input.nrows(), self.cache_width()));
+        ensure_shape(output, (input.nrows(), t * d));
+
+        let w_qkv = self.weights.slice(s![.., ..3 * d]);
+        let w_o = self.weights.slice(s![.., 3 * d..]);
+        let b_qkv = self.biases.slice(s![.., ..3 * d]);
+        let b_o = self.biases.slice(s![.., 3 * d..]);
+
+        for (i, x) in input.rows().into_iter().enumerate() {
+            let x = x.into_shape((t, d)).unwrap();
+
+            let qkv = x.dot(&w_qkv) + b_qkv;
+            let mut heads = Array2::zeros((t, d));
+            let mut attention = Array2::zeros((self.heads * t, t));
+
+            for h in 0..self.heads {
+                let q = qkv.slice(s![.., self.head_columns(0, h)]);
+                let k = qkv.slice(s![.., self.head_columns(1, h)]);
+                let v = qkv.slice(s![.., self.head_columns(2, h)]);
+
+                let weights = softmax_rows(q.dot(&k.t()) * scale);
+                heads
+                    .slice_mut(s![.., h * self.head_size()..(h + 1) * self.head_size()])
+                    .assign(&weights.dot(&v));
+                attention
+                    .slice_mut(s![h * t..(h + 1) * t, ..])
+                    .assign(&weights);
+            }
+
+            let y = heads.dot(&w_o) + b_o;
+
+            let mut cache = z.row_mut(i);
+            cache.slice_mut(s![..3 * t * d]).assign(&flatten(&qkv));
+            cache
+                .slice_mut(s![3 * t * d..4 * t * d])
+                .assign(&flatten(&heads));
+            cache
+                .slice_mut(s![4 * t * d..])
+                .assign(&flatten(&attention));
+            output.row_mut(i).assign(&flatten(&y));
+        }
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        mut input_delta: Option<&mut Array2<f64>>,
+    ) {
+        let (t, d) = (self.steps, self.dimension);
+        let scale = 1.0 / (self.head_size() as f64).sqrt();
+        let input = input.as_standard_layout();
+        let delta = delta.as_standard_layout();
+
+        let w_qkv = self.weights.slice(s![.., ..3 * d]);
+        let w_o = self.weights.slice(s![.., 3 * d..]);
+
+        nabla_w.fill(0.0);
+        nabla_b.fill(0.0);
+        if let Some(input_delta) = input_delta.as_mut() {
+            ensure_shape(input_delta, input.dim());
+        }
+
+        for (i, (x, dy)) in input.rows().into_iter().zip(delta.rows()).enumerate() {
+            let x = x.into_shape((t, d)).unwrap();
+            let dy = dy.into_shape((t, d)).unwrap();
+            let (qkv, heads, attention) = self.split_cache(z.row(i));
+
+            // output projection
+            nabla_w
+                .slice_mut(s![.., 3 * d..])
+                .scaled_add(1.0, &heads.t().dot(&dy));
+            nabla_b
+                .slice_mut(s![0, 3 * d..])
+                .scaled_add(1.0, &dy.sum_axis(Axis(0)));
+            let d_heads = dy.dot(&w_o.t());
+
+            let mut d_qkv = Array2::zeros((t, 3 * d));
+            for h in 0..self.heads {
+                let q = qkv.slice(s![.., self.head_columns(0, h)]);
+                let k = qkv.slice(s![.., self.head_columns(1, h)]);
+                let v = qkv.slice(s![.., self.head_columns(2, h)]);
+                let weights = attention.slice(s![h * t..(h + 1) * t, ..]);
+                let d_head =
+                    d_heads.slice(s![.., h * self.head_size()..(h + 1) * self.head_size()]);
+
+                let d_weights = d_head.dot(&v.t());
+                d_qkv
+                    .slice_mut(s![.., self.head_columns(2, h)])
+                    .assign(&weights.t().dot(&d_head));
+
+                // derivative of the softmax of every row
+                let row_sums = (&d_weights * &weights)
+                    .sum_axis(Axis(1))
+                    .insert_axis(Axis(1));
+                let d_scores = &weights * &(d_weights - row_sums) * scale;
+
+                d_qkv
+                    .slice_mut(s![.., self.head_columns(0, h)])
+                    .assign(&d_scores.dot(&k));
+                d_qkv
+                    .slice_mut(s![.., self.head_columns(1, h)])
+                    .assign(&d_scores.t().dot(&q));
+            }
+
+            // query, key and value projections
+            nabla_w
+                .slice_mut(s![.., ..3 * d])
+                .scaled_add(1.0, &x.t().dot(&d_qkv));
+            nabla_b
+                .slice_mut(s![0, ..3 * d])
+                .scaled_add(1.0, &d_qkv.sum_axis(Axis(0)));
+
+            if let Some(input_delta) = input_delta.as_mut() {
+                input_delta
+                    .row_mut(i)
+                    .assign(&flatten(&d_qkv.dot(&w_qkv.t())));
+            }
+        }
+    }
+}
+
+// Row-wise softmax, shifted by the row maximum for numerical stability
+fn softmax_rows(mut scores: Array2<f64>) -> Array2<f64> {
+    for mut row in scores.rows_mut() {
+        let max = row.fold(f64::NEG_INFINITY, |max, &x| max.max(x));
+        row.mapv_inplace(|x| (x - max).exp());
+        let sum = row.sum();
+        row /= sum;
+    }
+    scores
+}
+
+// The entries of a matrix row after row
+fn flatten(matrix: &Array2<f64>) -> Array1<f64> {
+    matrix.iter().copied().collect()
+}
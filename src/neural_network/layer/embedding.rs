@@ -0,0 +1,176 @@
+use ndarray::{s, Array2};
+use ndarray_rand::{rand_distr::Normal, RandomExt};
+
+use super::Layer;
+use crate::{
+    error::{Error, Result},
+    neural_network::{
+        activation_function::{ActivationFunction, Linear},
+        workspace::ensure_shape,
+    },
+};
+
+// Maps integer indices to trainable vectors, so categories and tokens don't
+// have to be one-hot encoded. Every input row holds `tokens` indices stored as
+// floats, the output the `dimension` values of each of them one after another,
+// which is the sequence layout recurrent layers expect.
+//
+// The weights are the lookup table with one row per index. A batch only
+// touches the rows of the indices it contains, so only those are updated.
+pub struct EmbeddingLayer {
+    pub vocabulary_size: usize,
+    pub dimension: usize,
+    pub tokens: usize,
+    pub weights: Array2<f64>,
+    // Embeddings have no bias, this stays empty
+    pub biases: Array2<f64>,
+}
+
+impl EmbeddingLayer {
+    pub fn new(vocabulary_size: usize, dimension: usize, tokens: usize) -> EmbeddingLayer {
+        EmbeddingLayer {
+            vocabulary_size,
+            dimension,
+            tokens,
+            weights: Array2::zeros((0, 0)),
+            biases: Array2::zeros((1, 0)),
+        }
+    }
+}
+
+impl Layer for EmbeddingLayer {
+    fn name(&self) -> &'static str {
+        "embedding"
+    }
+
+    fn activation_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.tokens, self.dimension]
+    }
+
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+
+        self.weights = Array2::random(
+            (self.vocabulary_size, self.dimension),
+            Normal::new(0.0, 1.0).unwrap(),
+        ) / (self.dimension as f64).sqrt();
+        self.biases = Array2::zeros((1, 0));
+    }
+
+    fn sparse_rows(&self, input: &Array2<f64>, rows: &mut Vec<usize>) -> bool {
+        rows.extend(input.iter().map(|&index| index as usize));
+        rows.sort_unstable();
+        rows.dedup();
+        true
+    }
+
+    fn needs_raw_input(&self) -> bool {
+        true
+    }
+
+    fn check_input(&self, input: &Array2<f64>) -> Result<()> {
+        match input.iter().find(|&&index| {
+            index.fract() != 0.0 || index < 0.0 || index >= self.vocabulary_size as f64
+        }) {
+            Some(index) => Err(Error::InvalidData(format!(
+                "{} is not an index into an embedding of {} entries",
+                index, self.vocabulary_size
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn get_size(&self) -> usize {
+        self.tokens
+    }
+
+    fn get_output_size(&self) -> usize {
+        self.tokens * self.dimension
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        &Linear
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        &self.biases
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.weights = weights;
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.biases = biases;
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.forward(input)
+    }
+
+    // Looks up the vector of every index
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        let mut z = Array2::zeros((0, 0));
+        let mut output = Array2::zeros((0, 0));
+        self.forward_into(input, &mut z, &mut output);
+        output
+    }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let d = self.dimension;
+        ensure_shape(z, (input.nrows(), 0));
+        ensure_shape(output, (input.nrows(), self.get_output_size()));
+
+        for (mut output_row, input_row) in output.rows_mut().into_iter().zip(input.rows()) {
+            for (t, &index) in input_row.iter().enumerate() {
+                output_row
+                    .slice_mut(s![t * d..(t + 1) * d])
+                    .assign(&self.weights.row(index as usize));
+            }
+        }
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        _z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        _nabla_b: &mut Array2<f64>,
+        input_delta: Option<&mut Array2<f64>>,
+    ) {
+        let d = self.dimension;
+
+        // `nabla_w` is all zeros, only the rows of the indices are added to
+        for (delta_row, input_row) in delta.rows().into_iter().zip(input.rows()) {
+            for (t, &index) in input_row.iter().enumerate() {
+                nabla_w
+                    .row_mut(index as usize)
+                    .scaled_add(1.0, &delta_row.slice(s![t * d..(t + 1) * d]));
+            }
+        }
+
+        // indices can't be changed by gradient descent
+        if let Some(input_delta) = input_delta {
+            ensure_shape(input_delta, input.dim());
+            input_delta.fill(0.0);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use ndarray::{s, Array2, Axis};
+
+use super::Layer;
+use crate::neural_network::{
+    activation_function::{ActivationFunction, Linear},
+    workspace::ensure_shape,
+};
+
+const EPSILON: f64 = 1e-5;
+
+// Normalizes every step of a sequence to zero mean and unit variance over its
+// `dimension` values, then scales and shifts them by trainable factors. The
+// weights hold the factors, the biases the shifts, both shared by all steps.
+pub struct LayerNorm {
+    pub steps: usize,
+    pub dimension: usize,
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+}
+
+impl LayerNorm {
+    pub fn new(steps: usize, dimension: usize) -> LayerNorm {
+        LayerNorm {
+            steps,
+            dimension,
+            weights: Array2::zeros((0, 0)),
+            biases: Array2::zeros((0, 0)),
+        }
+    }
+}
+
+impl Layer for LayerNorm {
+    fn name(&self) -> &'static str {
+        "layer_norm"
+    }
+
+    fn activation_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.steps, self.dimension]
+    }
+
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+
+        self.weights = Array2::ones((1, self.dimension));
+        self.biases = Array2::zeros((1, self.dimension));
+    }
+
+    fn get_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_output_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        &Linear
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        &self.biases
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.weights = weights;
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.biases = biases;
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.forward(input)
+    }
+
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        let mut z = Array2::zeros((0, 0));
+        let mut output = Array2::zeros((0, 0));
+        self.forward_into(input, &mut z, &mut output);
+        output
+    }
+
+    // `z` caches the normalized values followed by the inverse standard
+    // deviation of every step
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let (t, d) = (self.steps, self.dimension);
+        ensure_shape(z, (input.nrows(), t * d + t));
+        ensure_shape(output, (input.nrows(), t * d));
+
+        for i in 0..input.nrows() {
+            for step in 0..t {
+                let columns = s![step * d..(step + 1) * d];
+                let x = input.slice(s![i, step * d..(step + 1) * d]);
+
+                let mean = x.sum() / d as f64;
+                let variance = x.mapv(|x| (x - mean).powi(2)).sum() / d as f64;
+                let inverse_std = 1.0 / (variance + EPSILON).sqrt();
+                let normalized = x.mapv(|x| (x - mean) * inverse_std);
+
+                output
+                    .row_mut(i)
+                    .slice_mut(columns)
+                    .assign(&(&normalized * &self.weights.row(0) + self.biases.row(0)));
+                z.row_mut(i).slice_mut(columns).assign(&normalized);
+                z[[i, t * d + step]] = inverse_std;
+            }
+        }
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        mut input_delta: Option<&mut Array2<f64>>,
+    ) {
+        let (t, d) = (self.steps, self.dimension);
+
+        nabla_w.fill(0.0);
+        nabla_b.fill(0.0);
+        if let Some(input_delta) = input_delta.as_mut() {
+            ensure_shape(input_delta, input.dim());
+        }
+
+        for i in 0..input.nrows() {
+            for step in 0..t {
+                let columns = s![step * d..(step + 1) * d];
+                let dy = delta.slice(s![i, step * d..(step + 1) * d]);
+                let normalized = z.slice(s![i, step * d..(step + 1) * d]);
+
+                nabla_w.row_mut(0).scaled_add(1.0, &(&dy * &normalized));
+                nabla_b.row_mut(0).scaled_add(1.0, &dy);
+
+                if let Some(input_delta) = input_delta.as_mut() {
+                    // dx = (dn - mean(dn) - n * mean(dn * n)) / std
+                    let d_normalized = &dy * &self.weights.row(0);
+                    let mean = d_normalized.mean_axis(Axis(0)).unwrap();
+                    let correlation = (&d_normalized * &normalized).mean().unwrap();
+                    let dx =
+                        (d_normalized - mean - &normalized * correlation) * z[[i, t * d + step]];
+
+                    input_delta.row_mut(i).slice_mut(columns).assign(&dx);
+                }
+            }
+        }
+    }
+}
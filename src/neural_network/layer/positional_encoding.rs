@@ -0,0 +1,136 @@
+use ndarray::Array2;
+
+use super::Layer;
+use crate::neural_network::{
+    activation_function::{ActivationFunction, Linear},
+    workspace::ensure_shape,
+};
+
+// Adds the sinusoidal position encodings of "Attention Is All You Need" to
+// every step of a sequence, so attention can tell the steps apart:
+//
+//     PE(t, 2i)     = sin(t / 10000^(2i / dimension))
+//     PE(t, 2i + 1) = cos(t / 10000^(2i / dimension))
+//
+// The encodings are fixed, the layer has no trainable parameters.
+pub struct PositionalEncoding {
+    pub steps: usize,
+    pub dimension: usize,
+    // The encodings of all steps, flattened like the input
+    encodings: Array2<f64>,
+    // Always empty
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+}
+
+impl PositionalEncoding {
+    pub fn new(steps: usize, dimension: usize) -> PositionalEncoding {
+        let encodings = Array2::from_shape_fn((1, steps * dimension), |(_, j)| {
+            let (t, k) = (j / dimension, j % dimension);
+            let angle = t as f64 / 10000f64.powf((k - k % 2) as f64 / dimension as f64);
+            if k % 2 == 0 {
+                angle.sin()
+            } else {
+                angle.cos()
+            }
+        });
+
+        PositionalEncoding {
+            steps,
+            dimension,
+            encodings,
+            weights: Array2::zeros((0, 0)),
+            biases: Array2::zeros((1, 0)),
+        }
+    }
+
+    pub fn encodings(&self) -> &Array2<f64> {
+        &self.encodings
+    }
+}
+
+impl Layer for PositionalEncoding {
+    fn name(&self) -> &'static str {
+        "positional_encoding"
+    }
+
+    fn activation_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.steps, self.dimension]
+    }
+
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+    }
+
+    fn get_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_output_size(&self) -> usize {
+        self.steps * self.dimension
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        &Linear
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        &self.biases
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.weights = weights;
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.biases = biases;
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.forward(input)
+    }
+
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        input + &self.encodings
+    }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        ensure_shape(z, (input.nrows(), 0));
+        ensure_shape(output, input.dim());
+
+        output.assign(&self.encodings);
+        *output += input;
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        _z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        _nabla_w: &mut Array2<f64>,
+        _nabla_b: &mut Array2<f64>,
+        input_delta: Option<&mut Array2<f64>>,
+    ) {
+        if let Some(input_delta) = input_delta {
+            ensure_shape(input_delta, input.dim());
+            input_delta.assign(delta);
+        }
+    }
+}
@@ -0,0 +1,475 @@
+use ndarray::{concatenate, linalg::general_mat_mul, s, Array2, ArrayView2, ArrayViewMut2, Axis};
+use ndarray_rand::{rand_distr::Normal, RandomExt};
+
+use super::Layer;
+use crate::{
+    error::{Error, Result},
+    neural_network::{
+        activation_function::{ActivationFunction, Sigmoid, Tanh},
+        workspace::ensure_shape,
+    },
+};
+
+// The update rule applied at every time step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    // h = activation(x Wx + h Wh + b)
+    SimpleRnn,
+    // Input, forget and output gates around a cell state
+    Lstm,
+    // Update and reset gates, without a separate cell state
+    Gru,
+}
+
+impl Cell {
+    // Number of blocks of `hidden` columns in the weights and biases
+    fn gates(&self) -> usize {
+        match self {
+            Cell::SimpleRnn => 1,
+            Cell::Lstm => 4,
+            Cell::Gru => 3,
+        }
+    }
+
+    // Number of blocks of `hidden` columns cached per time step, the last one
+    // is always the hidden state
+    fn cache_blocks(&self) -> usize {
+        match self {
+            Cell::SimpleRnn => 2,
+            Cell::Lstm => 6,
+            Cell::Gru => 4,
+        }
+    }
+}
+
+// A recurrent layer, trained with backpropagation through time.
+//
+// Sequences are passed as flattened rows: the input has `steps * features`
+// columns, time step after time step. The output is the last hidden state, or
+// the hidden states of all steps flattened the same way if `return_sequences`
+// is set.
+//
+// The input and recurrent weights are stacked into one matrix with
+// `features + hidden` rows, the gates are stacked along the columns
+// (LSTM: input, forget, candidate, output; GRU: update, reset, candidate), so
+// the optimizers treat recurrent layers like any other layer.
+pub struct RecurrentLayer {
+    pub cell: Cell,
+    pub steps: usize,
+    pub features: usize,
+    pub hidden: usize,
+    pub return_sequences: bool,
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+    // Applied to the candidate state, the gates always use a sigmoid
+    pub activation: Box<dyn ActivationFunction>,
+}
+
+impl RecurrentLayer {
+    // Sequences need at least one time step
+    pub fn new(
+        cell: Cell,
+        steps: usize,
+        features: usize,
+        hidden: usize,
+        activation: Box<dyn ActivationFunction>,
+        return_sequences: bool,
+    ) -> Result<RecurrentLayer> {
+        if steps == 0 {
+            return Err(Error::InvalidData(String::from(
+                "a recurrent layer needs at least one time step",
+            )));
+        }
+
+        Ok(RecurrentLayer {
+            cell,
+            steps,
+            features,
+            hidden,
+            return_sequences,
+            weights: Array2::zeros((0, 0)),
+            biases: Array2::zeros((0, 0)),
+            activation,
+        })
+    }
+
+    pub fn simple_rnn(
+        steps: usize,
+        features: usize,
+        hidden: usize,
+        return_sequences: bool,
+    ) -> Result<RecurrentLayer> {
+        let activation = Box::new(Tanh);
+        RecurrentLayer::new(
+            Cell::SimpleRnn,
+            steps,
+            features,
+            hidden,
+            activation,
+            return_sequences,
+        )
+    }
+
+    pub fn lstm(
+        steps: usize,
+        features: usize,
+        hidden: usize,
+        return_sequences: bool,
+    ) -> Result<RecurrentLayer> {
+        let activation = Box::new(Tanh);
+        RecurrentLayer::new(
+            Cell::Lstm,
+            steps,
+            features,
+            hidden,
+            activation,
+            return_sequences,
+        )
+    }
+
+    pub fn gru(
+        steps: usize,
+        features: usize,
+        hidden: usize,
+        return_sequences: bool,
+    ) -> Result<RecurrentLayer> {
+        let activation = Box::new(Tanh);
+        RecurrentLayer::new(
+            Cell::Gru,
+            steps,
+            features,
+            hidden,
+            activation,
+            return_sequences,
+        )
+    }
+
+    fn cache_width(&self) -> usize {
+        self.cell.cache_blocks() * self.hidden
+    }
+
+    // The columns of `z` holding the cache of time step `t`
+    fn step_cache<'a>(&self, z: &'a Array2<f64>, t: usize) -> ArrayView2<'a, f64> {
+        let width = self.cache_width();
+        z.slice(s![.., t * width..(t + 1) * width])
+    }
+
+    // The cache of the step before `t`, all zeros before the first step
+    fn previous(&self, z: &Array2<f64>, t: usize) -> Array2<f64> {
+        if t == 0 {
+            Array2::zeros((z.nrows(), self.cache_width()))
+        } else {
+            self.step_cache(z, t - 1).to_owned()
+        }
+    }
+
+    fn split_weights(&self) -> (ArrayView2<'_, f64>, ArrayView2<'_, f64>) {
+        self.weights.view().split_at(Axis(0), self.features)
+    }
+
+    // Calculates the cache of one time step from the input part of the gates
+    // `xw = x Wx + b` and the cache of the previous step
+    fn step_forward(
+        &self,
+        xw: Array2<f64>,
+        wh: ArrayView2<f64>,
+        previous: &Array2<f64>,
+    ) -> Array2<f64> {
+        let h = self.hidden;
+        let h_prev = previous.slice(s![.., previous.ncols() - h..]);
+        let activation = self.activation.as_ref();
+
+        match self.cell {
+            Cell::SimpleRnn => {
+                let a = xw + h_prev.dot(&wh);
+                let state = a.mapv(|x| activation.f(x));
+                concatenate![Axis(1), a, state]
+            }
+            Cell::Lstm => {
+                let g = xw + h_prev.dot(&wh);
+                let c_prev = previous.slice(s![.., 4 * h..5 * h]);
+                let i = sigmoid(g.slice(s![.., ..h]));
+                let f = sigmoid(g.slice(s![.., h..2 * h]));
+                let candidate = g.slice(s![.., 2 * h..3 * h]).mapv(|x| activation.f(x));
+                let o = sigmoid(g.slice(s![.., 3 * h..]));
+
+                let c = &i * &candidate + &f * &c_prev;
+                let state = &o * &c.mapv(|x| activation.f(x));
+                concatenate![Axis(1), g, c, state]
+            }
+            Cell::Gru => {
+                let zr = &xw.slice(s![.., ..2 * h]) + &h_prev.dot(&wh.slice(s![.., ..2 * h]));
+                let z = sigmoid(zr.slice(s![.., ..h]));
+                let r = sigmoid(zr.slice(s![.., h..]));
+
+                let n_pre =
+                    &xw.slice(s![.., 2 * h..]) + &(&r * &h_prev).dot(&wh.slice(s![.., 2 * h..]));
+                let n = n_pre.mapv(|x| activation.f(x));
+
+                // (1 - z) * n + z * h_prev
+                let state = &n + &(&z * &(&h_prev - &n));
+                concatenate![Axis(1), zr, n_pre, state]
+            }
+        }
+    }
+
+    // Turns the derivative with respect to the hidden state of one step into
+    // the derivative with respect to the gate pre-activations and the hidden
+    // state of the previous step. The gradient of the recurrent weights is
+    // added to `nabla_wh`, `dc` carries the derivative with respect to the
+    // LSTM cell state from step to step.
+    fn step_backward(
+        &self,
+        wh: ArrayView2<f64>,
+        previous: &Array2<f64>,
+        cache: ArrayView2<f64>,
+        dh: &Array2<f64>,
+        dc: &mut Array2<f64>,
+        nabla_wh: &mut ArrayViewMut2<f64>,
+    ) -> (Array2<f64>, Array2<f64>) {
+        let h = self.hidden;
+        let h_prev = previous.slice(s![.., previous.ncols() - h..]);
+        let activation = self.activation.as_ref();
+
+        match self.cell {
+            Cell::SimpleRnn => {
+                let a = cache.slice(s![.., ..h]);
+                let da = dh * &a.mapv(|x| activation.d(x));
+
+                general_mat_mul(1.0, &h_prev.t(), &da, 1.0, nabla_wh);
+                let dh_prev = da.dot(&wh.t());
+                (da, dh_prev)
+            }
+            Cell::Lstm => {
+                let g = cache.slice(s![.., ..4 * h]);
+                let c = cache.slice(s![.., 4 * h..5 * h]);
+                let c_prev = previous.slice(s![.., 4 * h..5 * h]);
+                let (g_i, g_f) = (g.slice(s![.., ..h]), g.slice(s![.., h..2 * h]));
+                let (g_c, g_o) = (g.slice(s![.., 2 * h..3 * h]), g.slice(s![.., 3 * h..]));
+
+                let i = sigmoid(g_i);
+                let f = sigmoid(g_f);
+                let candidate = g_c.mapv(|x| activation.f(x));
+                let o = sigmoid(g_o);
+
+                let d_o = dh * &c.mapv(|x| activation.f(x)) * sigmoid_d(g_o);
+                let dc_total = &*dc + &(dh * &o * c.mapv(|x| activation.d(x)));
+                let d_i = &dc_total * &candidate * sigmoid_d(g_i);
+                let d_f = &dc_total * &c_prev * sigmoid_d(g_f);
+                let d_c = &dc_total * &i * g_c.mapv(|x| activation.d(x));
+                *dc = dc_total * f;
+
+                let dg = concatenate![Axis(1), d_i, d_f, d_c, d_o];
+                general_mat_mul(1.0, &h_prev.t(), &dg, 1.0, nabla_wh);
+                let dh_prev = dg.dot(&wh.t());
+                (dg, dh_prev)
+            }
+            Cell::Gru => {
+                let (z_pre, r_pre) = (cache.slice(s![.., ..h]), cache.slice(s![.., h..2 * h]));
+                let n_pre = cache.slice(s![.., 2 * h..3 * h]);
+                let (wh_zr, wh_n) = wh.split_at(Axis(1), 2 * h);
+
+                let z = sigmoid(z_pre);
+                let r = sigmoid(r_pre);
+                let n = n_pre.mapv(|x| activation.f(x));
+
+                let d_n = dh * &(1.0 - &z) * n_pre.mapv(|x| activation.d(x));
+                let d_z = dh * &(&h_prev - &n) * sigmoid_d(z_pre);
+                let d_rh = d_n.dot(&wh_n.t());
+                let d_r = &d_rh * &h_prev * sigmoid_d(r_pre);
+                let d_zr = concatenate![Axis(1), d_z, d_r];
+
+                let (mut nabla_zr, mut nabla_n) = nabla_wh.view_mut().split_at(Axis(1), 2 * h);
+                general_mat_mul(1.0, &h_prev.t(), &d_zr, 1.0, &mut nabla_zr);
+                general_mat_mul(1.0, &(&r * &h_prev).t(), &d_n, 1.0, &mut nabla_n);
+
+                let dh_prev = dh * &z + &d_rh * &r + d_zr.dot(&wh_zr.t());
+                (concatenate![Axis(1), d_zr, d_n], dh_prev)
+            }
+        }
+    }
+}
+
+impl Layer for RecurrentLayer {
+    fn name(&self) -> &'static str {
+        match self.cell {
+            Cell::SimpleRnn => "simple_rnn",
+            Cell::Lstm => "lstm",
+            Cell::Gru => "gru",
+        }
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        match self.return_sequences {
+            true => vec![self.steps, self.hidden],
+            false => vec![self.hidden],
+        }
+    }
+
+    // The shape is given by the constructor, the sizes only have to agree with it
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+
+        let (f, h) = (self.features, self.hidden);
+        let columns = self.cell.gates() * h;
+        let normal = Normal::new(0.0, 1.0).unwrap();
+
+        self.weights = Array2::zeros((f + h, columns));
+        self.weights
+            .slice_mut(s![..f, ..])
+            .assign(&(Array2::random((f, columns), normal) / (f as f64).sqrt()));
+        self.weights
+            .slice_mut(s![f.., ..])
+            .assign(&(Array2::random((h, columns), normal) / (h as f64).sqrt()));
+
+        self.biases = Array2::zeros((1, columns));
+        // start out remembering, so gradients flow through the cell state
+        if self.cell == Cell::Lstm {
+            self.biases.slice_mut(s![.., h..2 * h]).fill(1.0);
+        }
+    }
+
+    fn get_size(&self) -> usize {
+        self.steps * self.features
+    }
+
+    fn get_output_size(&self) -> usize {
+        if self.return_sequences {
+            self.steps * self.hidden
+        } else {
+            self.hidden
+        }
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        self.activation.as_ref()
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        &self.biases
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.weights
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        &mut self.biases
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.weights = weights;
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.biases = biases;
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        let mut z = Array2::zeros((0, 0));
+        let mut output = Array2::zeros((0, 0));
+        self.forward_into(input, &mut z, &mut output);
+        output
+    }
+
+    // The cache of all time steps: gate pre-activations and states
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        let mut z = Array2::zeros((0, 0));
+        let mut output = Array2::zeros((0, 0));
+        self.forward_into(input, &mut z, &mut output);
+        z
+    }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let (f, h, width) = (self.features, self.hidden, self.cache_width());
+        ensure_shape(z, (input.nrows(), self.steps * width));
+        ensure_shape(output, (input.nrows(), self.get_output_size()));
+
+        let (wx, wh) = self.split_weights();
+        for t in 0..self.steps {
+            let xw = input.slice(s![.., t * f..(t + 1) * f]).dot(&wx) + &self.biases;
+            let previous = self.previous(z, t);
+            let cache = self.step_forward(xw, wh, &previous);
+            z.slice_mut(s![.., t * width..(t + 1) * width])
+                .assign(&cache);
+        }
+
+        let state = |t: usize| z.slice(s![.., (t + 1) * width - h..(t + 1) * width]);
+        if self.return_sequences {
+            for t in 0..self.steps {
+                output
+                    .slice_mut(s![.., t * h..(t + 1) * h])
+                    .assign(&state(t));
+            }
+        } else {
+            output.assign(&state(self.steps - 1));
+        }
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        mut input_delta: Option<&mut Array2<f64>>,
+    ) {
+        let (f, h) = (self.features, self.hidden);
+        let (wx, wh) = self.split_weights();
+
+        nabla_w.fill(0.0);
+        nabla_b.fill(0.0);
+        if let Some(input_delta) = input_delta.as_mut() {
+            ensure_shape(input_delta, input.dim());
+        }
+
+        let (mut nabla_wx, mut nabla_wh) = nabla_w.view_mut().split_at(Axis(0), f);
+        let mut dh_next = Array2::zeros((input.nrows(), h));
+        let mut dc = Array2::zeros((input.nrows(), h));
+
+        for t in (0..self.steps).rev() {
+            let mut dh = dh_next;
+            if self.return_sequences {
+                dh += &delta.slice(s![.., t * h..(t + 1) * h]);
+            } else if t == self.steps - 1 {
+                dh += &*delta;
+            }
+
+            let previous = self.previous(z, t);
+            let (dg, dh_prev) = self.step_backward(
+                wh,
+                &previous,
+                self.step_cache(z, t),
+                &dh,
+                &mut dc,
+                &mut nabla_wh,
+            );
+
+            let x = input.slice(s![.., t * f..(t + 1) * f]);
+            general_mat_mul(1.0, &x.t(), &dg, 1.0, &mut nabla_wx);
+            let mut nabla_b_row = nabla_b.row_mut(0);
+            nabla_b_row += &dg.sum_axis(Axis(0));
+
+            if let Some(input_delta) = input_delta.as_mut() {
+                let mut dx = input_delta.slice_mut(s![.., t * f..(t + 1) * f]);
+                general_mat_mul(1.0, &dg, &wx.t(), 0.0, &mut dx);
+            }
+
+            dh_next = dh_prev;
+        }
+    }
+}
+
+fn sigmoid(x: ArrayView2<f64>) -> Array2<f64> {
+    x.mapv(|x| Sigmoid.f(x))
+}
+
+fn sigmoid_d(x: ArrayView2<f64>) -> Array2<f64> {
+    x.mapv(|x| Sigmoid.d(x))
+}
@@ -0,0 +1,142 @@
+use ndarray::Array2;
+
+use super::{DenseLayer, Layer};
+use crate::neural_network::{activation_function::ActivationFunction, workspace::ensure_shape};
+
+// Applies the same dense layer to every step of a sequence, like the
+// feed-forward part of a transformer. Input and output are flattened
+// sequences of `steps` vectors, the weights are those of a single step.
+pub struct TimeDistributed {
+    pub steps: usize,
+    pub dense: DenseLayer,
+}
+
+impl TimeDistributed {
+    // A dense layer from `features` to `size` values per step
+    pub fn new(
+        steps: usize,
+        features: usize,
+        size: usize,
+        activation: Box<dyn ActivationFunction>,
+    ) -> TimeDistributed {
+        let mut dense = DenseLayer::new(features, activation);
+        dense.initialize(features, size);
+        TimeDistributed { steps, dense }
+    }
+
+    // (batch, steps * n) -> (batch * steps, n), one row per step
+    fn per_step(&self, data: &Array2<f64>) -> Array2<f64> {
+        let rows = data.nrows() * self.steps;
+        let columns = data.ncols() / self.steps;
+        data.as_standard_layout()
+            .into_owned()
+            .into_shape((rows, columns))
+            .unwrap()
+    }
+
+    // (batch * steps, n) -> (batch, steps * n)
+    fn per_sample(&self, data: Array2<f64>) -> Array2<f64> {
+        let rows = data.nrows() / self.steps;
+        let columns = data.ncols() * self.steps;
+        data.into_shape((rows, columns)).unwrap()
+    }
+}
+
+impl Layer for TimeDistributed {
+    fn name(&self) -> &'static str {
+        "time_distributed"
+    }
+
+    fn output_shape(&self) -> Vec<usize> {
+        vec![self.steps, self.dense.get_output_size()]
+    }
+
+    // The dense layer is sized by `new`
+    fn initialize(&mut self, input_size: usize, output_size: usize) {
+        debug_assert_eq!(input_size, self.get_size());
+        debug_assert_eq!(output_size, self.get_output_size());
+
+        let (features, size) = self.dense.weights.dim();
+        self.dense.initialize(features, size);
+    }
+
+    fn get_size(&self) -> usize {
+        self.steps * self.dense.get_size()
+    }
+
+    fn get_output_size(&self) -> usize {
+        self.steps * self.dense.get_output_size()
+    }
+
+    fn get_activation(&self) -> &dyn ActivationFunction {
+        self.dense.get_activation()
+    }
+
+    fn get_weights(&self) -> &Array2<f64> {
+        self.dense.get_weights()
+    }
+
+    fn get_bias(&self) -> &Array2<f64> {
+        self.dense.get_bias()
+    }
+
+    fn get_weights_mut(&mut self) -> &mut Array2<f64> {
+        self.dense.get_weights_mut()
+    }
+
+    fn get_bias_mut(&mut self) -> &mut Array2<f64> {
+        self.dense.get_bias_mut()
+    }
+
+    fn set_weights(&mut self, weights: Array2<f64>) {
+        self.dense.set_weights(weights);
+    }
+
+    fn set_bias(&mut self, biases: Array2<f64>) {
+        self.dense.set_bias(biases);
+    }
+
+    fn predict(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.per_sample(self.dense.predict(&self.per_step(input)))
+    }
+
+    fn forward(&self, input: &Array2<f64>) -> Array2<f64> {
+        self.per_sample(self.dense.forward(&self.per_step(input)))
+    }
+
+    fn forward_into(&self, input: &Array2<f64>, z: &mut Array2<f64>, output: &mut Array2<f64>) {
+        let mut step_z = Array2::zeros((0, 0));
+        let mut step_output = Array2::zeros((0, 0));
+        self.dense
+            .forward_into(&self.per_step(input), &mut step_z, &mut step_output);
+
+        *z = self.per_sample(step_z);
+        *output = self.per_sample(step_output);
+    }
+
+    fn backward_into(
+        &self,
+        input: &Array2<f64>,
+        z: &Array2<f64>,
+        delta: &mut Array2<f64>,
+        nabla_w: &mut Array2<f64>,
+        nabla_b: &mut Array2<f64>,
+        input_delta: Option<&mut Array2<f64>>,
+    ) {
+        let mut step_delta = self.per_step(delta);
+        let mut step_input_delta = Array2::zeros((0, 0));
+        self.dense.backward_into(
+            &self.per_step(input),
+            &self.per_step(z),
+            &mut step_delta,
+            nabla_w,
+            nabla_b,
+            input_delta.is_some().then_some(&mut step_input_delta),
+        );
+
+        if let Some(input_delta) = input_delta {
+            ensure_shape(input_delta, input.dim());
+            input_delta.assign(&self.per_sample(step_input_delta));
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use ndarray::Array2;
+
+use super::{workspace::Workspace, Network, Recorder};
+use crate::{dataset::Dataset, error::Result};
+
+// The kinds of values whose distribution is recorded per layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    Weights,
+    Biases,
+    WeightGradients,
+    BiasGradients,
+    Activations,
+}
+
+impl Distribution {
+    pub const ALL: [Distribution; 5] = [
+        Distribution::Weights,
+        Distribution::Biases,
+        Distribution::WeightGradients,
+        Distribution::BiasGradients,
+        Distribution::Activations,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Distribution::Weights => "weights",
+            Distribution::Biases => "biases",
+            Distribution::WeightGradients => "weight_gradients",
+            Distribution::BiasGradients => "bias_gradients",
+            Distribution::Activations => "activations",
+        }
+    }
+}
+
+// The values of one layer at one point of the training
+#[derive(Debug, Clone)]
+pub struct LayerValues {
+    pub weights: Vec<f64>,
+    pub biases: Vec<f64>,
+    pub weight_gradients: Vec<f64>,
+    pub bias_gradients: Vec<f64>,
+    pub activations: Vec<f64>,
+    // Units that output zero for every probe sample
+    pub dead_units: usize,
+    pub units: usize,
+}
+
+impl LayerValues {
+    pub fn values(&self, distribution: Distribution) -> &[f64] {
+        match distribution {
+            Distribution::Weights => &self.weights,
+            Distribution::Biases => &self.biases,
+            Distribution::WeightGradients => &self.weight_gradients,
+            Distribution::BiasGradients => &self.bias_gradients,
+            Distribution::Activations => &self.activations,
+        }
+    }
+}
+
+pub struct Snapshot {
+    pub epoch: i32,
+    pub cost: f64,
+    pub layers: Vec<LayerValues>,
+}
+
+// Counts of values in evenly sized bins. Non-finite values, e.g. after the
+// training diverged, are counted on their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub range: (f64, f64),
+    pub counts: Vec<usize>,
+    pub non_finite: usize,
+}
+
+impl Histogram {
+    pub fn new(values: &[f64], range: (f64, f64), bins: usize) -> Histogram {
+        let bins = bins.max(1);
+        let mut counts = vec![0; bins];
+        let (min, max) = range;
+        let width = (max - min) / bins as f64;
+        let mut non_finite = 0;
+
+        for &value in values {
+            if !value.is_finite() {
+                non_finite += 1;
+            } else if value >= min && value <= max {
+                let bin = match width > 0.0 {
+                    true => ((value - min) / width) as usize,
+                    false => 0,
+                };
+                counts[bin.min(bins - 1)] += 1;
+            }
+        }
+
+        Histogram {
+            range,
+            counts,
+            non_finite,
+        }
+    }
+
+    // The smallest and largest finite value, if there are any
+    pub fn range_of<'a>(values: impl IntoIterator<Item = &'a f64>) -> Option<(f64, f64)> {
+        values
+            .into_iter()
+            .filter(|value| value.is_finite())
+            .fold(None, |range, &value| match range {
+                None => Some((value, value)),
+                Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+            })
+    }
+}
+
+// Records the per-layer distributions of weights, biases, gradients and
+// activations during training. Gradients and activations are computed by
+// `Network::backprop_into` on the same probe batch every time, so snapshots
+// are comparable:
+//
+//     let mut monitor = DistributionMonitor::from_dataset(50, &CIRCLE, 256)?;
+//     network.train_and_record(&CIRCLE, 32, 128, 1000, &mut monitor)?;
+//     println!("{}", monitor.dead_units().unwrap());
+//     DistributionPlot::new(&monitor, Distribution::Weights)
+//         .plot(Path::new("images"), "circle")?;
+pub struct DistributionMonitor {
+    // A snapshot is taken every `every` epochs, values below 1 count as 1
+    pub every: i32,
+    probe: (Array2<f64>, Array2<f64>),
+    workspace: Workspace,
+    snapshots: Vec<Snapshot>,
+}
+
+impl DistributionMonitor {
+    pub fn new(every: i32, probe: (Array2<f64>, Array2<f64>)) -> DistributionMonitor {
+        DistributionMonitor {
+            every: every.max(1),
+            probe,
+            workspace: Workspace::new(),
+            snapshots: Vec::new(),
+        }
+    }
+
+    // Probes with `samples` samples of the dataset
+    pub fn from_dataset(every: i32, data: &Dataset, samples: usize) -> Result<DistributionMonitor> {
+        Ok(DistributionMonitor::new(every, data.get_batch(samples)?))
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    // The dead units of every layer in the last snapshot
+    pub fn dead_units(&self) -> Option<DeadUnitReport> {
+        self.snapshots.last().map(DeadUnitReport::new)
+    }
+
+    // The histograms of one layer over time, all with the same bins
+    pub fn histograms(
+        &self,
+        layer: usize,
+        distribution: Distribution,
+        bins: usize,
+    ) -> Vec<(i32, Histogram)> {
+        fn values(snapshot: &Snapshot, layer: usize, distribution: Distribution) -> &[f64] {
+            snapshot
+                .layers
+                .get(layer)
+                .map_or(&[][..], |values| values.values(distribution))
+        }
+
+        let range = Histogram::range_of(
+            self.snapshots
+                .iter()
+                .flat_map(|snapshot| values(snapshot, layer, distribution)),
+        )
+        .unwrap_or((0.0, 0.0));
+
+        self.snapshots
+            .iter()
+            .map(|snapshot| {
+                (
+                    snapshot.epoch,
+                    Histogram::new(values(snapshot, layer, distribution), range, bins),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Recorder for DistributionMonitor {
+    fn is_due(&self, epoch: i32, epochs: i32) -> bool {
+        epoch % self.every.max(1) == 0 || epoch == epochs - 1
+    }
+
+    fn record(&mut self, network: &Network, epoch: i32, cost: f64) -> Result<()> {
+        let (x, y) = &self.probe;
+        network.backprop_into(x, y, &mut self.workspace)?;
+
+        let workspace = &self.workspace;
+        let layers = network
+            .layers()
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| {
+                let activations = &workspace.activations()[i];
+                LayerValues {
+                    weights: layer.get_weights().iter().copied().collect(),
+                    biases: layer.get_bias().iter().copied().collect(),
+                    weight_gradients: workspace.nabla_ws()[i].iter().copied().collect(),
+                    bias_gradients: workspace.nabla_bs()[i].iter().copied().collect(),
+                    activations: activations.iter().copied().collect(),
+                    dead_units: dead_units(activations),
+                    units: activations.ncols(),
+                }
+            })
+            .collect();
+
+        self.snapshots.push(Snapshot {
+            epoch,
+            cost,
+            layers,
+        });
+
+        Ok(())
+    }
+}
+
+// The units (columns) that are zero for every sample. For ReLU layers these
+// get no gradient anymore and stay dead.
+pub fn dead_units(activations: &Array2<f64>) -> usize {
+    if activations.nrows() == 0 {
+        return 0;
+    }
+
+    activations
+        .columns()
+        .into_iter()
+        .filter(|unit| unit.iter().all(|&value| value == 0.0))
+        .count()
+}
+
+// The fraction of dead units per layer, printed as a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadUnitReport {
+    pub epoch: i32,
+    // (dead units, units) per layer
+    pub layers: Vec<(usize, usize)>,
+}
+
+impl DeadUnitReport {
+    pub fn new(snapshot: &Snapshot) -> DeadUnitReport {
+        DeadUnitReport {
+            epoch: snapshot.epoch,
+            layers: snapshot
+                .layers
+                .iter()
+                .map(|layer| (layer.dead_units, layer.units))
+                .collect(),
+        }
+    }
+
+    pub fn fractions(&self) -> Vec<f64> {
+        self.layers
+            .iter()
+            .map(|&(dead, units)| match units {
+                0 => 0.0,
+                _ => dead as f64 / units as f64,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for DeadUnitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Dead units at epoch {}:", self.epoch)?;
+        for (i, (&(dead, units), fraction)) in self.layers.iter().zip(self.fractions()).enumerate()
+        {
+            writeln!(
+                f,
+                "  layer {:>2}: {:>5} of {:>5} ({:>5.1}%)",
+                i,
+                dead,
+                units,
+                fraction * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
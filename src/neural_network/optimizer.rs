@@ -2,21 +2,60 @@ pub mod adam_optimizer;
 pub mod rmsprop_optimizer;
 pub mod sgd_optimzer;
 
+use std::ops::Range;
+
+use itertools::Either;
 use ndarray::Array2;
 
 use crate::neural_network::{layer::Layer, Summary};
 
-pub trait Optimizer: Summary {
+pub trait Optimizer: Summary + Send + Sync {
+    // `sparse_rows` holds, per layer, the only rows of the weights with a
+    // non-zero gradient, see `Layer::sparse_rows`. Only those rows are updated.
     fn update_params(
         &mut self,
-        layers: &mut Vec<Box<dyn Layer>>,
-        nabla_bs: &Vec<Array2<f64>>,
-        nabla_ws: &Vec<Array2<f64>>,
+        layers: &mut [Box<dyn Layer>],
+        nabla_bs: &[Array2<f64>],
+        nabla_ws: &[Array2<f64>],
+        sparse_rows: &[Option<Vec<usize>>],
     );
 
-    fn initialize(&mut self, layers: &Vec<Box<dyn Layer>>);
+    fn initialize(&mut self, layers: &[Box<dyn Layer>]);
 
     fn pre_update(&mut self);
 
     fn post_update(&mut self);
+
+    // Multiplies the base learning rate, e.g. to slow down a diverging run
+    fn scale_learning_rate(&mut self, factor: f64);
+
+    // The learning rate used by the latest update, after decay
+    fn current_learning_rate(&self) -> f64;
+
+    // Everything an update changes besides the parameters, so a diverged step
+    // can be undone together with them
+    fn state_mut(&mut self) -> OptimizerState<'_>;
+}
+
+// Borrowed state of an optimizer. Every set holds one array per layer, e.g.
+// the momentum or the cache, sets that an optimizer doesn't need are empty.
+// The sets always come in the same order.
+pub struct OptimizerState<'a> {
+    pub iteration: &'a mut usize,
+    // Sets of arrays with the shape of the weights
+    pub weights: [&'a mut [Array2<f64>]; 2],
+    // Sets of arrays with the shape of the biases
+    pub biases: [&'a mut [Array2<f64>]; 2],
+}
+
+// The rows of the weights to update: one range covering all of them, or a
+// range per row of a layer with sparse gradients
+pub(crate) fn row_ranges(
+    sparse_rows: Option<&[usize]>,
+    rows: usize,
+) -> impl Iterator<Item = Range<usize>> + '_ {
+    match sparse_rows {
+        None => Either::Left(std::iter::once(0..rows)),
+        Some(sparse_rows) => Either::Right(sparse_rows.iter().map(|&row| row..row + 1)),
+    }
 }
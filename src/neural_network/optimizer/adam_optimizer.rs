@@ -1,9 +1,9 @@
 use itertools::izip;
-use ndarray::Array2;
+use ndarray::{s, Array2, Zip};
 
 use crate::neural_network::{layer::Layer, Summary};
 
-use super::Optimizer;
+use super::{row_ranges, Optimizer, OptimizerState};
 
 #[allow(non_camel_case_types)]
 pub struct ADAM {
@@ -36,8 +36,10 @@ impl ADAM {
             beta_2,
         }
     }
+}
 
-    pub fn default() -> ADAM {
+impl Default for ADAM {
+    fn default() -> ADAM {
         ADAM::new(0.002, 1e-5, 1e-7, 0.9, 0.999)
     }
 }
@@ -45,48 +47,47 @@ impl ADAM {
 impl Optimizer for ADAM {
     fn update_params(
         &mut self,
-        layers: &mut Vec<Box<dyn Layer>>,
-        nabla_bs: &Vec<Array2<f64>>,
-        nabla_ws: &Vec<Array2<f64>>,
+        layers: &mut [Box<dyn Layer>],
+        nabla_bs: &[Array2<f64>],
+        nabla_ws: &[Array2<f64>],
+        sparse_rows: &[Option<Vec<usize>>],
     ) {
-        for (i, (layer, nabla_b, nabla_w)) in izip!(layers, nabla_bs, nabla_ws).enumerate() {
-            //update momentum
-            self.weights_momentum[i] =
-                self.beta_1 * &self.weights_momentum[i] + (1.0 - self.beta_1) * nabla_w;
-
-            self.biases_momentum[i] =
-                self.beta_1 * &self.biases_momentum[i] + (1.0 - self.beta_1) * nabla_b;
-
-            //update cache
-            self.weights_cache[i] =
-                self.beta_2 * &self.weights_cache[i] + (1.0 - self.beta_2) * (nabla_w * nabla_w);
-
-            self.biases_cache[i] =
-                self.beta_2 * &self.biases_cache[i] + (1.0 - self.beta_2) * (nabla_b * nabla_b);
+        for (i, (layer, nabla_b, nabla_w, sparse_rows)) in
+            izip!(layers, nabla_bs, nabla_ws, sparse_rows).enumerate()
+        {
+            let nrows = nabla_w.nrows();
 
             //corrections
-            let weights_momentum_corrected =
-                &self.weights_momentum[i] / (1.0 - self.beta_1.powi(i as i32 + 1));
-            let biases_momentum_corrected =
-                &self.biases_momentum[i] / (1.0 - self.beta_1.powi(i as i32 + 1));
-            let weights_cache_corrected =
-                &self.weights_cache[i] / (1.0 - self.beta_2.powi(i as i32 + 1));
-            let biases_cache_corrected =
-                &self.biases_cache[i] / (1.0 - self.beta_2.powi(i as i32 + 1));
-
-            let weights_update = self.current_learning_rate * weights_momentum_corrected
-                / (weights_cache_corrected.mapv(f64::sqrt) + self.epsilon);
-            let biases_update = self.current_learning_rate * biases_momentum_corrected
-                / (biases_cache_corrected.mapv(f64::sqrt) + self.epsilon);
-
-            //updates
-
-            layer.set_weights(layer.get_weights() - &weights_update);
-            layer.set_bias(layer.get_bias() - &biases_update);
+            let momentum_correction = 1.0 / (1.0 - self.beta_1.powi(i as i32 + 1));
+            let cache_correction = 1.0 / (1.0 - self.beta_2.powi(i as i32 + 1));
+
+            let step = |param: &mut f64, momentum: &mut f64, cache: &mut f64, &nabla: &f64| {
+                //update momentum and cache
+                *momentum = self.beta_1 * *momentum + (1.0 - self.beta_1) * nabla;
+                *cache = self.beta_2 * *cache + (1.0 - self.beta_2) * nabla * nabla;
+
+                //update
+                *param -= self.current_learning_rate * (*momentum * momentum_correction)
+                    / ((*cache * cache_correction).sqrt() + self.epsilon);
+            };
+
+            for rows in row_ranges(sparse_rows.as_deref(), nrows) {
+                Zip::from(layer.get_weights_mut().slice_mut(s![rows.clone(), ..]))
+                    .and(self.weights_momentum[i].slice_mut(s![rows.clone(), ..]))
+                    .and(self.weights_cache[i].slice_mut(s![rows.clone(), ..]))
+                    .and(nabla_w.slice(s![rows, ..]))
+                    .for_each(step);
+            }
+
+            Zip::from(layer.get_bias_mut())
+                .and(&mut self.biases_momentum[i])
+                .and(&mut self.biases_cache[i])
+                .and(nabla_b)
+                .for_each(step);
         }
     }
 
-    fn initialize(&mut self, layers: &Vec<Box<dyn Layer>>) {
+    fn initialize(&mut self, layers: &[Box<dyn Layer>]) {
         for layer in layers {
             self.weights_cache
                 .push(Array2::zeros(layer.get_weights().dim()));
@@ -109,6 +110,23 @@ impl Optimizer for ADAM {
     fn post_update(&mut self) {
         self.iteration += 1;
     }
+
+    fn scale_learning_rate(&mut self, factor: f64) {
+        self.learning_rate *= factor;
+        self.current_learning_rate *= factor;
+    }
+
+    fn current_learning_rate(&self) -> f64 {
+        self.current_learning_rate
+    }
+
+    fn state_mut(&mut self) -> OptimizerState<'_> {
+        OptimizerState {
+            iteration: &mut self.iteration,
+            weights: [&mut self.weights_momentum, &mut self.weights_cache],
+            biases: [&mut self.biases_momentum, &mut self.biases_cache],
+        }
+    }
 }
 
 impl Summary for ADAM {
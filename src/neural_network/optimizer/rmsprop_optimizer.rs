@@ -1,9 +1,9 @@
 use itertools::izip;
-use ndarray::Array2;
+use ndarray::{s, Array2, Zip};
 
 use crate::neural_network::{layer::Layer, Summary};
 
-use super::Optimizer;
+use super::{row_ranges, Optimizer, OptimizerState};
 
 #[allow(non_camel_case_types)]
 pub struct RMS_PROP {
@@ -30,8 +30,10 @@ impl RMS_PROP {
             rho,
         }
     }
+}
 
-    pub fn default() -> RMS_PROP {
+impl Default for RMS_PROP {
+    fn default() -> RMS_PROP {
         RMS_PROP::new(0.001, 1e-4, 1e-7, 0.9)
     }
 }
@@ -39,31 +41,39 @@ impl RMS_PROP {
 impl Optimizer for RMS_PROP {
     fn update_params(
         &mut self,
-        layers: &mut Vec<Box<dyn Layer>>,
-        nabla_bs: &Vec<Array2<f64>>,
-        nabla_ws: &Vec<Array2<f64>>,
+        layers: &mut [Box<dyn Layer>],
+        nabla_bs: &[Array2<f64>],
+        nabla_ws: &[Array2<f64>],
+        sparse_rows: &[Option<Vec<usize>>],
     ) {
-        for (i, (layer, nabla_b, nabla_w)) in izip!(layers, nabla_bs, nabla_ws).enumerate() {
-            //update cache
-            self.weights_cache[i] =
-                self.rho * &self.weights_cache[i] + (1.0 - self.rho) * (nabla_w * nabla_w);
-            self.biases_cache[i] =
-                self.rho * &self.biases_cache[i] + (1.0 - self.rho) * (nabla_b * nabla_b);
-
-            //calculate updates
-            let weights_update = -self.current_learning_rate * nabla_w
-                / (self.weights_cache[i].mapv(|x| x.sqrt()) + self.epsilon);
-            let biases_update = -self.current_learning_rate * nabla_b
-                / (self.biases_cache[i].mapv(|x| x.sqrt()) + self.epsilon);
-
-            //Update weights and biases
-
-            layer.set_weights(layer.get_weights() + weights_update);
-            layer.set_bias(layer.get_bias() + biases_update);
+        for (i, (layer, nabla_b, nabla_w, sparse_rows)) in
+            izip!(layers, nabla_bs, nabla_ws, sparse_rows).enumerate()
+        {
+            let nrows = nabla_w.nrows();
+
+            let step = |param: &mut f64, cache: &mut f64, &nabla: &f64| {
+                //update cache
+                *cache = self.rho * *cache + (1.0 - self.rho) * nabla * nabla;
+
+                //update weights and biases
+                *param -= self.current_learning_rate * nabla / (cache.sqrt() + self.epsilon);
+            };
+
+            for rows in row_ranges(sparse_rows.as_deref(), nrows) {
+                Zip::from(layer.get_weights_mut().slice_mut(s![rows.clone(), ..]))
+                    .and(self.weights_cache[i].slice_mut(s![rows.clone(), ..]))
+                    .and(nabla_w.slice(s![rows, ..]))
+                    .for_each(step);
+            }
+
+            Zip::from(layer.get_bias_mut())
+                .and(&mut self.biases_cache[i])
+                .and(nabla_b)
+                .for_each(step);
         }
     }
 
-    fn initialize(&mut self, layers: &Vec<Box<dyn Layer>>) {
+    fn initialize(&mut self, layers: &[Box<dyn Layer>]) {
         for layer in layers {
             self.weights_cache
                 .push(Array2::zeros(layer.get_weights().dim()));
@@ -82,6 +92,23 @@ impl Optimizer for RMS_PROP {
     fn post_update(&mut self) {
         self.iteration += 1;
     }
+
+    fn scale_learning_rate(&mut self, factor: f64) {
+        self.learning_rate *= factor;
+        self.current_learning_rate *= factor;
+    }
+
+    fn current_learning_rate(&self) -> f64 {
+        self.current_learning_rate
+    }
+
+    fn state_mut(&mut self) -> OptimizerState<'_> {
+        OptimizerState {
+            iteration: &mut self.iteration,
+            weights: [&mut self.weights_cache, &mut []],
+            biases: [&mut self.biases_cache, &mut []],
+        }
+    }
 }
 
 impl Summary for RMS_PROP {
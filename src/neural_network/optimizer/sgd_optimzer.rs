@@ -1,9 +1,9 @@
 use itertools::izip;
-use ndarray::Array2;
+use ndarray::{s, Array2, Zip};
 
 use crate::neural_network::{layer::Layer, Summary};
 
-use super::Optimizer;
+use super::{row_ranges, Optimizer, OptimizerState};
 
 pub struct SGD {
     momentum: f64,
@@ -27,8 +27,10 @@ impl SGD {
             biases_momentum: Vec::new(),
         }
     }
+}
 
-    pub fn default() -> SGD {
+impl Default for SGD {
+    fn default() -> SGD {
         SGD::new(0.1, 0.5, 0.0005)
     }
 }
@@ -36,34 +38,50 @@ impl SGD {
 impl Optimizer for SGD {
     fn update_params(
         &mut self,
-        layers: &mut Vec<Box<dyn Layer>>,
-        nabla_bs: &Vec<Array2<f64>>,
-        nabla_ws: &Vec<Array2<f64>>,
+        layers: &mut [Box<dyn Layer>],
+        nabla_bs: &[Array2<f64>],
+        nabla_ws: &[Array2<f64>],
+        sparse_rows: &[Option<Vec<usize>>],
     ) {
-        for (i, (layer, nabla_b, nabla_w)) in izip!(layers, nabla_bs, nabla_ws).enumerate() {
-            //Calculate standart update_params
-            let mut weights_update = -self.current_learning_rate * nabla_w;
-            let mut biases_update = -self.current_learning_rate * nabla_b;
+        for (i, (layer, nabla_b, nabla_w, sparse_rows)) in
+            izip!(layers, nabla_bs, nabla_ws, sparse_rows).enumerate()
+        {
+            let nrows = nabla_w.nrows();
 
-            //Add momentum
             if self.momentum > 0.0 {
-                let weights_momentum = &self.weights_momentum[i];
-                let biases_momentum = &self.biases_momentum[i];
+                //Standard update plus momentum, which remembers the last update
+                let step = |param: &mut f64, momentum: &mut f64, &nabla: &f64| {
+                    *momentum = -self.current_learning_rate * nabla + self.momentum * *momentum;
+                    *param += *momentum;
+                };
 
-                weights_update = weights_update + self.momentum * weights_momentum;
-                biases_update = biases_update + self.momentum * biases_momentum;
+                for rows in row_ranges(sparse_rows.as_deref(), nrows) {
+                    Zip::from(layer.get_weights_mut().slice_mut(s![rows.clone(), ..]))
+                        .and(self.weights_momentum[i].slice_mut(s![rows.clone(), ..]))
+                        .and(nabla_w.slice(s![rows, ..]))
+                        .for_each(step);
+                }
 
-                self.weights_momentum[i] = weights_update.clone();
-                self.biases_momentum[i] = biases_update.clone();
+                Zip::from(layer.get_bias_mut())
+                    .and(&mut self.biases_momentum[i])
+                    .and(nabla_b)
+                    .for_each(step);
+            } else {
+                //Standard update
+                for rows in row_ranges(sparse_rows.as_deref(), nrows) {
+                    layer
+                        .get_weights_mut()
+                        .slice_mut(s![rows.clone(), ..])
+                        .scaled_add(-self.current_learning_rate, &nabla_w.slice(s![rows, ..]));
+                }
+                layer
+                    .get_bias_mut()
+                    .scaled_add(-self.current_learning_rate, nabla_b);
             }
-
-            //Update weights and biases
-            layer.set_weights(layer.get_weights() + weights_update);
-            layer.set_bias(layer.get_bias() + biases_update);
         }
     }
 
-    fn initialize(&mut self, layers: &Vec<Box<dyn Layer>>) {
+    fn initialize(&mut self, layers: &[Box<dyn Layer>]) {
         for layer in layers {
             self.weights_momentum
                 .push(Array2::zeros(layer.get_weights().dim()));
@@ -82,6 +100,23 @@ impl Optimizer for SGD {
     fn post_update(&mut self) {
         self.iteration += 1;
     }
+
+    fn scale_learning_rate(&mut self, factor: f64) {
+        self.learning_rate *= factor;
+        self.current_learning_rate *= factor;
+    }
+
+    fn current_learning_rate(&self) -> f64 {
+        self.current_learning_rate
+    }
+
+    fn state_mut(&mut self) -> OptimizerState<'_> {
+        OptimizerState {
+            iteration: &mut self.iteration,
+            weights: [&mut self.weights_momentum, &mut []],
+            biases: [&mut self.biases_momentum, &mut []],
+        }
+    }
 }
 
 impl Summary for SGD {
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use super::layer::Layer;
+
+// One node of a model: an input, a layer or a merge of several nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryRow {
+    // The kind of node, e.g. "input", "dense" or "add"
+    pub kind: String,
+    // The shape of the output of one sample
+    pub output_shape: Vec<usize>,
+    pub activation: Option<String>,
+    pub params: usize,
+    // The rows this node takes its input from
+    pub inputs: Vec<usize>,
+}
+
+impl SummaryRow {
+    pub fn input(size: usize) -> SummaryRow {
+        SummaryRow {
+            kind: String::from("input"),
+            output_shape: vec![size],
+            activation: None,
+            params: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn layer(layer: &dyn Layer, input: usize) -> SummaryRow {
+        SummaryRow {
+            kind: layer.name().to_string(),
+            output_shape: layer.output_shape(),
+            activation: layer.activation_name().map(str::to_string),
+            params: layer.get_weights().len() + layer.get_bias().len(),
+            inputs: vec![input],
+        }
+    }
+
+    // The kind and activation, e.g. "dense (relu)"
+    pub fn title(&self) -> String {
+        match &self.activation {
+            Some(activation) => format!("{} ({})", self.kind, activation),
+            None => self.kind.clone(),
+        }
+    }
+
+    // The output shape and the parameter count, e.g. "(8, 16), 272 params"
+    pub fn details(&self) -> String {
+        format!("{}, {} params", shape(&self.output_shape), self.params)
+    }
+}
+
+// A model node by node, printed as a table:
+//
+//     println!("{}", network.summary());
+//
+// `to_dot` describes the architecture for Graphviz, `plot_architecture` draws
+// it as a diagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSummary {
+    // The optimizer and shape, as in `Summary::summerize`
+    pub title: String,
+    pub rows: Vec<SummaryRow>,
+    // The rows that are outputs of the model
+    pub outputs: Vec<usize>,
+}
+
+impl ModelSummary {
+    // All weights and biases are trained
+    pub fn trainable_params(&self) -> usize {
+        self.rows.iter().map(|row| row.params).sum()
+    }
+
+    // Whether every node takes the one before it as its only input
+    pub fn is_chain(&self) -> bool {
+        self.rows.iter().enumerate().all(|(i, row)| match i {
+            0 => row.inputs.is_empty(),
+            _ => row.inputs == [i - 1],
+        })
+    }
+
+    // The architecture as a Graphviz digraph, rendered with e.g.
+    // `dot -Tsvg model.dot -o model.svg`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph model {\n    rankdir=TB;\n    node [shape=record];\n");
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let style = match self.outputs.contains(&i) {
+                true => ", style=bold",
+                false => "",
+            };
+            dot += &format!(
+                "    n{} [label=\"{{{}|{}}}\"{}];\n",
+                i,
+                row.title(),
+                row.details(),
+                style
+            );
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            for input in &row.inputs {
+                dot += &format!("    n{} -> n{};\n", input, i);
+            }
+        }
+
+        dot + "}\n"
+    }
+}
+
+fn shape(shape: &[usize]) -> String {
+    let sizes = shape.iter().map(usize::to_string).collect::<Vec<_>>();
+    format!("({})", sizes.join(", "))
+}
+
+impl fmt::Display for ModelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let connections = !self.is_chain();
+
+        let mut header = vec!["#", "Layer", "Output shape", "Activation", "Params"];
+        if connections {
+            header.push("Inputs");
+        }
+
+        let rows = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut cells = vec![
+                    i.to_string(),
+                    row.kind.clone(),
+                    shape(&row.output_shape),
+                    row.activation.clone().unwrap_or_else(|| String::from("-")),
+                    row.params.to_string(),
+                ];
+                if connections {
+                    let inputs = row.inputs.iter().map(usize::to_string).collect::<Vec<_>>();
+                    cells.push(inputs.join(", "));
+                }
+                cells
+            })
+            .collect::<Vec<_>>();
+
+        let widths = (0..header.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|cells| cells[column].len())
+                    .chain([header[column].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        let rule = "-".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1));
+
+        let line = |f: &mut fmt::Formatter<'_>, cells: &[&str]| {
+            let padded = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", padded.join("  ").trim_end())
+        };
+
+        writeln!(f, "Model: {}", self.title)?;
+        writeln!(f, "{}", rule)?;
+        line(f, &header)?;
+        writeln!(f, "{}", rule)?;
+        for cells in &rows {
+            line(f, &cells.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        writeln!(f, "{}", rule)?;
+        writeln!(f, "Trainable params: {}", self.trainable_params())
+    }
+}
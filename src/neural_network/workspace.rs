@@ -0,0 +1,86 @@
+use ndarray::Array2;
+
+use super::{layer::Layer, Gradients};
+
+// Preallocated buffers for a training step. The buffers are sized on first use
+// and only reallocated if the batch size or the network shape changes, so
+// repeated training steps reuse the same memory.
+#[derive(Default)]
+pub struct Workspace {
+    pub(crate) zs: Vec<Array2<f64>>,
+    pub(crate) activations: Vec<Array2<f64>>,
+    pub(crate) deltas: Vec<Array2<f64>>,
+    pub(crate) nabla_ws: Vec<Array2<f64>>,
+    pub(crate) nabla_bs: Vec<Array2<f64>>,
+    // The rows with a non-zero weight gradient of layers with sparse gradients
+    pub(crate) sparse_rows: Vec<Option<Vec<usize>>>,
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    // Makes sure there is one set of buffers per layer and that the gradient
+    // buffers match the shape of the layer parameters
+    pub(crate) fn prepare(&mut self, layers: &[Box<dyn Layer>]) {
+        let n = layers.len();
+        for buffers in [
+            &mut self.zs,
+            &mut self.activations,
+            &mut self.deltas,
+            &mut self.nabla_ws,
+            &mut self.nabla_bs,
+        ] {
+            buffers.resize_with(n, || Array2::zeros((0, 0)));
+        }
+        self.sparse_rows.resize(n, None);
+
+        for (i, layer) in layers.iter().enumerate() {
+            // layers with sparse gradients expect the other rows to be zero,
+            // so only the rows of the last step have to be cleared
+            if let Some(rows) = &self.sparse_rows[i] {
+                if self.nabla_ws[i].dim() == layer.get_weights().dim() {
+                    for &row in rows {
+                        self.nabla_ws[i].row_mut(row).fill(0.0);
+                    }
+                }
+            }
+            ensure_shape(&mut self.nabla_ws[i], layer.get_weights().dim());
+            ensure_shape(&mut self.nabla_bs[i], layer.get_bias().dim());
+        }
+    }
+
+    // The output of the network from the last forward pass
+    pub fn output(&self) -> &Array2<f64> {
+        &self.activations[self.activations.len() - 1]
+    }
+
+    // The output of every layer from the last forward pass
+    pub fn activations(&self) -> &[Array2<f64>] {
+        &self.activations
+    }
+
+    pub fn nabla_ws(&self) -> &[Array2<f64>] {
+        &self.nabla_ws
+    }
+
+    pub fn nabla_bs(&self) -> &[Array2<f64>] {
+        &self.nabla_bs
+    }
+
+    pub fn sparse_rows(&self) -> &[Option<Vec<usize>>] {
+        &self.sparse_rows
+    }
+
+    pub fn into_gradients(self) -> Gradients {
+        (self.nabla_bs, self.nabla_ws)
+    }
+}
+
+// Reallocates `buffer` only if its shape differs from `shape`
+pub(crate) fn ensure_shape(buffer: &mut Array2<f64>, shape: (usize, usize)) {
+    if buffer.dim() != shape {
+        *buffer = Array2::zeros(shape);
+    }
+}
@@ -1,2 +1,34 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+
+// Plots with options are builders or structs of options, written to a
+// directory under a conventional file name with `plot`, to a path with `save`
+// or to any writer with `write`. `plot_graph` and `plot_png` are shortcuts for
+// their default options, plots without options are free functions like
+// `plot_architecture` and `write_architecture`.
+pub mod animation;
+pub mod architecture;
+pub mod decision_boundary;
+pub mod distributions;
 pub mod graph_plotter;
-pub mod png_plotter;
\ No newline at end of file
+pub mod png_plotter;
+pub mod projection;
+
+// Writes a plot to the path, creating missing directories. The plot is
+// rendered into memory first, so a failed plot leaves no empty or partial file
+// behind.
+pub(crate) fn save_with(
+    path: &Path,
+    write: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<PathBuf> {
+    let mut image = Vec::new();
+    write(&mut image)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, image)?;
+
+    Ok(path.to_path_buf())
+}
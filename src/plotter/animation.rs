@@ -0,0 +1,232 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use plotters::{element::BitMapElement, prelude::*};
+
+use super::{
+    png_plotter::{Colormap, Normalization, PngPlot},
+    save_with,
+};
+use crate::{
+    error::{Error, Result},
+    neural_network::{Network, Recorder},
+};
+
+// Height of the caption above every frame
+const CAPTION: u32 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    // Animated PNG, lossless but larger
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+}
+
+// The prediction over the unit square at one point of the training
+pub struct Frame {
+    pub epoch: i32,
+    pub cost: f64,
+    pub dims: (usize, usize),
+    pub pixels: Vec<Vec<f64>>,
+}
+
+// Records how the prediction of a network over the unit square changes during
+// training and saves it as an animation, annotated with epoch and cost:
+//
+//     let mut animation = TrainingAnimation::new(10, 64);
+//     network.train_and_record(&RGB_DONUT, 32, 128, 1000, &mut animation)?;
+//     animation.plot(Path::new("images"), "donut", AnimationFormat::Gif)?;
+//
+// Frames are drawn like `PngPlot::for_outputs`: one output in grayscale, two
+// as red and green and the first three as RGB.
+pub struct TrainingAnimation {
+    // A frame is recorded every `every` epochs, values below 1 count as 1
+    pub every: i32,
+    // Grid points per axis the network is evaluated at
+    pub resolution: usize,
+    // Every grid point is drawn as a square of `scale` pixels
+    pub scale: usize,
+    // Time every frame is shown
+    pub delay_ms: u32,
+    // Draws the first output with a colormap instead
+    pub colormap: Option<Colormap>,
+    // How the outputs of every frame are brought into [0, 1]
+    pub normalization: Normalization,
+    frames: Vec<Frame>,
+}
+
+impl TrainingAnimation {
+    pub fn new(every: i32, resolution: usize) -> TrainingAnimation {
+        TrainingAnimation {
+            every: every.max(1),
+            resolution,
+            scale: (256 / resolution.max(1)).max(1),
+            delay_ms: 100,
+            colormap: None,
+            normalization: Normalization::Clamp,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    // Writes the animation to `dir/{name}_training.gif` or `.png`
+    pub fn plot(&self, dir: &Path, name: &str, format: AnimationFormat) -> Result<PathBuf> {
+        let path = dir.join(format!("{}_training.{}", name, format.extension()));
+        self.save(&path, format)
+    }
+
+    // Writes the animation to the path, creating missing directories
+    pub fn save(&self, path: &Path, format: AnimationFormat) -> Result<PathBuf> {
+        save_with(path, |animation| self.write(animation, format))
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: AnimationFormat) -> Result<()> {
+        if self.frames.is_empty() {
+            return Err(Error::InvalidData(String::from(
+                "the animation has no frames",
+            )));
+        }
+
+        match format {
+            AnimationFormat::Gif => self.write_gif(writer),
+            AnimationFormat::Apng => self.write_apng(writer),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let (width, height) = self.frames[0].dims;
+        (
+            (width * self.scale) as u32,
+            (height * self.scale) as u32 + CAPTION,
+        )
+    }
+
+    fn write_gif<W: Write>(&self, writer: W) -> Result<()> {
+        let (width, height) = self.size();
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(Error::InvalidData(format!(
+                "a GIF can't be {}x{} pixels",
+                width, height
+            )));
+        };
+        let gif_error = |e: gif::EncodingError| Error::Plot(e.to_string());
+
+        let mut encoder = gif::Encoder::new(writer, width, height, &[]).map_err(gif_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(gif_error)?;
+
+        // the delay of GIF frames is in hundredths of a second
+        let delay = (self.delay_ms / 10).min(u16::MAX as u32) as u16;
+        self.render(|buffer| {
+            let mut frame = gif::Frame::from_rgb_speed(width, height, buffer, 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(gif_error)
+        })
+    }
+
+    fn write_apng<W: Write>(&self, writer: W) -> Result<()> {
+        let (width, height) = self.size();
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+        encoder.set_frame_delay(self.delay_ms.min(u16::MAX as u32) as u16, 1000)?;
+        let mut writer = encoder.write_header()?;
+
+        self.render(|buffer| Ok(writer.write_image_data(buffer)?))?;
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    // Draws the frames one after another into an RGB buffer
+    fn render(&self, mut write: impl FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        let (width, height) = self.size();
+
+        let mut buffer = vec![0; (width * height * 3) as usize];
+        for frame in &self.frames {
+            {
+                let root =
+                    BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+                self.draw(&root, frame)?;
+                root.present()?;
+            }
+            write(&buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: &DrawingArea<DB, plotters::coord::Shift>,
+        frame: &Frame,
+    ) -> Result<()> {
+        root.fill(&WHITE)?;
+
+        let (width, height) = frame.dims;
+        let size = ((width * self.scale) as u32, (height * self.scale) as u32);
+        let mut plot = PngPlot::for_outputs(frame.dims, &frame.pixels)
+            .normalization(self.normalization)
+            .size(size.0, size.1);
+        if let Some(colormap) = self.colormap {
+            plot = plot.colormap(colormap);
+        }
+
+        // the bitmap is always RGB
+        let (color_type, _, samples) = plot.render()?;
+        let channels = color_type.samples();
+        let image = samples
+            .chunks_exact(channels)
+            .flat_map(|pixel| match channels {
+                1 | 2 => [pixel[0]; 3],
+                _ => [pixel[0], pixel[1], pixel[2]],
+            })
+            .collect();
+
+        let bitmap = BitMapElement::with_owned_buffer((0, CAPTION as i32), size, image)
+            .ok_or_else(|| Error::Plot(String::from("the frame does not fit the image")))?;
+        root.draw(&bitmap)?;
+
+        root.draw(&Text::new(
+            format!("Epoch: {}, Cost: {:.6}", frame.epoch, frame.cost),
+            (4, 4),
+            ("sans-serif", 16).into_font(),
+        ))?;
+
+        Ok(())
+    }
+}
+
+impl Recorder for TrainingAnimation {
+    fn is_due(&self, epoch: i32, epochs: i32) -> bool {
+        epoch % self.every.max(1) == 0 || epoch == epochs - 1
+    }
+
+    fn record(&mut self, network: &Network, epoch: i32, cost: f64) -> Result<()> {
+        let (dims, pixels) = network.predict_unit_square(self.resolution)?;
+        self.frames.push(Frame {
+            epoch,
+            cost,
+            dims,
+            pixels,
+        });
+
+        Ok(())
+    }
+}
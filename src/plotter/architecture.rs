@@ -0,0 +1,213 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use plotters::{
+    coord::Shift,
+    prelude::*,
+    style::text_anchor::{HPos, Pos, VPos},
+};
+
+use super::{graph_plotter::ImageFormat, save_with};
+use crate::{error::Result, neural_network::summary::ModelSummary};
+
+const NODE: (i32, i32) = (260, 56);
+const GAP: (i32, i32) = (30, 40);
+const MARGIN: i32 = 20;
+const TITLE: i32 = 40;
+const SKIP: i32 = 40;
+
+// Draws the architecture of a model as a diagram of its nodes to
+// `dir/{name}_architecture.png` or `.svg`. Nodes are placed in rows by their
+// distance from the inputs, with arrows from every node to the nodes that take
+// it as input. Outputs are framed in bold.
+pub fn plot_architecture(
+    dir: &Path,
+    name: &str,
+    summary: &ModelSummary,
+    format: ImageFormat,
+) -> Result<PathBuf> {
+    let path = dir.join(format!("{}_architecture.{}", name, format.extension()));
+    save_with(&path, |image| write_architecture(image, summary, format))
+}
+
+pub fn write_architecture<W: Write>(
+    mut writer: W,
+    summary: &ModelSummary,
+    format: ImageFormat,
+) -> Result<()> {
+    let layout = Layout::new(summary);
+    let (width, height) = layout.size;
+
+    match format {
+        ImageFormat::Png => {
+            let mut buffer = vec![0; (width * height * 3) as usize];
+            draw(
+                BitMapBackend::with_buffer(&mut buffer, layout.size).into_drawing_area(),
+                summary,
+                &layout,
+            )?;
+
+            let mut encoder = png::Encoder::new(writer, width, height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.write_header()?.write_image_data(&buffer)?;
+        }
+        ImageFormat::Svg => {
+            let mut svg = String::new();
+            draw(
+                SVGBackend::with_string(&mut svg, layout.size).into_drawing_area(),
+                summary,
+                &layout,
+            )?;
+            writer.write_all(svg.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+// The top left corner and row of every node and the size of the image
+struct Layout {
+    positions: Vec<(i32, i32)>,
+    depths: Vec<usize>,
+    size: (u32, u32),
+}
+
+impl Layout {
+    fn new(summary: &ModelSummary) -> Layout {
+        // nodes only take earlier nodes as input, so one pass finds the depths
+        let mut depths = Vec::with_capacity(summary.rows.len());
+        for row in &summary.rows {
+            let depth = row.inputs.iter().map(|&input| depths[input] + 1).max();
+            depths.push(depth.unwrap_or(0));
+        }
+
+        let levels = depths.iter().max().map_or(0, |depth| depth + 1);
+        let mut per_level = vec![0; levels];
+        depths.iter().for_each(|&depth| per_level[depth] += 1);
+        let widest = per_level.iter().copied().max().unwrap_or(0) as i32;
+
+        let row_width = |nodes: i32| nodes * (NODE.0 + GAP.0) - GAP.0;
+        // the right margin leaves room for connections that skip rows
+        let width = 2 * MARGIN + row_width(widest.max(1)) + SKIP;
+        let height = TITLE + 2 * MARGIN + levels.max(1) as i32 * (NODE.1 + GAP.1) - GAP.1;
+
+        let mut placed = vec![0; levels];
+        let positions = depths
+            .iter()
+            .map(|&depth| {
+                let left = (width - SKIP - row_width(per_level[depth])) / 2;
+                let x = left + placed[depth] * (NODE.0 + GAP.0);
+                placed[depth] += 1;
+                (x, TITLE + MARGIN + depth as i32 * (NODE.1 + GAP.1))
+            })
+            .collect();
+
+        Layout {
+            positions,
+            depths,
+            size: (width as u32, height as u32),
+        }
+    }
+}
+
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    summary: &ModelSummary,
+    layout: &Layout,
+) -> Result<()> {
+    root.fill(&WHITE)?;
+
+    let centered = Pos::new(HPos::Center, VPos::Center);
+    root.draw(&Text::new(
+        summary.title.clone(),
+        (layout.size.0 as i32 / 2, TITLE / 2 + 4),
+        ("sans-serif", 20).into_font().color(&BLACK).pos(centered),
+    ))?;
+
+    for (i, row) in summary.rows.iter().enumerate() {
+        let (x, y) = layout.positions[i];
+        for &input in &row.inputs {
+            let (ix, iy) = layout.positions[input];
+
+            // connections to the next row go straight down, the others around
+            // the right of the nodes in between
+            let path = match layout.depths[i] - layout.depths[input] {
+                1 => vec![(ix + NODE.0 / 2, iy + NODE.1), (x + NODE.0 / 2, y)],
+                _ => {
+                    let (start, end) = (iy + NODE.1 / 2, y + NODE.1 / 2);
+                    let side = ix.max(x) + NODE.0 + SKIP / 2;
+                    vec![
+                        (ix + NODE.0, start),
+                        (side, start),
+                        (side, end),
+                        (x + NODE.0, end),
+                    ]
+                }
+            };
+
+            let (from, to) = (path[path.len() - 2], path[path.len() - 1]);
+            root.draw(&PathElement::new(path, BLACK.stroke_width(1)))?;
+            root.draw(&arrowhead(from, to))?;
+        }
+    }
+
+    for (i, row) in summary.rows.iter().enumerate() {
+        let (x, y) = layout.positions[i];
+        // inputs, layers and merges
+        let fill = match row.inputs.len() {
+            0 => RGBColor(230, 230, 230),
+            1 => RGBColor(210, 228, 250),
+            _ => RGBColor(252, 240, 200),
+        };
+        let border = match summary.outputs.contains(&i) {
+            true => 3,
+            false => 1,
+        };
+
+        root.draw(&Rectangle::new(
+            [(x, y), (x + NODE.0, y + NODE.1)],
+            fill.filled(),
+        ))?;
+        root.draw(&Rectangle::new(
+            [(x, y), (x + NODE.0, y + NODE.1)],
+            BLACK.stroke_width(border),
+        ))?;
+
+        let center = x + NODE.0 / 2;
+        root.draw(&Text::new(
+            row.title(),
+            (center, y + NODE.1 / 3),
+            ("sans-serif", 16).into_font().color(&BLACK).pos(centered),
+        ))?;
+        root.draw(&Text::new(
+            row.details(),
+            (center, y + 2 * NODE.1 / 3 + 2),
+            ("sans-serif", 13)
+                .into_font()
+                .color(&RGBColor(80, 80, 80))
+                .pos(centered),
+        ))?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+// A small triangle pointing along the line at its end
+fn arrowhead(from: (i32, i32), to: (i32, i32)) -> Polygon<(i32, i32)> {
+    let (dx, dy) = ((to.0 - from.0) as f64, (to.1 - from.1) as f64);
+    let length = (dx * dx + dy * dy).sqrt().max(1.0);
+    let (ux, uy) = (dx / length, dy / length);
+
+    let point = |along: f64, across: f64| {
+        (
+            (to.0 as f64 - ux * along - uy * across).round() as i32,
+            (to.1 as f64 - uy * along + ux * across).round() as i32,
+        )
+    };
+
+    Polygon::new(vec![to, point(9.0, 4.0), point(9.0, -4.0)], BLACK.filled())
+}
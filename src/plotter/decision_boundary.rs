@@ -0,0 +1,303 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ndarray::Array2;
+use plotters::{
+    coord::Shift,
+    prelude::*,
+    style::colors::colormaps::{ColorMap, ViridisRGB},
+};
+
+use super::save_with;
+use crate::{
+    dataset::{slice::FeatureSlice, Dataset},
+    error::{Error, Result},
+    neural_network::Network,
+};
+
+// A heatmap of one output of a network with the samples of a dataset on top
+#[derive(Debug, Clone)]
+pub struct BoundaryPlot {
+    // Grid points per axis the network is evaluated at
+    pub resolution: usize,
+    // Contour lines are drawn where the output crosses these values
+    pub thresholds: Vec<f64>,
+    // The output that is plotted
+    pub output: usize,
+    // Samples drawn from datasets that are generated on the fly
+    pub samples: usize,
+    // The values mapped to the ends of the colormap, by default the smallest
+    // and largest prediction or label
+    pub range: Option<(f64, f64)>,
+    // The two inputs swept by networks with more than two inputs, by default
+    // the unit square of the first two
+    pub slice: Option<FeatureSlice>,
+    pub size: (u32, u32),
+}
+
+impl Default for BoundaryPlot {
+    fn default() -> Self {
+        BoundaryPlot {
+            resolution: 100,
+            thresholds: vec![0.5],
+            output: 0,
+            samples: 200,
+            range: None,
+            slice: None,
+            size: (800, 640),
+        }
+    }
+}
+
+impl BoundaryPlot {
+    // Writes the plot to `dir/{name}_boundary.png`
+    pub fn plot(
+        &self,
+        dir: &Path,
+        name: &str,
+        network: &Network,
+        data: &Dataset,
+    ) -> Result<PathBuf> {
+        let path = dir.join(format!("{}_boundary.png", name));
+        save_with(&path, |image| self.write(image, name, network, data))
+    }
+
+    // Writes the plot to the path, creating missing directories
+    pub fn save(&self, path: &Path, network: &Network, data: &Dataset) -> Result<PathBuf> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        save_with(path, |image| self.write(image, &name, network, data))
+    }
+
+    // Draws one output of a network with two inputs over the unit square, or
+    // of a larger network over a slice of two of its inputs, as a heatmap with
+    // contour lines at the thresholds, next to a colorbar. The samples of the
+    // dataset are scattered on top in the color of their label, so
+    // misclassified samples stand out against the background. The PNG has the
+    // title `Decision boundary-{name}`.
+    pub fn write<W: Write>(
+        &self,
+        writer: W,
+        name: &str,
+        network: &Network,
+        data: &Dataset,
+    ) -> Result<()> {
+        let (width, height) = self.size;
+        let mut buffer = vec![0; width as usize * height as usize * 3];
+        self.draw(
+            BitMapBackend::with_buffer(&mut buffer, self.size).into_drawing_area(),
+            name,
+            network,
+            data,
+        )?;
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.write_header()?.write_image_data(&buffer)?;
+
+        Ok(())
+    }
+
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: DrawingArea<DB, Shift>,
+        name: &str,
+        network: &Network,
+        data: &Dataset,
+    ) -> Result<()> {
+        let resolution = self.resolution.max(2);
+        if self.output >= network.output_size() {
+            return Err(Error::InvalidData(format!(
+                "the network has no output {}",
+                self.output
+            )));
+        }
+
+        let slice = self
+            .slice
+            .clone()
+            .unwrap_or_else(|| FeatureSlice::new(network.input_size(), (0, 1)));
+        let (_, prediction) = network.predict_slice(&slice, resolution)?;
+        let grid = Array2::from_shape_fn((resolution, resolution), |(i, j)| {
+            prediction[i * resolution + j][self.output]
+        });
+
+        let (x, y) = match data.get_full() {
+            Err(Error::NoFullDataset(_)) => data.get_batch(self.samples)?,
+            samples => samples?,
+        };
+        if x.ncols() != slice.fixed.len() || self.output >= y.ncols() {
+            return Err(Error::InvalidData(format!(
+                "{} has no {} inputs and label {} to plot",
+                data.name,
+                slice.fixed.len(),
+                self.output
+            )));
+        }
+        let labels = y.column(self.output);
+
+        let (min, max) = self.range.unwrap_or_else(|| {
+            grid.iter()
+                .chain(labels.iter())
+                .filter(|value| value.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                    (min.min(value), max.max(value))
+                })
+        });
+        let (min, max) = if min < max {
+            (min, max)
+        } else {
+            (min - 0.5, min + 0.5)
+        };
+        let color = |value: f64| ViridisRGB.get_color_normalized(value.clamp(min, max), min, max);
+
+        root.fill(&WHITE)?;
+        let (main, bar) = root.split_horizontally(self.size.0.saturating_sub(110));
+
+        let mut chart = ChartBuilder::on(&main)
+            .caption("Decision boundary-".to_owned() + name, ("sans-serif", 24))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(50)
+            .build_cartesian_2d(
+                slice.x_range.0..slice.x_range.1,
+                slice.y_range.0..slice.y_range.1,
+            )?;
+
+        let (fx, fy) = slice.features;
+        chart
+            .configure_mesh()
+            .x_desc(format!("x{}", fx))
+            .y_desc(format!("x{}", fy))
+            .draw()?;
+
+        // every grid point colors the cell around it, in unit square
+        // coordinates that the slice maps to its ranges
+        let step = 1.0 / (resolution - 1) as f64;
+        let cell = |k: usize| {
+            let center = k as f64 * step;
+            (
+                (center - step / 2.0).max(0.0),
+                (center + step / 2.0).min(1.0),
+            )
+        };
+        chart.draw_series(grid.indexed_iter().map(|((i, j), &value)| {
+            let ((x0, x1), (y0, y1)) = (cell(j), cell(i));
+            let corners = [slice.scale((x0, y0)), slice.scale((x1, y1))];
+            Rectangle::new(corners, color(value).filled())
+        }))?;
+
+        for &threshold in &self.thresholds {
+            chart.draw_series(contour(&grid, threshold).into_iter().map(|segment| {
+                let points = segment
+                    .iter()
+                    .map(|&point| slice.scale(point))
+                    .collect::<Vec<_>>();
+                PathElement::new(points, BLACK.stroke_width(2))
+            }))?;
+        }
+
+        chart.draw_series(x.rows().into_iter().zip(labels).flat_map(|(x, &label)| {
+            let point = (x[fx], x[fy]);
+            [
+                Circle::new(point, 5, color(label).filled()),
+                Circle::new(point, 5, BLACK.stroke_width(1)),
+            ]
+        }))?;
+
+        draw_colorbar(&bar, (min, max), &self.thresholds, color)?;
+
+        root.present()?;
+
+        Ok(())
+    }
+}
+
+fn draw_colorbar<DB: DrawingBackend>(
+    area: &DrawingArea<DB, plotters::coord::Shift>,
+    (min, max): (f64, f64),
+    thresholds: &[f64],
+    color: impl Fn(f64) -> RGBColor,
+) -> Result<()> {
+    let mut bar = ChartBuilder::on(area)
+        .margin_top(44)
+        .margin_bottom(50)
+        .margin_right(10)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..1.0, min..max)?;
+
+    bar.configure_mesh()
+        .disable_mesh()
+        .disable_x_axis()
+        .y_label_style(("sans-serif", 14))
+        .draw()?;
+
+    let steps = 256;
+    let height = (max - min) / steps as f64;
+    bar.draw_series((0..steps).map(|k| {
+        let y = min + k as f64 * height;
+        Rectangle::new(
+            [(0.0, y), (1.0, y + height)],
+            color(y + height / 2.0).filled(),
+        )
+    }))?;
+
+    bar.draw_series(
+        thresholds
+            .iter()
+            .filter(|&&threshold| threshold > min && threshold < max)
+            .map(|&threshold| {
+                PathElement::new(
+                    vec![(0.0, threshold), (1.0, threshold)],
+                    BLACK.stroke_width(2),
+                )
+            }),
+    )?;
+
+    Ok(())
+}
+
+// The line segments where the grid crosses the threshold (marching squares).
+// The grid holds the values at evenly spaced points of the unit square, row i
+// at x1 = i / (rows - 1), column j at x0 = j / (columns - 1).
+pub fn contour(grid: &Array2<f64>, threshold: f64) -> Vec<[(f64, f64); 2]> {
+    let (rows, columns) = grid.dim();
+    if rows < 2 || columns < 2 {
+        return Vec::new();
+    }
+
+    let point = |i: f64, j: f64| (j / (columns - 1) as f64, i / (rows - 1) as f64);
+    let mut segments = Vec::new();
+
+    for i in 0..rows - 1 {
+        for j in 0..columns - 1 {
+            // the corners counterclockwise, starting bottom left
+            let corners = [(i, j), (i, j + 1), (i + 1, j + 1), (i + 1, j)];
+
+            let crossings = (0..4)
+                .filter_map(|k| {
+                    let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                    let (va, vb) = (grid[a] - threshold, grid[b] - threshold);
+                    if (va < 0.0) == (vb < 0.0) {
+                        return None;
+                    }
+
+                    let t = va / (va - vb);
+                    let (ia, ja) = (a.0 as f64, a.1 as f64);
+                    let (ib, jb) = (b.0 as f64, b.1 as f64);
+                    Some(point(ia + t * (ib - ia), ja + t * (jb - ja)))
+                })
+                .collect::<Vec<_>>();
+
+            // two crossings are one segment, four (a saddle) are two
+            for pair in crossings.chunks_exact(2) {
+                segments.push([pair[0], pair[1]]);
+            }
+        }
+    }
+
+    segments
+}
@@ -0,0 +1,191 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use plotters::{
+    coord::Shift,
+    prelude::*,
+    style::colors::colormaps::{ColorMap, ViridisRGB},
+};
+
+use super::save_with;
+use crate::{
+    error::{Error, Result},
+    neural_network::monitor::{Distribution, DistributionMonitor},
+};
+
+// Bins of the value axis
+const BINS: usize = 40;
+
+// Draws how the distribution of one kind of values changes during training,
+// one panel per layer. Every column is the histogram of one snapshot, colored
+// relative to its densest bin, so exploding weights or vanishing gradients
+// show up as a band that widens or collapses over time.
+//
+//     DistributionPlot::new(&monitor, Distribution::Weights)
+//         .plot(Path::new("images"), "circle")?;
+pub struct DistributionPlot<'a> {
+    monitor: &'a DistributionMonitor,
+    distribution: Distribution,
+    size: Option<(u32, u32)>,
+}
+
+impl<'a> DistributionPlot<'a> {
+    pub fn new(monitor: &'a DistributionMonitor, distribution: Distribution) -> Self {
+        DistributionPlot {
+            monitor,
+            distribution,
+            size: None,
+        }
+    }
+
+    // Size of the image in pixels, by default 200 pixels high per layer
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    // Writes the plot to `dir/{name}_{distribution}.png`
+    pub fn plot(&self, dir: &Path, name: &str) -> Result<PathBuf> {
+        let path = dir.join(format!("{}_{}.png", name, self.distribution.label()));
+        save_with(&path, |image| self.write(image, name))
+    }
+
+    // Writes the plot to the path, creating missing directories
+    pub fn save(&self, path: &Path) -> Result<PathBuf> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        save_with(path, |image| self.write(image, &name))
+    }
+
+    // Writes the plot as a PNG with the title `{distribution}-{name}`
+    pub fn write<W: Write>(&self, writer: W, name: &str) -> Result<()> {
+        let layers = self
+            .monitor
+            .snapshots()
+            .first()
+            .map_or(0, |snapshot| snapshot.layers.len());
+        if layers == 0 {
+            return Err(Error::InvalidData(String::from(
+                "the monitor has no snapshots",
+            )));
+        }
+
+        let (width, height) = self.size.unwrap_or((800, 40 + 200 * layers as u32));
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidData(String::from("the image is empty")));
+        }
+        let mut buffer = vec![0; width as usize * height as usize * 3];
+        self.draw(
+            BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area(),
+            name,
+            layers,
+        )?;
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.write_header()?.write_image_data(&buffer)?;
+
+        Ok(())
+    }
+
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: DrawingArea<DB, Shift>,
+        name: &str,
+        layers: usize,
+    ) -> Result<()> {
+        let (monitor, distribution) = (self.monitor, self.distribution);
+        let snapshots = monitor.snapshots();
+        let epochs = |k: usize| {
+            let start = snapshots[k].epoch;
+            let end = snapshots
+                .get(k + 1)
+                .map_or(start + monitor.every, |next| next.epoch);
+            (start, end.max(start + 1))
+        };
+
+        root.fill(&WHITE)?;
+
+        let title = format!("{}-{}", distribution.label(), name);
+        let root = root.titled(&title, ("sans-serif", 24))?;
+
+        for (layer, area) in root.split_evenly((layers, 1)).iter().enumerate() {
+            let histograms = monitor.histograms(layer, distribution, BINS);
+            let (min, max) = histograms[0].1.range;
+            let (min, max) = if min < max {
+                (min, max)
+            } else {
+                (min - 0.5, min + 0.5)
+            };
+            let height = (max - min) / BINS as f64;
+
+            let mut chart = ChartBuilder::on(area)
+                .caption(format!("layer {}", layer), ("sans-serif", 16))
+                .margin(5)
+                .x_label_area_size(30)
+                .y_label_area_size(70)
+                .build_cartesian_2d(snapshots[0].epoch..epochs(snapshots.len() - 1).1, min..max)?;
+
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_desc("Epoch")
+                .y_label_formatter(&|value| label(*value, max - min))
+                .draw()?;
+
+            chart.draw_series(
+                histograms
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(k, (_, histogram))| {
+                        let (start, end) = epochs(k);
+                        let densest =
+                            histogram.counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+                        histogram
+                            .counts
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, &count)| count > 0)
+                            .map(move |(bin, &count)| {
+                                let low = min + bin as f64 * height;
+                                let color = ViridisRGB.get_color(count as f64 / densest);
+                                Rectangle::new([(start, low), (end, low + height)], color.filled())
+                            })
+                    }),
+            )?;
+
+            // marks snapshots with non-finite values in red along the top
+            chart.draw_series(
+                histograms
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, histogram))| histogram.non_finite > 0)
+                    .map(|(k, _)| {
+                        let (start, end) = epochs(k);
+                        Rectangle::new([(start, max - height), (end, max)], RED.filled())
+                    }),
+            )?;
+        }
+
+        root.present()?;
+
+        Ok(())
+    }
+}
+
+// Labels with as many decimals as the span of the axis needs. Gradients are
+// often tiny, so narrow axes are labeled in scientific notation.
+fn label(value: f64, span: f64) -> String {
+    if value.abs() < span * 1e-9 {
+        return String::from("0");
+    }
+
+    let decimals = (2.0 - span.log10().floor()).max(0.0) as usize;
+    match decimals {
+        0..=4 => format!("{:.*}", decimals, value),
+        _ => format!("{:.1e}", value),
+    }
+}
@@ -1,43 +1,355 @@
-use plotters::prelude::*;
+use std::{
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-pub fn plot_graph(name: &String, data: &Vec<(i32, f64)>) -> Result<(), Box<dyn std::error::Error>> {
-    let path_name = format!("images/{}_history.png", name);
+use plotters::{coord::Shift, prelude::*};
 
-    let root = BitMapBackend::new(&path_name, (640, 480)).into_drawing_area();
-    root.fill(&WHITE)?;
+use super::save_with;
+use crate::{
+    error::{Error, Result},
+    neural_network::TrainingHistory,
+};
 
-    let x_range = 0..data[data.len() - 1].0;
-    let y_range = 0.0..data
+const COLORS: [RGBColor; 6] = [RED, BLUE, GREEN, MAGENTA, CYAN, BLACK];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+}
+
+// The y axis a series is drawn against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YAxis {
+    Left,
+    Right,
+}
+
+// One line of a history plot, as (epoch, value) pairs
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(i32, f64)>,
+    pub axis: YAxis,
+}
+
+// A plot of values logged during training, e.g. the training and validation
+// cost with the learning rate on a second axis:
+//
+//     HistoryPlot::from_history(&history)
+//         .log_scale(true)
+//         .smoothing(0.9)
+//         .plot(Path::new("images"), "donut", ImageFormat::Svg)?;
+//
+// Non-finite values (e.g. from a diverged run), and values that are not
+// positive on a log scale, are left out.
+#[derive(Debug, Clone)]
+pub struct HistoryPlot {
+    series: Vec<Series>,
+    log_scale: bool,
+    smoothing: f64,
+    size: (u32, u32),
+    y_desc: String,
+}
+
+impl Default for HistoryPlot {
+    fn default() -> Self {
+        HistoryPlot {
+            series: Vec::new(),
+            log_scale: false,
+            smoothing: 0.0,
+            size: (640, 480),
+            y_desc: String::from("Cost"),
+        }
+    }
+}
+
+impl HistoryPlot {
+    pub fn new() -> HistoryPlot {
+        HistoryPlot::default()
+    }
+
+    // The training and, if logged, validation cost, with the learning rate on
+    // the right axis
+    pub fn from_history(history: &TrainingHistory) -> HistoryPlot {
+        let mut plot = HistoryPlot::new().series("training", &history.cost);
+        if !history.validation.is_empty() {
+            plot = plot.series("validation", &history.validation);
+        }
+        plot.learning_rate(&history.learning_rate)
+    }
+
+    pub fn series(self, label: &str, points: &[(i32, f64)]) -> HistoryPlot {
+        self.add(label, points, YAxis::Left)
+    }
+
+    // A series with its own scale on the right axis
+    pub fn right_series(self, label: &str, points: &[(i32, f64)]) -> HistoryPlot {
+        self.add(label, points, YAxis::Right)
+    }
+
+    pub fn learning_rate(self, points: &[(i32, f64)]) -> HistoryPlot {
+        self.right_series("learning rate", points)
+    }
+
+    // Logarithmic instead of linear y axes
+    pub fn log_scale(mut self, log_scale: bool) -> HistoryPlot {
+        self.log_scale = log_scale;
+        self
+    }
+
+    // Smooths the series on the left axis with an exponential moving average,
+    // `factor` in [0, 1) is the weight of the previous average. The raw values
+    // are drawn faded behind the average.
+    pub fn smoothing(mut self, factor: f64) -> HistoryPlot {
+        self.smoothing = factor.clamp(0.0, 0.999);
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> HistoryPlot {
+        self.size = (width, height);
+        self
+    }
+
+    // Description of the left axis
+    pub fn y_desc(mut self, desc: &str) -> HistoryPlot {
+        self.y_desc = desc.to_string();
+        self
+    }
+
+    fn add(mut self, label: &str, points: &[(i32, f64)], axis: YAxis) -> HistoryPlot {
+        self.series.push(Series {
+            label: label.to_string(),
+            points: points.to_vec(),
+            axis,
+        });
+        self
+    }
+
+    // Writes the plot to `dir/{name}_history.png` or `.svg`
+    pub fn plot(&self, dir: &Path, name: &str, format: ImageFormat) -> Result<PathBuf> {
+        let path = dir.join(format!("{}_history.{}", name, format.extension()));
+        save_with(&path, |image| self.write(image, name, format))
+    }
+
+    // Writes the plot to the path, creating missing directories
+    pub fn save(&self, path: &Path, format: ImageFormat) -> Result<PathBuf> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        save_with(path, |image| self.write(image, &name, format))
+    }
+
+    // Writes the plot with the title `History-{name}`
+    pub fn write<W: Write>(&self, mut writer: W, name: &str, format: ImageFormat) -> Result<()> {
+        if self.series.iter().all(|series| series.points.is_empty()) {
+            return Err(Error::EmptyHistory);
+        }
+
+        match format {
+            ImageFormat::Png => {
+                let (width, height) = self.size;
+                let mut buffer = vec![0; (width * height * 3) as usize];
+                self.draw(
+                    BitMapBackend::with_buffer(&mut buffer, self.size).into_drawing_area(),
+                    name,
+                )?;
+
+                let mut encoder = png::Encoder::new(writer, width, height);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.write_header()?.write_image_data(&buffer)?;
+            }
+            ImageFormat::Svg => {
+                let mut svg = String::new();
+                self.draw(
+                    SVGBackend::with_string(&mut svg, self.size).into_drawing_area(),
+                    name,
+                )?;
+                writer.write_all(svg.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw<DB: DrawingBackend>(&self, root: DrawingArea<DB, Shift>, name: &str) -> Result<()> {
+        let last_epoch = self
+            .series
+            .iter()
+            .filter_map(|series| series.points.last())
+            .map(|point| point.0)
+            .max()
+            .unwrap_or(0);
+
+        // the values as drawn, and their averages if smoothed
+        let lines = self
+            .series
+            .iter()
+            .map(|series| {
+                let raw = self.visible(&series.points);
+                let smoothed = match series.axis {
+                    YAxis::Left if self.smoothing > 0.0 => Some(self.smooth(&raw)),
+                    _ => None,
+                };
+                (raw, smoothed)
+            })
+            .collect::<Vec<_>>();
+
+        let range = |axis: YAxis| {
+            let values = self
+                .series
+                .iter()
+                .zip(&lines)
+                .filter(|(series, _)| series.axis == axis)
+                .flat_map(|(_, (raw, _))| raw.iter().map(|point| point.1));
+            self.value_range(values)
+        };
+        let left = range(YAxis::Left).ok_or(Error::NonFiniteCost)?;
+        let right = range(YAxis::Right);
+
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("History-".to_owned() + name, ("sans-serif", 24).into_font())
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .right_y_label_area_size(if right.is_some() { 60 } else { 0 })
+            .build_cartesian_2d(0..last_epoch.max(1), left)?
+            .set_secondary_coord(0..last_epoch.max(1), right.clone().unwrap_or(0.0..1.0));
+
+        // log scales are drawn as log10 of the values, labeled with the values
+        let decades = |value: &f64| format!("{:.0e}", 10f64.powf(*value));
+
+        let mut mesh = chart.configure_mesh();
+        mesh.x_desc("Epoch").y_desc(self.y_desc.as_str());
+        if self.log_scale {
+            mesh.y_label_formatter(&decades);
+        }
+        mesh.draw()?;
+
+        if right.is_some() {
+            let right_desc = self
+                .series
+                .iter()
+                .find(|series| series.axis == YAxis::Right)
+                .map_or("", |series| series.label.as_str());
+
+            let mut axes = chart.configure_secondary_axes();
+            axes.y_desc(right_desc);
+            if self.log_scale {
+                axes.y_label_formatter(&decades);
+            }
+            axes.draw()?;
+        }
+
+        for (i, (series, (raw, smoothed))) in self.series.iter().zip(lines).enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            let legend = move |(x, y): (i32, i32)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+            };
+
+            match (series.axis, smoothed) {
+                (YAxis::Left, Some(smoothed)) => {
+                    chart.draw_series(LineSeries::new(raw, color.mix(0.25)))?;
+                    chart
+                        .draw_series(LineSeries::new(smoothed, color.stroke_width(2)))?
+                        .label(series.label.as_str())
+                        .legend(legend);
+                }
+                (YAxis::Left, None) => {
+                    chart
+                        .draw_series(LineSeries::new(raw, color))?
+                        .label(series.label.as_str())
+                        .legend(legend);
+                }
+                (YAxis::Right, _) => {
+                    chart
+                        .draw_secondary_series(DashedLineSeries::new(raw, 6, 4, color.into()))?
+                        .label(series.label.as_str())
+                        .legend(legend);
+                }
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    // The points that can be drawn, as log10 on a log scale
+    fn visible(&self, points: &[(i32, f64)]) -> Vec<(i32, f64)> {
+        points
+            .iter()
+            .filter(|point| point.1.is_finite() && (!self.log_scale || point.1 > 0.0))
+            .map(|&(epoch, value)| match self.log_scale {
+                true => (epoch, value.log10()),
+                false => (epoch, value),
+            })
+            .collect()
+    }
+
+    fn smooth(&self, points: &[(i32, f64)]) -> Vec<(i32, f64)> {
+        ema(points, self.smoothing)
+    }
+
+    // A linear axis starts at zero like the plain cost plot, unless there are
+    // negative values. A log axis covers whole decades.
+    fn value_range(&self, values: impl Iterator<Item = f64>) -> Option<Range<f64>> {
+        let (min, max) = values.fold(None, |range, value| match range {
+            None => Some((value, value)),
+            Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+        })?;
+
+        let (min, max) = match self.log_scale {
+            true => (min.floor(), max.ceil()),
+            false => (min.min(0.0), max),
+        };
+
+        Some(if min < max {
+            min..max
+        } else {
+            min - 1.0..max + 1.0
+        })
+    }
+}
+
+// Exponential moving average, starting at the first value
+pub fn ema(points: &[(i32, f64)], factor: f64) -> Vec<(i32, f64)> {
+    points
         .iter()
-        .map(|x| x.1)
-        .max_by(|x, y| x.partial_cmp(y).unwrap())
-        .unwrap();
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("History-".to_owned() + &name, ("sans-serif", 24).into_font())
-        .margin(5)
-        .x_label_area_size(30)
-        .y_label_area_size(60)
-        .build_cartesian_2d(x_range, y_range)?;
-
-    chart
-        .configure_mesh()
-        .x_desc("Epoch")
-        .y_desc("Cost")
-        .draw()?;
-
-    chart
-        .draw_series(LineSeries::new((0..data.len()).map(|i| data[i]), &RED))?
-        .label("cost")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
-
-    root.present()?;
-
-    Ok(())
+        .scan(None, |average: &mut Option<f64>, &(epoch, value)| {
+            let next = match *average {
+                Some(average) => factor * average + (1.0 - factor) * value,
+                None => value,
+            };
+            *average = Some(next);
+            Some((epoch, next))
+        })
+        .collect()
+}
+
+// Plots a single cost history to `dir/{name}_history.png` and returns the
+// path, see `HistoryPlot` for more series, other formats and sizes
+pub fn plot_graph(dir: &Path, name: &str, data: &[(i32, f64)]) -> Result<PathBuf> {
+    HistoryPlot::new()
+        .series("cost", data)
+        .plot(dir, name, ImageFormat::Png)
 }
@@ -1,41 +1,373 @@
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-fn convert_to_256_color_scale(value: &f64) -> u8 {
-    (value * 255.0) as u8
+use super::save_with;
+use crate::error::{Error, Result};
+
+// Named color scales for images of a single output, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Grayscale,
+    Viridis,
+    Magma,
+    // Blue below 0.5, white at 0.5 and red above, for values around zero
+    // with `Normalization::Symmetric`
+    Diverging,
 }
 
-pub fn plot_png(
-    name: &str,
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Grayscale,
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Diverging,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Grayscale => "grayscale",
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Diverging => "diverging",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Colormap> {
+        Colormap::ALL
+            .into_iter()
+            .find(|colormap| colormap.name() == name)
+    }
+
+    // The color of a value in [0, 1], values outside are clamped and NaN is
+    // drawn like 0
+    pub fn color(&self, value: f64) -> [u8; 3] {
+        // evenly spaced samples of the matplotlib colormaps
+        const VIRIDIS: [[u8; 3]; 9] = [
+            [68, 1, 84],
+            [71, 44, 122],
+            [59, 81, 139],
+            [44, 113, 142],
+            [33, 144, 141],
+            [39, 173, 129],
+            [92, 200, 99],
+            [170, 220, 50],
+            [253, 231, 37],
+        ];
+        const MAGMA: [[u8; 3]; 9] = [
+            [0, 0, 4],
+            [28, 16, 68],
+            [79, 18, 123],
+            [129, 37, 129],
+            [181, 54, 122],
+            [229, 80, 100],
+            [251, 135, 97],
+            [254, 194, 135],
+            [252, 253, 191],
+        ];
+        const DIVERGING: [[u8; 3]; 5] = [
+            [59, 76, 192],
+            [141, 176, 254],
+            [221, 221, 221],
+            [244, 154, 123],
+            [180, 4, 38],
+        ];
+
+        let value = unit(value);
+        match self {
+            Colormap::Grayscale => [to_u8(value); 3],
+            Colormap::Viridis => interpolate(&VIRIDIS, value),
+            Colormap::Magma => interpolate(&MAGMA, value),
+            Colormap::Diverging => interpolate(&DIVERGING, value),
+        }
+    }
+}
+
+fn interpolate(stops: &[[u8; 3]], value: f64) -> [u8; 3] {
+    let position = value * (stops.len() - 1) as f64;
+    let i = (position as usize).min(stops.len() - 2);
+    let t = position - i as f64;
+    let mut color = [0; 3];
+    for (channel, out) in color.iter_mut().enumerate() {
+        let (a, b) = (stops[i][channel] as f64, stops[i + 1][channel] as f64);
+        *out = (a + (b - a) * t).round() as u8;
+    }
+    color
+}
+
+// Values in [0, 1] to 0..=255, see `unit`
+fn to_u8(value: f64) -> u8 {
+    (unit(value) * 255.0).round() as u8
+}
+
+// Clamps a value to [0, 1], so that values outside the range don't wrap
+// around. Infinities go to the end of their sign and NaN to 0, i.e. black.
+fn unit(value: f64) -> f64 {
+    match value {
+        v if v.is_nan() => 0.0,
+        f64::INFINITY => 1.0,
+        f64::NEG_INFINITY => 0.0,
+        v => v.clamp(0.0, 1.0),
+    }
+}
+
+// How the values of every output are brought into [0, 1] before they are
+// drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    // Values are expected in [0, 1] and clamped outside of it
+    Clamp,
+    // Each output is stretched from its smallest to its largest value
+    MinMax,
+    // Each output is scaled so that zero is at 0.5 and the largest absolute
+    // value at 0 or 1
+    Symmetric,
+    // Values are scaled from the range and clamped outside of it, the bounds
+    // must be finite with the first below the second
+    Range(f64, f64),
+}
+
+impl Normalization {
+    pub fn from_name(name: &str) -> Option<Normalization> {
+        match name {
+            "clamp" => Some(Normalization::Clamp),
+            "minmax" => Some(Normalization::MinMax),
+            "symmetric" => Some(Normalization::Symmetric),
+            _ => None,
+        }
+    }
+
+    // The range of one output that is mapped to [0, 1]
+    fn range(&self, values: impl Iterator<Item = f64>) -> (f64, f64) {
+        let finite = || values.filter(|value| value.is_finite());
+        let (min, max) = match self {
+            Normalization::Clamp => return (0.0, 1.0),
+            Normalization::Range(min, max) => return (*min, *max),
+            Normalization::MinMax => finite().fold((f64::INFINITY, f64::NEG_INFINITY), |r, v| {
+                (r.0.min(v), r.1.max(v))
+            }),
+            Normalization::Symmetric => {
+                let largest = finite().fold(0.0, |largest: f64, v| largest.max(v.abs()));
+                (-largest, largest)
+            }
+        };
+        match min < max {
+            true => (min, max),
+            // constant outputs are drawn in the middle of the scale
+            false if min.is_finite() => (min - 0.5, min + 0.5),
+            false => (0.0, 1.0),
+        }
+    }
+}
+
+// Which outputs of every pixel are drawn and how
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorMapping {
+    // One output drawn with a colormap
+    Colormap { output: usize, colormap: Colormap },
+    // One output per channel of the color type, `None` leaves a channel at 0
+    Channels(Vec<Option<usize>>),
+}
+
+// Pixel values written as a PNG, row by row. By default every pixel holds one
+// value in [0, 1] per channel of the color type. The image can be scaled to a
+// different size, e.g. a 64x64 prediction drawn as a 512x512 image:
+//
+//     PngPlot::new(dims, &prediction, png::ColorType::Rgb)
+//         .size(512, 512)
+//         .save(Path::new("out/donut.png"))?;
+//
+// `for_outputs` picks the colors from the number of outputs instead, and the
+// values can be drawn on a colormap or mapped to channels:
+//
+//     PngPlot::for_outputs(dims, &prediction)
+//         .colormap(Colormap::Diverging)
+//         .normalization(Normalization::Symmetric)
+//         .save(Path::new("out/regression.png"))?;
+pub struct PngPlot<'a> {
     dims: (usize, usize),
-    data: &Vec<Vec<f64>>,
-    color_type: png::ColorType,
-) -> Result<(), std::io::Error> {
-    let path = Path::new("images").join(name).with_extension("png");
+    data: &'a [Vec<f64>],
+    mapping: ColorMapping,
+    normalization: Normalization,
+    size: Option<(u32, u32)>,
+}
+
+impl<'a> PngPlot<'a> {
+    pub fn new(dims: (usize, usize), data: &'a [Vec<f64>], color_type: png::ColorType) -> Self {
+        let channels = (0..color_type.samples()).map(Some).collect();
+        PngPlot {
+            dims,
+            data,
+            mapping: ColorMapping::Channels(channels),
+            normalization: Normalization::Clamp,
+            size: None,
+        }
+    }
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
+    // Grayscale for one output, red and green for two and the first three as
+    // RGB for more
+    pub fn for_outputs(dims: (usize, usize), data: &'a [Vec<f64>]) -> Self {
+        let outputs = data.first().map_or(1, Vec::len);
+        let mut plot = PngPlot::new(dims, data, png::ColorType::Grayscale);
+        if outputs > 1 {
+            plot = plot.channels(&[Some(0), Some(1), (outputs > 2).then_some(2)]);
+        }
+        plot
     }
 
-    let file = File::create(&path)?;
-    let ref mut w = BufWriter::new(file);
+    // Draws the first output with the colormap
+    pub fn colormap(self, colormap: Colormap) -> Self {
+        self.colormap_output(0, colormap)
+    }
 
-    let width = dims.0 as u32;
-    let height = dims.1 as u32;
+    pub fn colormap_output(mut self, output: usize, colormap: Colormap) -> Self {
+        self.mapping = ColorMapping::Colormap { output, colormap };
+        self
+    }
 
-    let mut encoder = png::Encoder::new(w, width, height);
-    encoder.set_color(color_type);
+    // The output drawn to each channel: one channel is grayscale, two
+    // grayscale with alpha, three RGB and four RGBA
+    pub fn channels(mut self, channels: &[Option<usize>]) -> Self {
+        self.mapping = ColorMapping::Channels(channels.to_vec());
+        self
+    }
 
-    let mut writer = encoder.write_header()?;
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
 
-    let data_uint8 = data
-        .iter()
-        .flatten()
-        .map(convert_to_256_color_scale)
-        .collect::<Vec<u8>>();
+    // Size of the image in pixels, by default one pixel per value
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
 
-    writer.write_image_data(&data_uint8)?;
+    // Writes the image to `dir/{name}.png`
+    pub fn plot(&self, dir: &Path, name: &str) -> Result<PathBuf> {
+        self.save(&dir.join(name).with_extension("png"))
+    }
+
+    // Writes the image to the path, creating missing directories
+    pub fn save(&self, path: &Path) -> Result<PathBuf> {
+        save_with(path, |image| self.write(image))
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> Result<()> {
+        let (color_type, (width, height), data) = self.render()?;
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(color_type);
+        encoder.write_header()?.write_image_data(&data)?;
+
+        Ok(())
+    }
 
-    Ok(())
+    // The color type, size and samples of the image, row by row
+    pub(crate) fn render(&self) -> Result<(png::ColorType, (u32, u32), Vec<u8>)> {
+        let (columns, rows) = self.dims;
+        let (color_type, outputs) = match &self.mapping {
+            ColorMapping::Colormap {
+                output,
+                colormap: Colormap::Grayscale,
+            } => (png::ColorType::Grayscale, vec![Some(*output)]),
+            ColorMapping::Colormap { output, .. } => (png::ColorType::Rgb, vec![Some(*output)]),
+            ColorMapping::Channels(channels) => {
+                let color_type = match channels.len() {
+                    1 => png::ColorType::Grayscale,
+                    2 => png::ColorType::GrayscaleAlpha,
+                    3 => png::ColorType::Rgb,
+                    4 => png::ColorType::Rgba,
+                    n => {
+                        return Err(Error::InvalidData(format!(
+                            "can't write an image with {} channels",
+                            n
+                        )))
+                    }
+                };
+                (color_type, channels.clone())
+            }
+        };
+
+        let values = self.data.first().map_or(0, Vec::len);
+        let needed = outputs
+            .iter()
+            .flatten()
+            .max()
+            .map_or(1, |output| output + 1);
+        if self.data.len() != columns * rows
+            || values < needed
+            || self.data.iter().any(|p| p.len() != values)
+        {
+            return Err(Error::InvalidData(format!(
+                "expected {}x{} pixels with {} values each",
+                columns, rows, needed
+            )));
+        }
+
+        let (width, height) = self.size.unwrap_or((columns as u32, rows as u32));
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidData(String::from("the image is empty")));
+        }
+
+        if let Normalization::Range(min, max) = self.normalization {
+            if !(min.is_finite() && max.is_finite() && min < max) {
+                return Err(Error::InvalidData(format!(
+                    "can't normalize to the range {} to {}",
+                    min, max
+                )));
+            }
+        }
+
+        // every output is normalized over the whole image
+        let ranges = (0..values)
+            .map(|output| {
+                self.normalization
+                    .range(self.data.iter().map(|pixel| pixel[output]))
+            })
+            .collect::<Vec<_>>();
+        let normalized = |pixel: &[f64], output: usize| {
+            let (min, max) = ranges[output];
+            (pixel[output] - min) / (max - min)
+        };
+
+        let pixels = self
+            .data
+            .iter()
+            .map(|pixel| match &self.mapping {
+                ColorMapping::Colormap {
+                    colormap: Colormap::Grayscale,
+                    output,
+                } => vec![to_u8(normalized(pixel, *output))],
+                ColorMapping::Colormap { output, colormap } => {
+                    colormap.color(normalized(pixel, *output)).to_vec()
+                }
+                ColorMapping::Channels(channels) => channels
+                    .iter()
+                    .map(|channel| channel.map_or(0, |output| to_u8(normalized(pixel, output))))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        // nearest neighbour scaling
+        let data_uint8 = (0..height as usize)
+            .flat_map(|y| {
+                let row = y * rows / height as usize;
+                (0..width as usize).map(move |x| row * columns + x * columns / width as usize)
+            })
+            .flat_map(|i| pixels[i].iter().copied())
+            .collect::<Vec<u8>>();
+
+        Ok((color_type, (width, height), data_uint8))
+    }
+}
+
+// Writes the pixels to `dir/{name}.png` and returns the path
+pub fn plot_png(
+    dir: &Path,
+    name: &str,
+    dims: (usize, usize),
+    data: &[Vec<f64>],
+    color_type: png::ColorType,
+) -> Result<PathBuf> {
+    PngPlot::new(dims, data, color_type).plot(dir, name)
 }
@@ -0,0 +1,181 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ndarray::Array2;
+use plotters::{
+    coord::Shift,
+    prelude::*,
+    style::colors::colormaps::{ColorMap, ViridisRGB},
+};
+
+use super::save_with;
+use crate::error::{Error, Result};
+
+// Scatters 2D points, e.g. a `Pca` or `Tsne` projection of a dataset or of the
+// hidden activations of a network. Points are colored by their label: single
+// labels on a colormap, one-hot labels by the class of their largest entry.
+//
+//     ProjectionPlot::new(&points, &labels)
+//         .size(640, 480)
+//         .plot(Path::new("images"), "hidden")?;
+pub struct ProjectionPlot<'a> {
+    points: &'a Array2<f64>,
+    labels: &'a Array2<f64>,
+    size: (u32, u32),
+}
+
+impl<'a> ProjectionPlot<'a> {
+    pub fn new(points: &'a Array2<f64>, labels: &'a Array2<f64>) -> Self {
+        ProjectionPlot {
+            points,
+            labels,
+            size: (640, 480),
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    // Writes the plot to `dir/{name}_projection.png`
+    pub fn plot(&self, dir: &Path, name: &str) -> Result<PathBuf> {
+        let path = dir.join(format!("{}_projection.png", name));
+        save_with(&path, |image| self.write(image, name))
+    }
+
+    // Writes the plot to the path, creating missing directories
+    pub fn save(&self, path: &Path) -> Result<PathBuf> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        save_with(path, |image| self.write(image, &name))
+    }
+
+    // Writes the plot as a PNG with the title `Projection-{name}`
+    pub fn write<W: Write>(&self, writer: W, name: &str) -> Result<()> {
+        let (width, height) = self.size;
+        let mut buffer = vec![0; width as usize * height as usize * 3];
+        self.draw(
+            BitMapBackend::with_buffer(&mut buffer, self.size).into_drawing_area(),
+            name,
+        )?;
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.write_header()?.write_image_data(&buffer)?;
+
+        Ok(())
+    }
+
+    fn draw<DB: DrawingBackend>(&self, root: DrawingArea<DB, Shift>, name: &str) -> Result<()> {
+        let (points, labels) = (self.points, self.labels);
+        if points.ncols() != 2 || points.nrows() != labels.nrows() || points.nrows() == 0 {
+            return Err(Error::InvalidData(format!(
+                "can't plot {} points with {} coordinates and {} labels",
+                points.nrows(),
+                points.ncols(),
+                labels.nrows()
+            )));
+        }
+
+        let colors = label_colors(labels);
+
+        let range = |column: usize| {
+            let (min, max) = points
+                .column(column)
+                .iter()
+                .filter(|value| value.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                    (min.min(value), max.max(value))
+                });
+            // a margin keeps the points off the axes
+            let margin = ((max - min) * 0.05).max(1e-9);
+            min - margin..max + margin
+        };
+        let (xs, ys) = (range(0), range(1));
+        if !xs.start.is_finite() || !ys.start.is_finite() {
+            return Err(Error::InvalidData(String::from("no finite points to plot")));
+        }
+
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Projection-".to_owned() + name, ("sans-serif", 24))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(50)
+            .build_cartesian_2d(xs, ys)?;
+
+        chart
+            .configure_mesh()
+            .x_desc("component 1")
+            .y_desc("component 2")
+            .draw()?;
+
+        chart.draw_series(
+            points
+                .rows()
+                .into_iter()
+                .zip(colors)
+                .map(|(point, color)| Circle::new((point[0], point[1]), 4, color.filled())),
+        )?;
+
+        root.present()?;
+
+        Ok(())
+    }
+}
+
+fn label_colors(labels: &Array2<f64>) -> Vec<RGBColor> {
+    const CLASSES: [RGBColor; 8] = [
+        RGBColor(31, 119, 180),
+        RGBColor(255, 127, 14),
+        RGBColor(44, 160, 44),
+        RGBColor(214, 39, 40),
+        RGBColor(148, 103, 189),
+        RGBColor(140, 86, 75),
+        RGBColor(227, 119, 194),
+        RGBColor(127, 127, 127),
+    ];
+
+    if labels.ncols() != 1 {
+        return labels
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let class = row
+                    .iter()
+                    .enumerate()
+                    .fold((0, f64::NEG_INFINITY), |best, (i, &value)| {
+                        if value > best.1 {
+                            (i, value)
+                        } else {
+                            best
+                        }
+                    })
+                    .0;
+                CLASSES[class % CLASSES.len()]
+            })
+            .collect();
+    }
+
+    let (min, max) = labels
+        .iter()
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+    let (min, max) = if min < max {
+        (min, max)
+    } else {
+        (min - 0.5, min + 0.5)
+    };
+
+    labels
+        .iter()
+        .map(|&label| ViridisRGB.get_color_normalized(label.clamp(min, max), min, max))
+        .collect()
+}
@@ -0,0 +1,136 @@
+use std::{env, fs, process};
+
+use neural_network_rs::{
+    dataset::example_datasets::{CIRCLE, RGB_DONUT},
+    error::Error,
+    neural_network::{
+        activation_function::{Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        optimizer::adam_optimizer::ADAM,
+        Network, Recorder,
+    },
+    plotter::{
+        animation::{AnimationFormat, TrainingAnimation},
+        png_plotter::Colormap,
+    },
+};
+
+fn network(outputs: usize) -> Network {
+    Network::builder()
+        .input(2)
+        .dense(8, Relu)
+        .dense(outputs, Sigmoid)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn frames_are_recorded_every_n_epochs_and_at_the_end() {
+    let mut network = network(1);
+    let mut animation = TrainingAnimation::new(10, 8);
+
+    network
+        .train_and_record(&CIRCLE, 16, 16, 25, &mut animation)
+        .unwrap();
+
+    let epochs = animation
+        .frames()
+        .iter()
+        .map(|frame| frame.epoch)
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, [0, 10, 20, 24]);
+    assert_eq!(animation.frames()[0].pixels.len(), 64);
+}
+
+#[test]
+fn intervals_below_one_record_every_epoch() {
+    let mut network = network(1);
+    let mut animation = TrainingAnimation::new(10, 4);
+    animation.every = 0;
+
+    network
+        .train_and_record(&CIRCLE, 16, 16, 3, &mut animation)
+        .unwrap();
+
+    assert_eq!(animation.frames().len(), 3);
+}
+
+#[test]
+fn animations_are_saved_as_gif_and_apng() {
+    let dir = env::temp_dir().join(format!("animation_{}", process::id()));
+    let mut network = network(3);
+    let mut animation = TrainingAnimation::new(5, 16);
+    network
+        .train_and_record(&RGB_DONUT, 16, 16, 15, &mut animation)
+        .unwrap();
+
+    let gif = animation.plot(&dir, "donut", AnimationFormat::Gif).unwrap();
+    assert_eq!(gif, dir.join("donut_training.gif"));
+    assert!(fs::read(&gif).unwrap().starts_with(b"GIF89a"));
+
+    let apng = animation
+        .plot(&dir, "donut", AnimationFormat::Apng)
+        .unwrap();
+    let reader = png::Decoder::new(fs::File::open(&apng).unwrap())
+        .read_info()
+        .unwrap();
+    let info = reader.info();
+    assert_eq!(info.animation_control().unwrap().num_frames, 4);
+    assert_eq!(info.width, 16 * animation.scale as u32);
+
+    let path = dir.join("nested").join("donut.gif");
+    assert_eq!(animation.save(&path, AnimationFormat::Gif).unwrap(), path);
+    let mut written = Vec::new();
+    animation.write(&mut written, AnimationFormat::Gif).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), written);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn frames_are_drawn_with_the_colormap() {
+    let mut animation = TrainingAnimation::new(1, 4);
+    animation.scale = 1;
+    animation.colormap = Some(Colormap::Viridis);
+    animation.record(&network(1), 0, 0.0).unwrap();
+
+    let mut apng = Vec::new();
+    animation.write(&mut apng, AnimationFormat::Apng).unwrap();
+    let mut reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).unwrap();
+
+    // the first point of the prediction is drawn right below the caption
+    let start = 24 * info.line_size;
+    let value = animation.frames()[0].pixels[0][0];
+    assert_eq!(buffer[start..start + 3], Colormap::Viridis.color(value));
+}
+
+#[test]
+fn unsupported_networks_and_empty_animations_are_errors() {
+    let mut animation = TrainingAnimation::new(1, 8);
+
+    // two outputs are drawn as red and green
+    animation.record(&network(2), 0, 0.0).unwrap();
+    let wide = Network::builder()
+        .input(3)
+        .dense(1, Sigmoid)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+    assert!(animation.record(&wide, 1, 0.0).is_err());
+
+    let animation = TrainingAnimation::new(1, 8);
+    assert!(matches!(
+        animation.plot(&env::temp_dir(), "empty", AnimationFormat::Gif),
+        Err(Error::InvalidData(_))
+    ));
+
+    // nothing is written for a failed animation
+    let path = env::temp_dir().join(format!("empty_animation_{}.png", process::id()));
+    assert!(animation.save(&path, AnimationFormat::Apng).is_err());
+    assert!(!path.exists());
+}
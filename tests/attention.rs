@@ -0,0 +1,137 @@
+use ndarray::{array, s, Array2, Axis};
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::{
+    dataset::example_datasets::SINE_SEQUENCE,
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Tanh},
+        builder::BuildError,
+        cost_function::QuadraticCost,
+        gradient_check::check_graph_gradients,
+        graph::Graph,
+        layer::{attention::MultiHeadAttention, positional_encoding::PositionalEncoding, Layer},
+        optimizer::adam_optimizer::ADAM,
+    },
+};
+
+const STEPS: usize = 4;
+const DIMENSION: usize = 6;
+const TOLERANCE: f64 = 1e-5;
+
+fn assert_gradients_match(graph: &mut Graph) {
+    let x = Array2::random((3, STEPS * DIMENSION), Uniform::new(-1.0, 1.0));
+    let y = Array2::random((3, 2), Uniform::new(-1.0, 1.0));
+
+    let errors = check_graph_gradients(graph, &[x], &[y], 1e-5).unwrap();
+    for (i, error) in errors.iter().enumerate() {
+        assert!(error.max() < TOLERANCE, "layer {}: {:?}", i, error);
+    }
+}
+
+#[test]
+fn attention_gradients_match() {
+    for heads in [1, 2, 3] {
+        let mut builder = Graph::builder();
+        let x = builder.input(STEPS * DIMENSION);
+        let h = builder.attention(x, STEPS, heads);
+        let h = builder.time_distributed(h, STEPS, DIMENSION, Tanh);
+        let h = builder.attention(h, STEPS, heads);
+        let y = builder.dense(h, 2, Linear);
+        let mut graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+        assert_gradients_match(&mut graph);
+    }
+}
+
+#[test]
+fn layer_norm_normalizes_every_step() {
+    let mut builder = Graph::builder();
+    let x = builder.input(STEPS * DIMENSION);
+    let y = builder.layer_norm(x, STEPS);
+    let graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+    let x = Array2::random((2, STEPS * DIMENSION), Uniform::new(-5.0, 5.0));
+    let y = graph.predict(&[x]).unwrap().remove(0);
+
+    let steps = y.into_shape((2 * STEPS, DIMENSION)).unwrap();
+    for mean in steps.mean_axis(Axis(1)).unwrap() {
+        assert!(mean.abs() < 1e-9);
+    }
+    for variance in steps.var_axis(Axis(1), 0.0) {
+        assert!((variance - 1.0).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn positional_encodings_alternate_sine_and_cosine() {
+    let encoding = PositionalEncoding::new(3, 4);
+    let encodings = encoding.encodings();
+
+    assert_eq!(encodings.slice(s![0, ..4]), array![0.0, 1.0, 0.0, 1.0]);
+    assert_eq!(encodings[[0, 4]], 1f64.sin());
+    assert_eq!(encodings[[0, 5]], 1f64.cos());
+    assert_eq!(encodings[[0, 10]], (2.0 / 100f64).sin());
+
+    let x = Array2::ones((2, 12));
+    assert_eq!(encoding.predict(&x), &x + encodings);
+}
+
+#[test]
+fn transformer_encoder_gradients_match() {
+    let mut builder = Graph::builder();
+    let x = builder.input(STEPS * DIMENSION);
+    let h = builder.positional_encoding(x, STEPS);
+    let h = builder.transformer_encoder(h, STEPS, 2, 8);
+    let y = builder.dense(h, 2, Linear);
+    let mut graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+    // attention, layer norm, two feed-forward layers, layer norm and the readout
+    assert_eq!(graph.layers().len(), 7);
+    assert_gradients_match(&mut graph);
+}
+
+#[test]
+fn heads_must_divide_the_step_size() {
+    let mut builder = Graph::builder();
+    let x = builder.input(STEPS * DIMENSION);
+    let y = builder.attention(x, STEPS, 4);
+
+    assert_eq!(
+        builder.build(&[y], ADAM::default(), QuadraticCost).err(),
+        Some(BuildError::IndivisibleHeads {
+            node: 1,
+            dimension: DIMENSION,
+            heads: 4
+        })
+    );
+
+    for heads in [0, 4] {
+        assert!(matches!(
+            MultiHeadAttention::new(STEPS, DIMENSION, heads),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
+
+#[test]
+fn transformer_learns_a_sine_wave() {
+    let mut builder = Graph::builder();
+    let x = builder.input(16);
+    let h = builder.time_distributed(x, 16, 4, Linear);
+    let h = builder.positional_encoding(h, 16);
+    let h = builder.transformer_encoder(h, 16, 2, 8);
+    let y = builder.dense(h, 1, Linear);
+    let mut graph = builder
+        .build(&[y], ADAM::new(0.01, 0.0, 1e-8, 0.9, 0.999), QuadraticCost)
+        .unwrap();
+
+    let (x, y) = SINE_SEQUENCE.get_full().unwrap();
+    let before = graph
+        .cost(std::slice::from_ref(&x), std::slice::from_ref(&y))
+        .unwrap();
+    graph.train_and_log(&SINE_SEQUENCE, 16, 16, 60).unwrap();
+    let after = graph.cost(&[x], &[y]).unwrap();
+
+    assert!(after < before / 10.0, "{} -> {}", before, after);
+}
@@ -0,0 +1,77 @@
+use ndarray::Array2;
+
+use neural_network_rs::neural_network::{
+    activation_function::{Linear, Relu},
+    builder::BuildError,
+    cost_function::QuadraticCost,
+    optimizer::sgd_optimzer::SGD,
+    Network,
+};
+
+#[test]
+fn builds_network_with_requested_shape() {
+    let network = Network::builder()
+        .input(2)
+        .dense(16, Relu)
+        .dense(3, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+
+    let prediction = network.predict(&Array2::zeros((5, 2))).unwrap();
+    assert_eq!(prediction.dim(), (5, 3));
+}
+
+#[test]
+fn missing_input_is_an_error() {
+    let result = Network::builder()
+        .dense(3, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build();
+
+    assert_eq!(result.err(), Some(BuildError::MissingInput));
+}
+
+#[test]
+fn network_without_layers_is_an_error() {
+    let result = Network::builder()
+        .input(2)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build();
+
+    assert_eq!(result.err(), Some(BuildError::NoLayers));
+}
+
+#[test]
+fn zero_sized_layer_is_an_error() {
+    let result = Network::builder()
+        .input(2)
+        .dense(8, Relu)
+        .dense(0, Relu)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build();
+
+    assert_eq!(result.err(), Some(BuildError::ZeroSizedLayer(2)));
+}
+
+#[test]
+fn missing_optimizer_and_cost_are_errors() {
+    let without_optimizer = Network::builder()
+        .input(2)
+        .dense(1, Linear)
+        .cost(QuadraticCost)
+        .build();
+    assert_eq!(without_optimizer.err(), Some(BuildError::MissingOptimizer));
+
+    let without_cost = Network::builder()
+        .input(2)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .build();
+    assert_eq!(without_cost.err(), Some(BuildError::MissingCostFunction));
+}
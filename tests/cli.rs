@@ -0,0 +1,138 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Output},
+};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_neural-network-rs"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// A directory with a model of the donut config trained for one epoch
+fn trained_donut(name: &str) -> (PathBuf, String) {
+    let dir = env::temp_dir().join(format!("cli_{}_{}", name, process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let model = dir.join("donut.json");
+    let output = run(&[
+        "train",
+        "--config",
+        "configs/rgb_donut.toml",
+        "--epochs",
+        "1",
+        "--model",
+        path(&model),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+
+    (dir, path(&model).to_owned())
+}
+
+fn path(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn features_are_separated_by_commas() {
+    let (dir, model) = trained_donut("features");
+    let image = dir.join("boundary.png");
+
+    let output = run(&[
+        "plot",
+        "--model",
+        &model,
+        "--boundary",
+        "--features",
+        "0,1",
+        "--resolution",
+        "8",
+        "--output",
+        path(&image),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(image.exists());
+
+    let output = run(&[
+        "plot",
+        "--model",
+        &model,
+        "--boundary",
+        "--features",
+        "0,1,2",
+        "--output",
+        path(&image),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--features takes two inputs"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn training_plots_skip_the_prediction_of_other_networks() {
+    let dir = env::temp_dir().join(format!("cli_images_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let output = run(&[
+        "train",
+        "--config",
+        "configs/sine_lstm.toml",
+        "--epochs",
+        "1",
+        "--model",
+        path(&dir.join("sine.json")),
+        "--images",
+        path(&dir),
+        "--name",
+        "sine",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("sine_history.png").exists());
+    assert!(!dir.join("sine.png").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn plots_need_a_resolution_of_at_least_one() {
+    let output = run(&["plot", "--model", "donut.json", "--resolution", "0"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--resolution"));
+}
+
+#[test]
+fn png_datasets_are_recognized_in_any_case() {
+    let dir = env::temp_dir().join(format!("cli_png_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let image = dir.join("pixels.PNG");
+    let mut encoder = png::Encoder::new(fs::File::create(&image).unwrap(), 2, 1);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&[255, 0, 0, 0, 0, 255])
+        .unwrap();
+
+    let output = run(&[
+        "train",
+        "--config",
+        "configs/rgb_donut.toml",
+        "--dataset",
+        path(&image),
+        "--batch-size",
+        "2",
+        "--verification-samples",
+        "2",
+        "--epochs",
+        "1",
+        "--model",
+        path(&dir.join("pixels.json")),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+
+    fs::remove_dir_all(dir).unwrap();
+}
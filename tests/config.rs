@@ -0,0 +1,141 @@
+use ndarray::Array2;
+
+use neural_network_rs::{
+    config::{ConfigError, ExperimentConfig},
+    neural_network::builder::BuildError,
+};
+
+const XOR_CONFIG: &str = r#"
+[network]
+input = 2
+layers = [
+    { size = 8, activation = "sigmoid" },
+    { size = 1, activation = "linear" },
+]
+
+[optimizer]
+type = "sgd"
+learning_rate = 0.1
+momentum = 0.5
+decay = 0.0005
+
+[cost]
+type = "huber"
+delta = 1.0
+
+[training]
+dataset = "XOR"
+batch_size = 4
+verification_samples = 4
+epochs = 100
+"#;
+
+#[test]
+fn example_config_builds_network_and_dataset() {
+    let config = ExperimentConfig::from_file("configs/rgb_donut.toml").unwrap();
+
+    let network = config.build_network().unwrap();
+    let dataset = config.dataset().unwrap();
+
+    assert_eq!(dataset.name, "RGB_DONUT");
+    assert_eq!(
+        network.predict(&Array2::zeros((4, 2))).unwrap().dim(),
+        (4, 3)
+    );
+}
+
+#[test]
+fn toml_and_json_describe_the_same_experiment() {
+    let from_toml = ExperimentConfig::from_toml(XOR_CONFIG).unwrap();
+    let json = serde_json::to_string(&from_toml).unwrap();
+    let from_json = ExperimentConfig::from_json(&json).unwrap();
+
+    assert_eq!(format!("{:?}", from_toml), format!("{:?}", from_json));
+    assert!(from_json.build_network().is_ok());
+}
+
+#[test]
+fn unknown_activation_is_an_error() {
+    let config =
+        ExperimentConfig::from_toml(&XOR_CONFIG.replace("\"sigmoid\"", "\"tanhh\"")).unwrap();
+
+    assert!(matches!(
+        config.build_network(),
+        Err(ConfigError::UnknownActivation(name)) if name == "tanhh"
+    ));
+}
+
+#[test]
+fn missing_cost_parameter_is_an_error() {
+    let config = ExperimentConfig::from_toml(&XOR_CONFIG.replace("delta = 1.0", "")).unwrap();
+
+    assert!(matches!(
+        config.build_network(),
+        Err(ConfigError::MissingCostParameter {
+            parameter: "delta",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn invalid_cost_parameter_is_an_error() {
+    let config =
+        ExperimentConfig::from_toml(&XOR_CONFIG.replace("delta = 1.0", "delta = -1.0")).unwrap();
+
+    assert!(matches!(
+        config.build_network(),
+        Err(ConfigError::InvalidCostParameter {
+            parameter: "delta",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn invalid_training_parameters_are_errors() {
+    let config =
+        ExperimentConfig::from_toml(&XOR_CONFIG.replace("batch_size = 4", "batch_size = 0"));
+    assert!(matches!(
+        config,
+        Err(ConfigError::InvalidTrainingParameter {
+            parameter: "batch_size",
+            ..
+        })
+    ));
+
+    let config = ExperimentConfig::from_toml(&XOR_CONFIG.replace("epochs = 100", "epochs = -1"));
+    assert!(matches!(
+        config,
+        Err(ConfigError::InvalidTrainingParameter {
+            parameter: "epochs",
+            value: -1,
+            ..
+        })
+    ));
+
+    // overrides are checked the same way
+    let mut config = ExperimentConfig::from_toml(XOR_CONFIG).unwrap();
+    config.training.batch_size = 0;
+    assert!(config.training.validate().is_err());
+}
+
+#[test]
+fn invalid_shape_is_an_error() {
+    let config = ExperimentConfig::from_toml(&XOR_CONFIG.replace("size = 8", "size = 0")).unwrap();
+
+    assert!(matches!(
+        config.build_network(),
+        Err(ConfigError::Build(BuildError::ZeroSizedLayer(1)))
+    ));
+}
+
+#[test]
+fn unknown_dataset_is_an_error() {
+    let config = ExperimentConfig::from_toml(&XOR_CONFIG.replace("\"XOR\"", "\"MNIST\"")).unwrap();
+
+    assert!(matches!(
+        config.dataset(),
+        Err(ConfigError::UnknownDataset(_))
+    ));
+}
@@ -0,0 +1,107 @@
+use ndarray::{array, Array1};
+
+use neural_network_rs::{
+    error::Error,
+    neural_network::cost_function::{
+        CostFunction, HuberCost, QuadraticCost, QuantileCost, ReducedCost, Reduction, SmoothL1Cost,
+    },
+};
+
+fn quadratic() -> Box<dyn CostFunction> {
+    Box::new(QuadraticCost)
+}
+
+#[test]
+fn weighted_costs_are_reduced() {
+    // per-sample costs of 0.5, 2 and 0
+    let a = array![[1.0], [2.0], [0.0]];
+    let expected = array![[0.0], [0.0], [0.0]];
+    let weights = array![1.0, 3.0, 4.0];
+    let cost = quadratic();
+
+    let per_sample = cost
+        .cost_with(&a, &expected, Some(&weights), Reduction::None)
+        .unwrap();
+    assert_eq!(per_sample, ReducedCost::PerSample(array![0.5, 6.0, 0.0]));
+
+    let sum = cost
+        .cost_with(&a, &expected, Some(&weights), Reduction::Sum)
+        .unwrap();
+    assert_eq!(sum, ReducedCost::Total(6.5));
+
+    let mean = cost
+        .cost_with(&a, &expected, Some(&weights), Reduction::Mean)
+        .unwrap();
+    assert_eq!(mean, ReducedCost::Total(6.5 / 8.0));
+
+    // without weights the mean is the plain mean
+    let mean = cost
+        .cost_with(&a, &expected, None, Reduction::Mean)
+        .unwrap();
+    assert_eq!(mean.total(), cost.cost(&a, &expected));
+}
+
+#[test]
+fn weighted_derivatives_match_the_reduction() {
+    let a = array![[1.0, -1.0], [2.0, 0.5]];
+    let expected = array![[0.0, 0.0], [0.0, 0.0]];
+    let weights = array![1.0, 3.0];
+    let cost = quadratic();
+
+    let none = cost
+        .cost_derivative_with(&a, &expected, Some(&weights), Reduction::None)
+        .unwrap();
+    assert_eq!(none, array![[1.0, -1.0], [6.0, 1.5]]);
+
+    let sum = cost
+        .cost_derivative_with(&a, &expected, Some(&weights), Reduction::Sum)
+        .unwrap();
+    assert_eq!(sum, none);
+
+    let mean = cost
+        .cost_derivative_with(&a, &expected, Some(&weights), Reduction::Mean)
+        .unwrap();
+    assert_eq!(mean, none / 4.0);
+}
+
+#[test]
+fn weights_must_match_the_samples() {
+    let a = array![[1.0], [2.0]];
+    let expected = array![[0.0], [0.0]];
+    let cost = quadratic();
+
+    for weights in [array![1.0], array![1.0, -1.0], Array1::zeros(2)] {
+        let result = cost.cost_with(&a, &expected, Some(&weights), Reduction::Mean);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+
+        let result = cost.cost_derivative_with(&a, &expected, Some(&weights), Reduction::Sum);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+    }
+
+    let result = cost.cost_with(&a, &array![[0.0]], None, Reduction::Sum);
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn cost_parameters_are_validated() {
+    for delta in [0.0, -1.0, f64::NAN] {
+        assert!(matches!(HuberCost::new(delta), Err(Error::InvalidData(_))));
+    }
+    for beta in [-0.5, f64::INFINITY, f64::NAN] {
+        assert!(matches!(
+            SmoothL1Cost::new(beta),
+            Err(Error::InvalidData(_))
+        ));
+    }
+    for tau in [0.0, 1.0, -0.1, f64::NAN] {
+        assert!(matches!(QuantileCost::new(tau), Err(Error::InvalidData(_))));
+    }
+
+    // zero beta is the absolute cost
+    let a = array![[1.0], [-3.0]];
+    let expected = array![[0.0], [0.0]];
+    let smooth_l1: Box<dyn CostFunction> = Box::new(SmoothL1Cost::new(0.0).unwrap());
+    assert_eq!(smooth_l1.cost(&a, &expected), 2.0);
+    assert_eq!(HuberCost::new(0.5).unwrap().delta(), 0.5);
+    assert_eq!(QuantileCost::new(0.9).unwrap().tau(), 0.9);
+}
@@ -0,0 +1,37 @@
+use std::{env, fs, process};
+
+use ndarray::array;
+
+use neural_network_rs::dataset::{csv::write_csv, Dataset};
+
+#[test]
+fn csv_dataset_splits_inputs_and_outputs() {
+    let path = env::temp_dir().join(format!("xor_{}.csv", process::id()));
+    fs::write(&path, "x,y,xor\n0,0,0\n0,1,1\n1,0,1\n1,1,0\n").unwrap();
+
+    let dataset = Dataset::from_csv("XOR", &path, 2);
+    fs::remove_file(&path).unwrap();
+
+    let (x, y) = dataset.unwrap().get_full().unwrap();
+    assert_eq!(x, array![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]]);
+    assert_eq!(y, array![[0.0], [1.0], [1.0], [0.0]]);
+}
+
+#[test]
+fn ragged_csv_is_an_error() {
+    let path = env::temp_dir().join(format!("ragged_{}.csv", process::id()));
+    fs::write(&path, "0,0,0\n0,1\n").unwrap();
+
+    let dataset = Dataset::from_csv("ragged", &path, 2);
+    fs::remove_file(&path).unwrap();
+
+    assert!(dataset.is_err());
+}
+
+#[test]
+fn written_csv_has_one_line_per_row() {
+    let mut output = Vec::new();
+    write_csv(&mut output, &array![[1.0, 0.5], [0.0, 2.0]]).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "1,0.5\n0,2\n");
+}
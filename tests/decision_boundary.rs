@@ -0,0 +1,103 @@
+use std::{env, fs, process};
+
+use ndarray::{array, Array2};
+
+use neural_network_rs::{
+    dataset::example_datasets::{CIRCLE, RGB_TEST, XOR},
+    error::Error,
+    neural_network::{
+        activation_function::{Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        optimizer::adam_optimizer::ADAM,
+        Network,
+    },
+    plotter::decision_boundary::{contour, BoundaryPlot},
+};
+
+fn network(outputs: usize) -> Network {
+    Network::builder()
+        .input(2)
+        .dense(8, Relu)
+        .dense(outputs, Sigmoid)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn contour_of_a_ramp_is_a_straight_line() {
+    // the value grows with x0 from 0 to 1
+    let grid = Array2::from_shape_fn((5, 5), |(_, j)| j as f64 / 4.0);
+
+    let segments = contour(&grid, 0.6);
+
+    assert_eq!(segments.len(), 4);
+    for segment in segments {
+        for (x, _) in segment {
+            assert!((x - 0.6).abs() < 1e-12);
+        }
+    }
+    assert!(contour(&grid, 2.0).is_empty());
+}
+
+#[test]
+fn saddles_give_two_segments() {
+    let grid = array![[1.0, 0.0], [0.0, 1.0]];
+
+    assert_eq!(contour(&grid, 0.5).len(), 2);
+}
+
+#[test]
+fn boundary_is_plotted_with_static_and_generated_samples() {
+    let dir = env::temp_dir().join(format!("decision_boundary_{}", process::id()));
+    let options = BoundaryPlot {
+        resolution: 20,
+        thresholds: vec![0.25, 0.5],
+        size: (400, 300),
+        ..BoundaryPlot::default()
+    };
+
+    for data in [&XOR, &CIRCLE] {
+        let path = options.plot(&dir, data.name, &network(1), data).unwrap();
+
+        assert_eq!(path, dir.join(format!("{}_boundary.png", data.name)));
+        assert!(fs::metadata(path).unwrap().len() > 0);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn boundary_is_saved_to_a_path_or_written_to_a_writer() {
+    let dir = env::temp_dir().join(format!("decision_boundary_save_{}", process::id()));
+    let network = network(1);
+    let options = BoundaryPlot {
+        resolution: 10,
+        size: (300, 200),
+        ..BoundaryPlot::default()
+    };
+
+    let path = dir.join("nested").join("xor.png");
+    assert_eq!(options.save(&path, &network, &XOR).unwrap(), path);
+
+    let mut image = Vec::new();
+    options.write(&mut image, "xor", &network, &XOR).unwrap();
+    let info = png::Decoder::new(&image[..]).read_info().unwrap();
+    assert_eq!((info.info().width, info.info().height), (300, 200));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_outputs_are_an_error() {
+    let dir = env::temp_dir();
+    let options = BoundaryPlot {
+        output: 1,
+        ..BoundaryPlot::default()
+    };
+
+    let result = options.plot(&dir, "missing", &network(1), &RGB_TEST);
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+    assert!(!dir.join("missing_boundary.png").exists());
+}
@@ -0,0 +1,149 @@
+use ndarray::Array2;
+
+use neural_network_rs::{
+    config::ExperimentConfig,
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Relu},
+        cost_function::QuadraticCost,
+        divergence::{Divergence, DivergencePolicy, Step},
+        optimizer::sgd_optimzer::SGD,
+        Network,
+    },
+};
+
+fn network(learning_rate: f64, policy: DivergencePolicy) -> Network {
+    Network::builder()
+        .input(2)
+        .dense(4, Relu)
+        .dense(1, Linear)
+        .optimizer(SGD::new(learning_rate, 0.0, 0.0))
+        .cost(QuadraticCost)
+        .divergence_policy(policy)
+        .build()
+        .unwrap()
+}
+
+fn batch(value: f64) -> (Array2<f64>, Array2<f64>) {
+    (
+        Array2::from_elem((4, 2), value),
+        Array2::from_elem((4, 1), value),
+    )
+}
+
+fn weights(network: &Network) -> Vec<Array2<f64>> {
+    network
+        .layers()
+        .iter()
+        .map(|layer| layer.get_weights().clone())
+        .collect()
+}
+
+#[test]
+fn non_finite_cost_aborts_by_default() {
+    let mut network = network(0.1, DivergencePolicy::default());
+    network.layers_mut()[1].get_weights_mut()[[0, 0]] = f64::NAN;
+
+    assert!(matches!(
+        network.train_minibatch(&batch(1.0)),
+        Err(Error::Diverged(Divergence::Cost))
+    ));
+}
+
+#[test]
+fn skipped_step_keeps_the_previous_weights() {
+    let mut network = network(f64::MAX, DivergencePolicy::SkipStep);
+    let before = weights(&network);
+
+    let step = network.train_minibatch(&batch(100.0)).unwrap();
+
+    assert_eq!(step, Step::Skipped(Divergence::Weights));
+    assert_eq!(weights(&network), before);
+}
+
+#[test]
+fn rollback_restores_the_last_good_weights() {
+    let mut network = network(0.01, DivergencePolicy::Rollback);
+    let initial = weights(&network);
+    assert_eq!(network.train_minibatch(&batch(0.5)).unwrap(), Step::Applied);
+
+    network.layers_mut()[1].get_weights_mut()[[0, 0]] = f64::NAN;
+    let step = network.train_minibatch(&batch(0.5)).unwrap();
+
+    // the checkpoint holds the weights the last good step started from
+    assert_eq!(step, Step::RolledBack(Divergence::Cost));
+    assert_eq!(weights(&network), initial);
+    assert!(network
+        .predict(&batch(0.5).0)
+        .unwrap()
+        .iter()
+        .all(|value| value.is_finite()));
+}
+
+#[test]
+fn rollback_recovers_from_a_huge_step_with_momentum() {
+    let mut network = Network::builder()
+        .input(2)
+        .dense(1, Linear)
+        .optimizer(SGD::new(0.01, 0.9, 0.0))
+        .cost(QuadraticCost)
+        .divergence_policy(DivergencePolicy::Rollback)
+        .build()
+        .unwrap();
+    for _ in 0..5 {
+        assert_eq!(network.train_minibatch(&batch(0.5)).unwrap(), Step::Applied);
+    }
+    let good = weights(&network);
+
+    // an outlier batch gives a finite cost but a huge step, the next cost overflows
+    assert_eq!(
+        network.train_minibatch(&batch(1e100)).unwrap(),
+        Step::Applied
+    );
+    assert_eq!(
+        network.train_minibatch(&batch(0.5)).unwrap(),
+        Step::RolledBack(Divergence::Cost)
+    );
+    assert_eq!(weights(&network), good);
+
+    // the momentum of the huge step is gone as well
+    for _ in 0..100 {
+        assert_eq!(network.train_minibatch(&batch(0.5)).unwrap(), Step::Applied);
+    }
+    assert!(network
+        .predict(&batch(0.5).0)
+        .unwrap()
+        .iter()
+        .all(|value| value.abs() < 10.0));
+}
+
+#[test]
+fn policy_is_read_from_the_training_config() {
+    let config = ExperimentConfig::from_toml(
+        r#"
+        [network]
+        input = 2
+        layers = [{ size = 1, activation = "linear" }]
+
+        [optimizer]
+        type = "sgd"
+        learning_rate = 0.1
+        momentum = 0.0
+        decay = 0.0
+
+        [cost]
+        type = "quadratic"
+
+        [training]
+        dataset = "XOR"
+        batch_size = 4
+        verification_samples = 4
+        epochs = 10
+        divergence = "rollback"
+        "#,
+    )
+    .unwrap();
+
+    let network = config.build_network().unwrap();
+    assert_eq!(network.divergence_policy(), DivergencePolicy::Rollback);
+}
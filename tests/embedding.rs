@@ -0,0 +1,157 @@
+use ndarray::{array, s, Array2};
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::{
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Relu, Sigmoid},
+        builder::BuildError,
+        cost_function::QuadraticCost,
+        divergence::{Divergence, DivergencePolicy, Step},
+        gradient_check::check_gradients,
+        optimizer::adam_optimizer::ADAM,
+        workspace::Workspace,
+        Network,
+    },
+};
+
+fn network() -> Network {
+    Network::builder()
+        .input(3)
+        .embedding(10, 4)
+        .dense(8, Sigmoid)
+        .dense(1, Linear)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn embedding_looks_up_one_vector_per_index() {
+    let network = network();
+    let table = network.layers()[0].get_weights().clone();
+
+    let output = network.layers()[0].predict(&array![[2.0, 0.0, 2.0]]);
+
+    assert_eq!(output.dim(), (1, 12));
+    assert_eq!(output.slice(s![0, ..4]), table.row(2));
+    assert_eq!(output.slice(s![0, 4..8]), table.row(0));
+    assert_eq!(output.slice(s![0, 8..]), table.row(2));
+}
+
+#[test]
+fn embedding_gradients_match() {
+    let mut network = network();
+    let x = array![[1.0, 4.0, 1.0], [9.0, 0.0, 3.0]];
+    let y = Array2::random((2, 1), Uniform::new(-1.0, 1.0));
+
+    let errors = check_gradients(&mut network, &x, &y, 1e-5).unwrap();
+    assert!(errors[0].max() < 1e-4, "{:?}", errors[0]);
+}
+
+#[test]
+fn only_rows_in_the_batch_are_updated() {
+    let mut network = network();
+    let before = network.layers()[0].get_weights().clone();
+
+    let batch = (
+        array![[1.0, 4.0, 1.0], [7.0, 4.0, 4.0]],
+        array![[1.0], [0.0]],
+    );
+    for _ in 0..5 {
+        network.train_minibatch(&batch).unwrap();
+    }
+
+    let after = network.layers()[0].get_weights();
+    for row in 0..10 {
+        let touched = [1, 4, 7].contains(&row);
+        assert_eq!(after.row(row) != before.row(row), touched, "row {}", row);
+    }
+}
+
+#[test]
+fn workspace_gradients_match_after_other_batches() {
+    let network = network();
+    let mut workspace = Workspace::new();
+
+    // every batch touches different rows than the one before
+    for x in [
+        array![[1.0, 4.0, 1.0], [7.0, 4.0, 4.0]],
+        array![[0.0, 2.0, 3.0], [5.0, 6.0, 8.0]],
+        array![[9.0, 9.0, 1.0]],
+    ] {
+        let y = Array2::random((x.nrows(), 1), Uniform::new(-1.0, 1.0));
+        network.backprop_into(&x, &y, &mut workspace).unwrap();
+        let (_, nabla_ws) = network.backprop(&x, &y).unwrap();
+
+        assert_eq!(workspace.nabla_ws()[0], nabla_ws[0]);
+    }
+}
+
+#[test]
+fn skipped_step_restores_the_rows_of_the_last_update() {
+    let mut network = network();
+    network.set_divergence_policy(DivergencePolicy::SkipStep);
+    let target = array![[1.0], [0.0]];
+
+    network
+        .train_minibatch(&(array![[1.0, 4.0, 1.0], [7.0, 4.0, 4.0]], target.clone()))
+        .unwrap();
+    let table = network.layers()[0].get_weights().clone();
+    let dense = network.layers()[1].get_weights().clone();
+
+    // the next step changes other rows, then the network diverges
+    let batch = (array![[0.0, 2.0, 3.0], [5.0, 6.0, 8.0]], target);
+    assert_eq!(network.train_minibatch(&batch).unwrap(), Step::Applied);
+    network.layers_mut()[1].get_weights_mut()[[0, 0]] = f64::NAN;
+
+    assert_eq!(
+        network.train_minibatch(&batch).unwrap(),
+        Step::Skipped(Divergence::Cost)
+    );
+    assert_eq!(network.layers()[0].get_weights(), &table);
+    assert_eq!(network.layers()[1].get_weights(), &dense);
+}
+
+#[test]
+fn invalid_indices_are_an_error() {
+    let network = network();
+
+    for index in [10.0, -1.0, 0.5, f64::NAN] {
+        let input = array![[0.0, index, 1.0]];
+        assert!(matches!(
+            network.predict(&input),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
+
+#[test]
+fn embedding_feeds_a_recurrent_layer() {
+    let network = Network::builder()
+        .input(5)
+        .embedding(20, 3)
+        .gru(4, false)
+        .dense(1, Linear)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+
+    let prediction = network.predict(&Array2::zeros((2, 5))).unwrap();
+    assert_eq!(prediction.dim(), (2, 1));
+}
+
+#[test]
+fn embedding_must_be_the_first_layer() {
+    let result = Network::builder()
+        .input(3)
+        .dense(3, Relu)
+        .embedding(10, 4)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build();
+
+    assert_eq!(result.err(), Some(BuildError::EmbeddingNotFirst(2)));
+}
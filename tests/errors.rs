@@ -0,0 +1,114 @@
+use std::env;
+
+use ndarray::{Array1, Array2};
+
+use neural_network_rs::{
+    dataset::{Dataset, DatasetType},
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Relu},
+        cost_function::QuadraticCost,
+        layer::{DenseLayer, Layer},
+        optimizer::sgd_optimzer::SGD,
+        Network,
+    },
+    plotter::graph_plotter::plot_graph,
+};
+
+fn network() -> Network {
+    Network::builder()
+        .input(2)
+        .dense(4, Relu)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn network_needs_at_least_two_layers() {
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(DenseLayer::new(2, Box::new(Linear)))];
+    let result = Network::new(layers, Box::new(SGD::default()), Box::new(QuadraticCost));
+
+    assert!(matches!(result, Err(Error::EmptyNetwork)));
+}
+
+#[test]
+fn wrong_input_shape_is_an_error() {
+    let result = network().predict(&Array2::zeros((4, 3)));
+
+    assert!(matches!(
+        result,
+        Err(Error::ShapeMismatch {
+            expected: 2,
+            found: 3,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn mismatched_batch_is_an_error() {
+    let mut network = network();
+
+    let wrong_outputs = (Array2::zeros((4, 2)), Array2::zeros((4, 2)));
+    assert!(matches!(
+        network.train_minibatch(&wrong_outputs),
+        Err(Error::ShapeMismatch { .. })
+    ));
+
+    let wrong_rows = (Array2::zeros((4, 2)), Array2::zeros((3, 1)));
+    assert!(matches!(
+        network.train_minibatch(&wrong_rows),
+        Err(Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn dynamic_dataset_has_no_full_dataset() {
+    let dataset = Dataset::new("dynamic", DatasetType::Dynamic(|x: Array1<f64>| x, (2, 2)));
+
+    assert!(matches!(dataset.get_full(), Err(Error::NoFullDataset(_))));
+    assert!(dataset.get_batch(4).is_ok());
+}
+
+#[test]
+fn empty_dataset_can_not_be_sampled() {
+    let dataset = Dataset::new(
+        "empty",
+        DatasetType::InMemory(Array2::zeros((0, 2)), Array2::zeros((0, 1))),
+    );
+
+    assert!(matches!(dataset.get_batch(4), Err(Error::EmptyDataset(_))));
+}
+
+#[test]
+fn inputs_and_labels_need_the_same_rows() {
+    let dataset = Dataset::new(
+        "mismatched",
+        DatasetType::InMemory(Array2::zeros((4, 2)), Array2::zeros((3, 1))),
+    );
+
+    assert!(matches!(dataset.get_full(), Err(Error::InvalidData(_))));
+    assert!(matches!(dataset.get_batch(8), Err(Error::InvalidData(_))));
+    let mut batch = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
+    assert!(matches!(
+        dataset.get_batch_into(8, &mut batch),
+        Err(Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn empty_or_diverged_history_can_not_be_plotted() {
+    let dir = env::temp_dir();
+
+    assert!(matches!(
+        plot_graph(&dir, "empty", &[]),
+        Err(Error::EmptyHistory)
+    ));
+    assert!(matches!(
+        plot_graph(&dir, "diverged", &[(0, f64::NAN), (1, f64::INFINITY)]),
+        Err(Error::NonFiniteCost)
+    ));
+}
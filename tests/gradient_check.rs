@@ -0,0 +1,118 @@
+use ndarray::Array2;
+use ndarray_rand::{
+    rand::{rngs::StdRng, SeedableRng},
+    rand_distr::Uniform,
+    RandomExt,
+};
+
+use neural_network_rs::neural_network::{
+    activation_function::{ActivationFunction, Linear, Relu, Sigmoid, Tanh},
+    cost_function::{
+        AbsoluteCost, CostFunction, HuberCost, LogCoshCost, QuadraticCost, QuantileCost,
+        SmoothL1Cost,
+    },
+    gradient_check::{check_gradients, GradientError},
+    layer::{DenseLayer, Layer},
+    optimizer::sgd_optimzer::SGD,
+    Network,
+};
+
+const EPSILON: f64 = 1e-5;
+const TOLERANCE: f64 = 1e-4;
+
+type Activation = fn() -> Box<dyn ActivationFunction>;
+type Cost = fn() -> Box<dyn CostFunction>;
+
+const ACTIVATIONS: [Activation; 4] = [
+    || Box::new(Relu),
+    || Box::new(Sigmoid),
+    || Box::new(Linear),
+    || Box::new(Tanh),
+];
+
+// Finite differences are wrong whenever a perturbation crosses a kink of Relu or
+// of the absolute and quantile costs. The weights and data come from a fixed
+// seed, so every run checks the same points and none of them is near a kink.
+const SEED: u64 = 42;
+
+fn max_gradient_error(
+    hidden: Activation,
+    output: Activation,
+    cost_function: Cost,
+) -> (usize, GradientError) {
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    let layers: Vec<Box<dyn Layer>> = vec![
+        Box::new(DenseLayer::new(3, hidden())),
+        Box::new(DenseLayer::new(5, hidden())),
+        Box::new(DenseLayer::new(4, output())),
+        Box::new(DenseLayer::new(2, Box::new(Linear))),
+    ];
+    let mut network = Network::new(layers, Box::new(SGD::default()), cost_function()).unwrap();
+    for layer in network.layers_mut() {
+        let weights =
+            Array2::random_using(layer.get_weights().dim(), Uniform::new(-1.0, 1.0), &mut rng);
+        let biases =
+            Array2::random_using(layer.get_bias().dim(), Uniform::new(-0.1, 0.1), &mut rng);
+        layer.set_weights(weights);
+        layer.set_bias(biases);
+    }
+
+    let x = Array2::random_using((8, 3), Uniform::new(-1.0, 1.0), &mut rng);
+    let y = Array2::random_using((8, 2), Uniform::new(-1.0, 1.0), &mut rng);
+
+    check_gradients(&mut network, &x, &y, EPSILON)
+        .unwrap()
+        .into_iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.max().total_cmp(&b.max()))
+        .unwrap()
+}
+
+fn assert_gradients_match(hidden: Activation, output: Activation, cost_function: Cost) {
+    let (layer, error) = max_gradient_error(hidden, output, cost_function);
+    assert!(
+        error.max() < TOLERANCE,
+        "layer {} gradients differ: {:?}",
+        layer,
+        error
+    );
+}
+
+fn assert_gradients_match_for_all_activations(cost_function: Cost) {
+    for hidden in ACTIVATIONS {
+        for output in ACTIVATIONS {
+            assert_gradients_match(hidden, output, cost_function);
+        }
+    }
+}
+
+#[test]
+fn quadratic_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(QuadraticCost));
+}
+
+#[test]
+fn absolute_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(AbsoluteCost));
+}
+
+#[test]
+fn huber_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(HuberCost::new(0.5).unwrap()));
+}
+
+#[test]
+fn smooth_l1_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(SmoothL1Cost::new(0.5).unwrap()));
+}
+
+#[test]
+fn log_cosh_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(LogCoshCost));
+}
+
+#[test]
+fn quantile_cost() {
+    assert_gradients_match_for_all_activations(|| Box::new(QuantileCost::new(0.9).unwrap()));
+}
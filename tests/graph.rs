@@ -0,0 +1,154 @@
+use ndarray::{array, Array2};
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::{
+    dataset::example_datasets::XOR,
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Sigmoid, Tanh},
+        builder::BuildError,
+        cost_function::QuadraticCost,
+        gradient_check::check_graph_gradients,
+        graph::{Graph, GraphBuilder},
+        layer::embedding::EmbeddingLayer,
+        optimizer::adam_optimizer::ADAM,
+    },
+};
+
+const TOLERANCE: f64 = 1e-5;
+
+fn assert_gradients_match(graph: &mut Graph, inputs: &[Array2<f64>], targets: &[Array2<f64>]) {
+    let errors = check_graph_gradients(graph, inputs, targets, 1e-5).unwrap();
+    for (i, error) in errors.iter().enumerate() {
+        assert!(error.max() < TOLERANCE, "layer {}: {:?}", i, error);
+    }
+}
+
+#[test]
+fn residual_block_gradients_match() {
+    let mut builder = Graph::builder();
+    let x = builder.input(3);
+    let h = builder.dense(x, 4, Tanh);
+    let r = builder.dense(h, 4, Tanh);
+    let h = builder.add(&[h, r]);
+    let y = builder.dense(h, 2, Linear);
+    let mut graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+    let x = Array2::random((5, 3), Uniform::new(-1.0, 1.0));
+    let y = Array2::random((5, 2), Uniform::new(-1.0, 1.0));
+
+    assert_gradients_match(&mut graph, &[x], &[y]);
+}
+
+#[test]
+fn multi_input_multi_output_gradients_match() {
+    let mut builder = Graph::builder();
+    let a = builder.input(2);
+    let b = builder.input(3);
+    let ha = builder.dense(a, 4, Tanh);
+    let hb = builder.dense(b, 4, Sigmoid);
+    let gated = builder.multiply(&[ha, hb]);
+    let joined = builder.concatenate(&[gated, hb]);
+    let first = builder.dense(joined, 1, Linear);
+    let second = builder.dense(gated, 2, Tanh);
+    let mut graph = builder
+        .build(&[first, second], ADAM::default(), QuadraticCost)
+        .unwrap();
+
+    let inputs = [
+        Array2::random((4, 2), Uniform::new(-1.0, 1.0)),
+        Array2::random((4, 3), Uniform::new(-1.0, 1.0)),
+    ];
+    let targets = [
+        Array2::random((4, 1), Uniform::new(-1.0, 1.0)),
+        Array2::random((4, 2), Uniform::new(-1.0, 1.0)),
+    ];
+
+    let predictions = graph.predict(&inputs).unwrap();
+    assert_eq!(predictions[0].dim(), (4, 1));
+    assert_eq!(predictions[1].dim(), (4, 2));
+
+    assert_gradients_match(&mut graph, &inputs, &targets);
+}
+
+#[test]
+fn residual_graph_learns_xor() {
+    let mut builder = Graph::builder();
+    let x = builder.input(2);
+    let h = builder.dense(x, 8, Tanh);
+    let r = builder.dense(h, 8, Tanh);
+    let h = builder.add(&[h, r]);
+    let y = builder.dense(h, 1, Sigmoid);
+    let mut graph = builder
+        .build(&[y], ADAM::new(0.05, 0.0, 1e-8, 0.9, 0.999), QuadraticCost)
+        .unwrap();
+
+    let history = graph.train_and_log(&XOR, 4, 4, 1000).unwrap();
+    let (x, y) = XOR.get_full().unwrap();
+
+    assert!(history.last().unwrap().1 < history[0].1);
+    assert!(graph.cost(&[x], &[y]).unwrap() < 0.05);
+}
+
+#[test]
+fn merges_of_different_sizes_are_rejected() {
+    let mut builder = GraphBuilder::new();
+    let x = builder.input(2);
+    let a = builder.dense(x, 3, Tanh);
+    let b = builder.dense(x, 4, Tanh);
+    let y = builder.add(&[a, b]);
+
+    let error = builder.build(&[y], ADAM::default(), QuadraticCost).err();
+    assert_eq!(
+        error,
+        Some(BuildError::WidthMismatch {
+            node: 3,
+            expected: 3,
+            found: 4
+        })
+    );
+}
+
+#[test]
+fn wrong_number_of_inputs_is_an_error() {
+    let mut builder = Graph::builder();
+    let a = builder.input(2);
+    let b = builder.input(2);
+    let y = builder.concatenate(&[a, b]);
+    let graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+    assert!(matches!(
+        graph.predict(&[array![[1.0, 2.0]]]),
+        Err(Error::InvalidData(_))
+    ));
+    assert_eq!(
+        graph
+            .predict(&[array![[1.0, 2.0]], array![[3.0, 4.0]]])
+            .unwrap()[0],
+        array![[1.0, 2.0, 3.0, 4.0]]
+    );
+}
+
+#[test]
+fn added_layers_are_initialized() {
+    let mut builder = GraphBuilder::new();
+    let x = builder.input(3);
+    let e = builder.layer(x, Box::new(EmbeddingLayer::new(10, 4, 3)));
+    let y = builder.dense(e, 1, Linear);
+    let graph = builder.build(&[y], ADAM::default(), QuadraticCost).unwrap();
+
+    assert_eq!(graph.layers()[0].get_weights().dim(), (10, 4));
+    let prediction = graph.predict(&[array![[1.0, 9.0, 0.0]]]).unwrap();
+    assert_eq!(prediction[0].dim(), (1, 1));
+}
+
+#[test]
+fn embedding_must_read_an_input() {
+    let mut builder = GraphBuilder::new();
+    let x = builder.input(3);
+    let h = builder.dense(x, 3, Tanh);
+    let e = builder.layer(h, Box::new(EmbeddingLayer::new(10, 4, 3)));
+
+    let error = builder.build(&[e], ADAM::default(), QuadraticCost).err();
+    assert_eq!(error, Some(BuildError::EmbeddingNotFirst(2)));
+}
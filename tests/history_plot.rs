@@ -0,0 +1,83 @@
+use std::{env, fs, process};
+
+use neural_network_rs::{
+    dataset::example_datasets::{CIRCLE, XOR},
+    error::Error,
+    neural_network::{
+        activation_function::{Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        optimizer::sgd_optimzer::SGD,
+        Network, TrainingHistory,
+    },
+    plotter::graph_plotter::{ema, HistoryPlot, ImageFormat},
+};
+
+#[test]
+fn ema_starts_at_the_first_value() {
+    let points = [(0, 1.0), (1, 0.0), (2, 0.0)];
+
+    assert_eq!(ema(&points, 0.5), [(0, 1.0), (1, 0.5), (2, 0.25)]);
+    assert_eq!(ema(&points, 0.0), points);
+}
+
+#[test]
+fn validation_cost_and_learning_rate_are_logged() {
+    let mut network = Network::builder()
+        .input(2)
+        .dense(4, Relu)
+        .dense(1, Sigmoid)
+        .optimizer(SGD::new(0.1, 0.0, 0.01))
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+
+    let history = network
+        .train_and_validate(&CIRCLE, &XOR, 8, 8, 200)
+        .unwrap();
+
+    assert_eq!(history.cost.len(), 67);
+    assert_eq!(history.validation.len(), history.cost.len());
+    let rates = history
+        .learning_rate
+        .iter()
+        .map(|point| point.1)
+        .collect::<Vec<_>>();
+    assert!(rates.windows(2).all(|pair| pair[1] < pair[0]));
+    assert_eq!(network.learning_rate(), 0.1 / (1.0 + 0.01 * 199.0));
+}
+
+#[test]
+fn histories_are_plotted_as_png_and_svg() {
+    let dir = env::temp_dir().join(format!("history_plot_{}", process::id()));
+    let history = TrainingHistory {
+        cost: vec![(0, 1.0), (10, 0.1), (20, 0.0), (30, f64::NAN)],
+        validation: vec![(0, 1.2), (10, 0.3), (20, 0.2)],
+        learning_rate: vec![(0, 0.01), (10, 0.005), (20, 0.001)],
+    };
+
+    let plot = HistoryPlot::from_history(&history)
+        .log_scale(true)
+        .smoothing(0.5)
+        .size(320, 240);
+
+    let png = plot.plot(&dir, "log", ImageFormat::Png).unwrap();
+    assert_eq!(png, dir.join("log_history.png"));
+    assert!(fs::metadata(&png).unwrap().len() > 0);
+
+    let svg = plot.plot(&dir, "log", ImageFormat::Svg).unwrap();
+    assert!(fs::read_to_string(svg).unwrap().starts_with("<svg"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn log_scale_needs_positive_values() {
+    let plot = HistoryPlot::new()
+        .series("cost", &[(0, 0.0), (1, -1.0)])
+        .log_scale(true);
+
+    assert!(matches!(
+        plot.plot(&env::temp_dir(), "negative", ImageFormat::Svg),
+        Err(Error::NonFiniteCost)
+    ));
+}
@@ -0,0 +1,110 @@
+use std::{env, fs, process};
+
+use ndarray::array;
+
+use neural_network_rs::{
+    dataset::{image::Image, Dataset, DatasetType},
+    error::Error,
+    neural_network::{
+        activation_function::{Linear, Sigmoid},
+        cost_function::QuadraticCost,
+        optimizer::sgd_optimzer::SGD,
+        Network,
+    },
+    plotter::png_plotter::plot_png,
+};
+
+fn encode(width: u32, height: u32, color_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, width, height);
+    encoder.set_color(color_type);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(data)
+        .unwrap();
+    image
+}
+
+#[test]
+fn pixel_coordinates_map_to_their_colors() {
+    // red and green on top, blue and white below
+    let png = encode(
+        2,
+        2,
+        png::ColorType::Rgb,
+        &[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255],
+    );
+    let path = env::temp_dir().join(format!("pixels_{}.png", process::id()));
+    fs::write(&path, png).unwrap();
+
+    let dataset = Dataset::from_png("pixels", &path);
+    fs::remove_file(&path).unwrap();
+
+    let (x, y) = dataset.unwrap().get_full().unwrap();
+    assert_eq!(x, array![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+    assert_eq!(
+        y,
+        array![
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0]
+        ]
+    );
+}
+
+#[test]
+fn grayscale_is_repeated_and_alpha_dropped() {
+    let png = encode(2, 1, png::ColorType::GrayscaleAlpha, &[51, 255, 255, 0]);
+    let image = Image::decode(&png[..]).unwrap();
+
+    assert_eq!(image.dims, (2, 1));
+    assert_eq!(image.pixels, vec![vec![0.2; 3], vec![1.0; 3]]);
+
+    let result = Image::decode(&b"not a png"[..]);
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn images_survive_a_round_trip_through_plot_png() {
+    let dir = env::temp_dir().join(format!("image_{}", process::id()));
+    let pixels = (0..12)
+        .map(|i| vec![i as f64 / 15.0, 1.0 - i as f64 / 15.0, 0.6])
+        .collect::<Vec<_>>();
+
+    let path = plot_png(&dir, "original", (4, 3), &pixels, png::ColorType::Rgb).unwrap();
+    let image = Image::read(&path).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+
+    assert_eq!(image.dims, (4, 3));
+    assert!(image.mean_squared_error(&pixels).unwrap() < 1e-5);
+    assert!(image.mean_squared_error(&pixels[1..]).is_err());
+}
+
+#[test]
+fn networks_draw_images_from_their_coordinates() {
+    let image = Image {
+        dims: (3, 2),
+        pixels: vec![vec![0.5, 0.25, 1.0]; 6],
+    };
+    let (x, y) = image.samples();
+    let dataset = Dataset::new("image", DatasetType::InMemory(x, y));
+
+    let mut network = Network::builder()
+        .input(2)
+        .dense(8, Sigmoid)
+        .dense(3, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+    let before = image
+        .mean_squared_error(&network.predict_image(&image).unwrap())
+        .unwrap();
+    network.train_and_log(&dataset, 6, 6, 300).unwrap();
+
+    let drawn = network.predict_image(&image).unwrap();
+    assert_eq!(drawn.len(), 6);
+    assert!(image.mean_squared_error(&drawn).unwrap() < before);
+}
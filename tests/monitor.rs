@@ -0,0 +1,162 @@
+use std::{env, fs, process};
+
+use ndarray::{array, Array2};
+
+use neural_network_rs::{
+    dataset::example_datasets::CIRCLE,
+    error::Error,
+    neural_network::{
+        activation_function::{Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        monitor::{dead_units, Distribution, DistributionMonitor, Histogram},
+        optimizer::sgd_optimzer::SGD,
+        Network, Recorder,
+    },
+    plotter::distributions::DistributionPlot,
+};
+
+fn network() -> Network {
+    Network::builder()
+        .input(2)
+        .dense(8, Relu)
+        .dense(1, Sigmoid)
+        .optimizer(SGD::new(0.1, 0.0, 0.0))
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn histograms_count_finite_values_per_bin() {
+    let values = [0.0, 0.1, 0.5, 0.99, 1.0, 2.0, f64::NAN, f64::INFINITY];
+
+    let histogram = Histogram::new(&values, (0.0, 1.0), 4);
+
+    assert_eq!(histogram.counts, [2, 0, 1, 2]);
+    assert_eq!(histogram.non_finite, 2);
+    assert_eq!(Histogram::range_of(&values), Some((0.0, 2.0)));
+    assert_eq!(Histogram::range_of(&[f64::NAN]), None);
+}
+
+#[test]
+fn units_that_are_zero_for_every_sample_are_dead() {
+    let activations = array![[0.0, 1.0, 0.0], [0.0, 0.0, 2.0]];
+
+    assert_eq!(dead_units(&activations), 1);
+    assert_eq!(dead_units(&Array2::zeros((0, 3))), 0);
+}
+
+#[test]
+fn snapshots_hold_every_layer() {
+    let mut network = network();
+    let mut monitor = DistributionMonitor::from_dataset(10, &CIRCLE, 32).unwrap();
+
+    network
+        .train_and_record(&CIRCLE, 16, 16, 25, &mut monitor)
+        .unwrap();
+
+    let epochs = monitor
+        .snapshots()
+        .iter()
+        .map(|s| s.epoch)
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, [0, 10, 20, 24]);
+
+    let last = &monitor.snapshots()[3].layers;
+    assert_eq!(last.len(), 2);
+    assert_eq!(last[0].weights.len(), 2 * 8);
+    assert_eq!(last[0].bias_gradients.len(), 8);
+    assert_eq!(last[0].activations.len(), 32 * 8);
+    assert_eq!(last[1].units, 1);
+    assert_eq!(
+        last[1].weights,
+        network.layers()[1]
+            .get_weights()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>()
+    );
+
+    let histograms = monitor.histograms(0, Distribution::Weights, 10);
+    assert_eq!(histograms.len(), 4);
+    assert!(histograms
+        .iter()
+        .all(|(_, h)| h.counts.iter().sum::<usize>() == 16));
+}
+
+#[test]
+fn intervals_below_one_take_a_snapshot_every_epoch() {
+    let mut network = network();
+    let mut monitor = DistributionMonitor::from_dataset(10, &CIRCLE, 8).unwrap();
+    monitor.every = -3;
+
+    network
+        .train_and_record(&CIRCLE, 16, 16, 3, &mut monitor)
+        .unwrap();
+
+    assert_eq!(monitor.snapshots().len(), 3);
+}
+
+#[test]
+fn dead_relu_layers_are_reported() {
+    let mut network = network();
+    // no input can make the hidden layer positive
+    network.layers_mut()[0].set_weights(Array2::zeros((2, 8)));
+    network.layers_mut()[0].set_bias(Array2::from_elem((1, 8), -1.0));
+
+    let mut monitor = DistributionMonitor::from_dataset(1, &CIRCLE, 16).unwrap();
+    monitor.record(&network, 0, 0.0).unwrap();
+
+    let report = monitor.dead_units().unwrap();
+    assert_eq!(report.layers, [(8, 8), (0, 1)]);
+    assert_eq!(report.fractions(), [1.0, 0.0]);
+    assert!(report.to_string().contains("100.0%"));
+
+    // dead units get no gradient
+    let layer = &monitor.snapshots()[0].layers[0];
+    assert!(layer.weight_gradients.iter().all(|&g| g == 0.0));
+}
+
+#[test]
+fn distributions_are_plotted_per_layer() {
+    let dir = env::temp_dir().join(format!("monitor_{}", process::id()));
+    let mut network = network();
+    let mut monitor = DistributionMonitor::from_dataset(5, &CIRCLE, 16).unwrap();
+
+    let empty = dir.join("empty.png");
+    assert!(matches!(
+        DistributionPlot::new(&monitor, Distribution::Weights).save(&empty),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(!empty.exists());
+
+    network
+        .train_and_record(&CIRCLE, 16, 16, 10, &mut monitor)
+        .unwrap();
+
+    for distribution in Distribution::ALL {
+        let path = DistributionPlot::new(&monitor, distribution)
+            .plot(&dir, "circle")
+            .unwrap();
+        assert_eq!(
+            path,
+            dir.join(format!("circle_{}.png", distribution.label()))
+        );
+        assert!(fs::metadata(path).unwrap().len() > 0);
+    }
+
+    let plot = DistributionPlot::new(&monitor, Distribution::WeightGradients).size(400, 300);
+    let path = dir.join("nested").join("gradients.png");
+    assert_eq!(plot.save(&path).unwrap(), path);
+    let mut written = Vec::new();
+    plot.write(&mut written, "gradients").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), written);
+    let info = png::Decoder::new(&written[..])
+        .read_info()
+        .unwrap()
+        .info()
+        .clone();
+    assert_eq!((info.width, info.height), (400, 300));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,38 @@
+use std::thread;
+
+use neural_network_rs::{
+    dataset::example_datasets::XOR,
+    neural_network::{
+        activation_function::{Linear, Sigmoid},
+        cost_function::QuadraticCost,
+        layer::{DenseLayer, Layer},
+        optimizer::adam_optimizer::ADAM,
+        Network,
+    },
+};
+
+fn xor_network() -> Network {
+    let layers: Vec<Box<dyn Layer>> = vec![
+        Box::new(DenseLayer::new(2, Box::new(Sigmoid))),
+        Box::new(DenseLayer::new(8, Box::new(Sigmoid))),
+        Box::new(DenseLayer::new(1, Box::new(Linear))),
+    ];
+
+    Network::new(layers, Box::new(ADAM::default()), Box::new(QuadraticCost)).unwrap()
+}
+
+#[test]
+fn network_can_be_moved_to_another_thread() {
+    let mut network = xor_network();
+
+    let network = thread::spawn(move || {
+        for _ in 0..10 {
+            network.train_minibatch(&XOR.get_batch(4).unwrap()).unwrap();
+        }
+        network
+    })
+    .join()
+    .unwrap();
+
+    assert!(network.eval(&XOR, 4).unwrap().is_finite());
+}
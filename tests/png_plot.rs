@@ -0,0 +1,196 @@
+use std::{env, fs, process};
+
+use neural_network_rs::{
+    error::Error,
+    plotter::{
+        graph_plotter::{plot_graph, HistoryPlot, ImageFormat},
+        png_plotter::{plot_png, Colormap, Normalization, PngPlot},
+    },
+};
+
+fn decode(bytes: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+    let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).unwrap();
+    buffer.truncate(info.buffer_size());
+    (info, buffer)
+}
+
+#[test]
+fn pixels_are_scaled_to_the_image_size() {
+    // a 2x1 image, black left and white right
+    let data = vec![vec![0.0], vec![1.0]];
+    let mut image = Vec::new();
+
+    PngPlot::new((2, 1), &data, png::ColorType::Grayscale)
+        .size(4, 2)
+        .write(&mut image)
+        .unwrap();
+
+    let (info, pixels) = decode(&image);
+    assert_eq!((info.width, info.height), (4, 2));
+    assert_eq!(pixels, [0, 0, 255, 255, 0, 0, 255, 255]);
+}
+
+#[test]
+fn pixels_must_match_dims_and_color_type() {
+    let data = vec![vec![0.0]; 4];
+
+    let result = PngPlot::new((2, 2), &data, png::ColorType::Rgb).write(Vec::new());
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+
+    let result = PngPlot::new((3, 2), &data, png::ColorType::Grayscale).write(Vec::new());
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+
+    // invalid data leaves no file behind
+    let path = env::temp_dir().join(format!("invalid_png_{}.png", process::id()));
+    let result = PngPlot::new((3, 2), &data, png::ColorType::Grayscale).save(&path);
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+    assert!(!path.exists());
+}
+
+#[test]
+fn values_outside_the_unit_range_are_clamped_or_normalized() {
+    let data = vec![vec![-1.0], vec![0.5], vec![2.0], vec![f64::NAN]];
+
+    let mut image = Vec::new();
+    PngPlot::new((4, 1), &data, png::ColorType::Grayscale)
+        .write(&mut image)
+        .unwrap();
+    assert_eq!(decode(&image).1, [0, 128, 255, 0]);
+
+    let mut image = Vec::new();
+    PngPlot::new((4, 1), &data, png::ColorType::Grayscale)
+        .normalization(Normalization::MinMax)
+        .write(&mut image)
+        .unwrap();
+    assert_eq!(decode(&image).1, [0, 128, 255, 0]);
+
+    let mut image = Vec::new();
+    PngPlot::new((4, 1), &data, png::ColorType::Grayscale)
+        .normalization(Normalization::Symmetric)
+        .write(&mut image)
+        .unwrap();
+    assert_eq!(decode(&image).1, [64, 159, 255, 0]);
+}
+
+#[test]
+fn infinities_are_clamped_and_nan_is_black() {
+    let data = vec![vec![f64::NEG_INFINITY], vec![f64::INFINITY], vec![f64::NAN]];
+
+    for normalization in [Normalization::Clamp, Normalization::Range(-1.0, 1.0)] {
+        let mut image = Vec::new();
+        PngPlot::new((3, 1), &data, png::ColorType::Grayscale)
+            .normalization(normalization)
+            .write(&mut image)
+            .unwrap();
+        assert_eq!(decode(&image).1, [0, 255, 0]);
+    }
+    assert_eq!(Colormap::Grayscale.color(f64::INFINITY), [255; 3]);
+    assert_eq!(Colormap::Grayscale.color(f64::NAN), [0; 3]);
+}
+
+#[test]
+fn degenerate_ranges_are_errors() {
+    let data = vec![vec![0.5]];
+
+    for (min, max) in [
+        (1.0, 1.0),
+        (1.0, 0.0),
+        (0.0, f64::INFINITY),
+        (f64::NAN, 1.0),
+    ] {
+        let plot = PngPlot::new((1, 1), &data, png::ColorType::Grayscale)
+            .normalization(Normalization::Range(min, max));
+        assert!(matches!(
+            plot.write(&mut Vec::new()),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
+
+#[test]
+fn single_outputs_are_drawn_with_a_colormap() {
+    let data = vec![vec![0.0], vec![0.5], vec![1.0]];
+    let mut image = Vec::new();
+
+    PngPlot::for_outputs((3, 1), &data)
+        .colormap(Colormap::Diverging)
+        .write(&mut image)
+        .unwrap();
+
+    let (info, pixels) = decode(&image);
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(pixels, [59, 76, 192, 221, 221, 221, 180, 4, 38]);
+
+    assert_eq!(Colormap::from_name("magma"), Some(Colormap::Magma));
+    assert_eq!(Colormap::Viridis.color(0.0), [68, 1, 84]);
+    assert_eq!(Colormap::Viridis.color(7.0), [253, 231, 37]);
+}
+
+#[test]
+fn outputs_are_mapped_to_channels() {
+    // two outputs are drawn as red and green
+    let data = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+    let mut image = Vec::new();
+    PngPlot::for_outputs((2, 1), &data)
+        .write(&mut image)
+        .unwrap();
+    assert_eq!(decode(&image).1, [255, 0, 0, 0, 255, 0]);
+
+    // any outputs of a wider network can be picked
+    let data = vec![vec![0.0, 0.2, 0.4, 1.0]; 2];
+    let mut image = Vec::new();
+    PngPlot::for_outputs((2, 1), &data)
+        .channels(&[Some(3), None, Some(0)])
+        .write(&mut image)
+        .unwrap();
+    assert_eq!(decode(&image).1, [255, 0, 0, 255, 0, 0]);
+
+    let result = PngPlot::for_outputs((2, 1), &data)
+        .channels(&[Some(4)])
+        .write(Vec::new());
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn plots_return_the_written_paths() {
+    let dir = env::temp_dir().join(format!("png_plot_{}", process::id()));
+    let data = vec![vec![0.5, 0.5, 0.5]; 4];
+    let history = [(0, 1.0), (1, 0.5)];
+
+    let path = plot_png(&dir, "square", (2, 2), &data, png::ColorType::Rgb).unwrap();
+    assert_eq!(path, dir.join("square.png"));
+    assert_eq!(decode(&fs::read(&path).unwrap()).0.width, 2);
+
+    let path = plot_graph(&dir, "square", &history).unwrap();
+    assert_eq!(path, dir.join("square_history.png"));
+
+    let path = dir.join("nested").join("cost.png");
+    let saved = HistoryPlot::new()
+        .series("cost", &history)
+        .size(320, 200)
+        .save(&path, ImageFormat::Png)
+        .unwrap();
+    assert_eq!(saved, path);
+    assert_eq!(decode(&fs::read(&path).unwrap()).0.width, 320);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn history_plots_can_be_written_to_any_writer() {
+    let mut svg = Vec::new();
+
+    HistoryPlot::new()
+        .series("cost", &[(0, 1.0), (1, 0.5)])
+        .write(&mut svg, "memory", ImageFormat::Svg)
+        .unwrap();
+
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("History-memory"));
+
+    let result = HistoryPlot::new().write(Vec::new(), "empty", ImageFormat::Png);
+    assert!(matches!(result, Err(Error::EmptyHistory)));
+}
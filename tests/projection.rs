@@ -0,0 +1,192 @@
+use std::{env, fs, process};
+
+use ndarray::{array, Array2};
+
+use neural_network_rs::{
+    dataset::{
+        projection::{Pca, Tsne},
+        slice::FeatureSlice,
+        Dataset, DatasetType,
+    },
+    error::Error,
+    neural_network::{
+        activation_function::{Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        optimizer::adam_optimizer::ADAM,
+        Network,
+    },
+    plotter::{decision_boundary::BoundaryPlot, projection::ProjectionPlot},
+};
+
+fn network(inputs: usize) -> Network {
+    Network::builder()
+        .input(inputs)
+        .dense(6, Relu)
+        .dense(1, Sigmoid)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+// two tight clusters around (0, 0, 0) and (5, 5, 5)
+fn clusters() -> Array2<f64> {
+    Array2::from_shape_fn((20, 3), |(i, j)| {
+        let center = if i < 10 { 0.0 } else { 5.0 };
+        center + 0.1 * ((i * 3 + j) as f64).sin()
+    })
+}
+
+#[test]
+fn slices_sweep_two_features_and_fix_the_others() {
+    let slice = FeatureSlice {
+        fixed: vec![7.0, 0.0, 8.0, 0.0],
+        ..FeatureSlice::new(4, (3, 1))
+    }
+    .ranges((-1.0, 1.0), (2.0, 4.0));
+
+    let grid = slice.grid(3).unwrap();
+
+    assert_eq!(grid.dim(), (9, 4));
+    assert_eq!(grid.row(0).to_vec(), [7.0, 2.0, 8.0, -1.0]);
+    assert_eq!(grid.row(5).to_vec(), [7.0, 3.0, 8.0, 1.0]);
+    assert_eq!(slice.scale((0.5, 1.0)), (0.0, 4.0));
+
+    let invalid = FeatureSlice::new(2, (0, 2)).grid(3);
+    assert!(matches!(invalid, Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn larger_networks_are_plotted_over_a_slice() {
+    let x = clusters();
+    let y = x
+        .column(0)
+        .mapv(|value| (value > 2.5) as u8 as f64)
+        .insert_axis(ndarray::Axis(1));
+    let data = Dataset::new("clusters", DatasetType::InMemory(x.clone(), y));
+    let network = network(3);
+
+    let slice = FeatureSlice::at_mean(&x, (0, 2));
+    assert!((slice.fixed[1] - 2.5).abs() < 0.1);
+    let (dims, prediction) = network.predict_slice(&slice, 4).unwrap();
+    assert_eq!(dims, (4, 4));
+    assert_eq!(prediction.len(), 16);
+
+    let dir = env::temp_dir().join(format!("projection_slice_{}", process::id()));
+    let options = BoundaryPlot {
+        resolution: 10,
+        slice: Some(slice),
+        size: (400, 300),
+        ..BoundaryPlot::default()
+    };
+    options.plot(&dir, "clusters", &network, &data).unwrap();
+    assert!(dir.join("clusters_boundary.png").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pca_finds_the_direction_of_largest_variance() {
+    // points on the line through (1, 2, 0) with direction (1, 1, 0), plus noise
+    // along the third axis
+    let data = Array2::from_shape_fn((50, 3), |(i, j)| {
+        let t = i as f64 / 10.0;
+        match j {
+            0 => 1.0 + t,
+            1 => 2.0 + t,
+            _ => 0.01 * (i as f64).cos(),
+        }
+    });
+
+    let pca = Pca::fit(&data, 2).unwrap();
+
+    let first = pca.components().column(0);
+    let expected = 1.0 / 2f64.sqrt();
+    assert!((first[0] - expected).abs() < 1e-3);
+    assert!((first[1] - expected).abs() < 1e-3);
+    assert!(pca.explained_variance_ratio()[0] > 0.999);
+
+    let projected = pca.transform(&data).unwrap();
+    assert_eq!(projected.dim(), (50, 2));
+    assert!(projected.column(0).sum().abs() < 1e-9);
+
+    assert!(matches!(
+        pca.transform(&array![[1.0, 2.0]]),
+        Err(Error::ShapeMismatch { .. })
+    ));
+    assert!(matches!(Pca::fit(&data, 4), Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn tsne_keeps_clusters_apart() {
+    let data = clusters();
+    let tsne = Tsne {
+        perplexity: 5.0,
+        iterations: 300,
+        learning_rate: 10.0,
+        ..Tsne::default()
+    };
+
+    let embedding = tsne.embed(&data).unwrap();
+    assert_eq!(embedding.dim(), (20, 2));
+    assert_eq!(embedding, tsne.embed(&data).unwrap());
+
+    // every point is closer to the points of its own cluster than to the others
+    let distance = |a: usize, b: usize| {
+        let d = &embedding.row(a) - &embedding.row(b);
+        d.dot(&d)
+    };
+    for i in 0..20 {
+        let own = (0..20).filter(|&j| j != i && (j < 10) == (i < 10));
+        let other = (0..20).filter(|&j| (j < 10) != (i < 10));
+        let farthest_own = own.map(|j| distance(i, j)).fold(0.0, f64::max);
+        let nearest_other = other.map(|j| distance(i, j)).fold(f64::INFINITY, f64::min);
+        assert!(farthest_own < nearest_other);
+    }
+}
+
+#[test]
+fn hidden_activations_can_be_projected_and_plotted() {
+    let x = clusters();
+    let network = network(3);
+
+    let activations = network.activations(&x).unwrap();
+    assert_eq!(activations.len(), 2);
+    assert_eq!(activations[0].dim(), (20, 6));
+    assert_eq!(activations[1], network.predict(&x).unwrap());
+
+    let hidden = Pca::fit(&activations[0], 2)
+        .unwrap()
+        .transform(&activations[0])
+        .unwrap();
+    let labels = Array2::from_shape_fn((20, 2), |(i, j)| ((i < 10) == (j == 0)) as u8 as f64);
+
+    let dir = env::temp_dir().join(format!("projection_plot_{}", process::id()));
+    let plot = ProjectionPlot::new(&hidden, &labels).size(400, 300);
+    let path = plot.plot(&dir, "hidden").unwrap();
+    assert_eq!(path, dir.join("hidden_projection.png"));
+    let info = png::Decoder::new(fs::File::open(&path).unwrap())
+        .read_info()
+        .unwrap()
+        .info()
+        .clone();
+    assert_eq!((info.width, info.height), (400, 300));
+
+    // a named file is the same image as one written anywhere else
+    let named = dir.join("nested").join("hidden_projection.png");
+    assert_eq!(plot.save(&named).unwrap(), named);
+    let mut written = Vec::new();
+    plot.write(&mut written, "hidden_projection").unwrap();
+    assert_eq!(fs::read(&named).unwrap(), written);
+
+    // nothing is written for invalid points
+    let few_labels = labels.slice(ndarray::s![..5, ..]).to_owned();
+    let missing = dir.join("missing.png");
+    assert!(matches!(
+        ProjectionPlot::new(&hidden, &few_labels).save(&missing),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(!missing.exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
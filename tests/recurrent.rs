@@ -0,0 +1,171 @@
+use ndarray::{array, Array2, Array3};
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::{
+    config::ExperimentConfig,
+    dataset::Dataset,
+    error::Error,
+    neural_network::{
+        activation_function::Linear,
+        builder::BuildError,
+        cost_function::QuadraticCost,
+        gradient_check::check_gradients,
+        layer::recurrent::{Cell, RecurrentLayer},
+        optimizer::sgd_optimzer::SGD,
+        Network,
+    },
+};
+
+const STEPS: usize = 4;
+const FEATURES: usize = 3;
+
+fn network(cell: Cell, return_sequences: bool) -> Network {
+    let builder = Network::builder().sequence_input(STEPS, FEATURES);
+    let builder = match cell {
+        Cell::SimpleRnn => builder.simple_rnn(5, return_sequences),
+        Cell::Lstm => builder.lstm(5, return_sequences),
+        Cell::Gru => builder.gru(5, return_sequences),
+    };
+
+    builder
+        .dense(2, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+// Backpropagation through time against finite differences, for the last state
+// and for whole sequences
+fn assert_gradients_match(cell: Cell) {
+    for return_sequences in [false, true] {
+        let mut network = network(cell, return_sequences);
+
+        let x = Array2::random((6, STEPS * FEATURES), Uniform::new(-1.0, 1.0));
+        let y = Array2::random((6, 2), Uniform::new(-1.0, 1.0));
+
+        for (layer, error) in check_gradients(&mut network, &x, &y, 1e-5)
+            .unwrap()
+            .iter()
+            .enumerate()
+        {
+            assert!(
+                error.max() < 1e-4,
+                "{:?} layer {} gradients differ: {:?}",
+                cell,
+                layer,
+                error
+            );
+        }
+    }
+}
+
+#[test]
+fn simple_rnn_gradients() {
+    assert_gradients_match(Cell::SimpleRnn);
+}
+
+#[test]
+fn lstm_gradients() {
+    assert_gradients_match(Cell::Lstm);
+}
+
+#[test]
+fn gru_gradients() {
+    assert_gradients_match(Cell::Gru);
+}
+
+#[test]
+fn stacked_recurrent_layers_have_sequence_shapes() {
+    let network = Network::builder()
+        .sequence_input(STEPS, FEATURES)
+        .gru(6, true)
+        .lstm(4, false)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap();
+
+    let sizes = network
+        .layers()
+        .iter()
+        .map(|layer| (layer.get_size(), layer.get_output_size()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sizes,
+        [(STEPS * FEATURES, STEPS * 6), (STEPS * 6, 4), (4, 1)]
+    );
+
+    let prediction = network.predict(&Array2::zeros((3, STEPS * FEATURES)));
+    assert_eq!(prediction.unwrap().dim(), (3, 1));
+}
+
+#[test]
+fn recurrent_layer_needs_a_sequence() {
+    let result = Network::builder()
+        .input(4)
+        .lstm(4, false)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build();
+
+    assert_eq!(result.err(), Some(BuildError::NotASequence(1)));
+}
+
+#[test]
+fn sequences_need_at_least_one_step() {
+    let result = Network::builder()
+        .sequence_input(0, FEATURES)
+        .lstm(4, false)
+        .dense(1, Linear)
+        .optimizer(SGD::default())
+        .cost(QuadraticCost)
+        .build();
+    assert_eq!(result.err(), Some(BuildError::ZeroSteps));
+
+    assert!(matches!(
+        RecurrentLayer::gru(0, FEATURES, 4, false),
+        Err(Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn sliding_windows_are_labeled_with_the_next_row() {
+    let series = array![[0.0, 10.0], [1.0, 11.0], [2.0, 12.0], [3.0, 13.0]];
+    let (x, y) = Dataset::sliding_windows("series", &series, 2)
+        .unwrap()
+        .get_full()
+        .unwrap();
+
+    assert_eq!(x, array![[0.0, 10.0, 1.0, 11.0], [1.0, 11.0, 2.0, 12.0]]);
+    assert_eq!(y, array![[2.0, 12.0], [3.0, 13.0]]);
+
+    assert!(Dataset::sliding_windows("short", &series, 4).is_err());
+}
+
+#[test]
+fn sequences_are_flattened_step_by_step() {
+    let sequences = Array3::from_shape_fn((2, 3, 2), |(i, t, f)| (i * 100 + t * 10 + f) as f64);
+    let (x, _) = Dataset::from_sequences("sequences", &sequences, Array2::zeros((2, 1)))
+        .unwrap()
+        .get_full()
+        .unwrap();
+
+    assert_eq!(
+        x.row(1).to_vec(),
+        [100.0, 101.0, 110.0, 111.0, 120.0, 121.0]
+    );
+}
+
+#[test]
+fn recurrent_network_from_config() {
+    let config = ExperimentConfig::from_file("configs/sine_lstm.toml").unwrap();
+    let network = config.build_network().unwrap();
+
+    assert_eq!(network.input_size(), 16);
+    assert_eq!(network.output_size(), 1);
+    let (x, _) = config.dataset().unwrap().get_batch(8).unwrap();
+    assert_eq!(network.predict(&x).unwrap().dim(), (8, 1));
+}
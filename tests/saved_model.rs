@@ -0,0 +1,36 @@
+use std::{env, fs, process};
+
+use ndarray::{array, Array2};
+
+use neural_network_rs::config::{ConfigError, ExperimentConfig, SavedModel};
+
+#[test]
+fn saved_model_predicts_like_the_original() {
+    let config = ExperimentConfig::from_file("configs/rgb_donut.toml").unwrap();
+    let mut network = config.build_network().unwrap();
+    network
+        .train_minibatch(&config.dataset().unwrap().get_batch(16).unwrap())
+        .unwrap();
+
+    let path = env::temp_dir().join(format!("saved_model_{}.json", process::id()));
+    SavedModel::new(&config, &network).save(&path).unwrap();
+    let restored = SavedModel::load(&path).unwrap().build_network().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let x = array![[0.1, 0.2], [0.5, 0.5], [0.9, 0.3]];
+    assert_eq!(network.predict(&x).unwrap(), restored.predict(&x).unwrap());
+}
+
+#[test]
+fn parameters_of_the_wrong_shape_are_an_error() {
+    let config = ExperimentConfig::from_file("configs/rgb_donut.toml").unwrap();
+    let network = config.build_network().unwrap();
+
+    let mut model = SavedModel::new(&config, &network);
+    model.layers[1].weights = Array2::zeros((3, 3));
+
+    assert!(matches!(
+        model.build_network(),
+        Err(ConfigError::ParameterShape { layer: 1, .. })
+    ));
+}
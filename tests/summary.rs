@@ -0,0 +1,101 @@
+use std::{env, fs, process};
+
+use neural_network_rs::{
+    neural_network::{
+        activation_function::{Linear, Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        graph::Graph,
+        optimizer::adam_optimizer::ADAM,
+        Network,
+    },
+    plotter::{
+        architecture::{plot_architecture, write_architecture},
+        graph_plotter::ImageFormat,
+    },
+};
+
+fn network() -> Network {
+    Network::builder()
+        .input(2)
+        .dense(16, Relu)
+        .dense(3, Sigmoid)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+fn residual_graph() -> Graph {
+    let mut graph = Graph::builder();
+    let x = graph.input(8);
+    let a = graph.attention(x, 4, 2);
+    let h = graph.add(&[x, a]);
+    let y = graph.dense(h, 1, Linear);
+    graph.build(&[y], ADAM::default(), QuadraticCost).unwrap()
+}
+
+#[test]
+fn networks_are_summarized_layer_by_layer() {
+    let summary = network().summary();
+
+    let kinds = summary
+        .rows
+        .iter()
+        .map(|row| row.kind.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["input", "dense", "dense"]);
+    assert_eq!(summary.rows[1].output_shape, [16]);
+    assert_eq!(summary.rows[1].activation.as_deref(), Some("relu"));
+    assert_eq!(summary.rows[2].params, 16 * 3 + 3);
+    assert_eq!(summary.trainable_params(), 2 * 16 + 16 + 16 * 3 + 3);
+    assert_eq!(summary.outputs, [2]);
+    assert!(summary.is_chain());
+
+    let table = summary.to_string();
+    assert!(table.contains("dense  (16)          relu        48"));
+    assert!(table.contains("Trainable params: 99"));
+    assert!(!table.contains("Inputs"));
+}
+
+#[test]
+fn graphs_list_the_inputs_of_every_node() {
+    let summary = residual_graph().summary();
+
+    let kinds = summary
+        .rows
+        .iter()
+        .map(|row| row.kind.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["input", "attention", "add", "dense"]);
+    assert_eq!(summary.rows[1].output_shape, [4, 2]);
+    assert_eq!(summary.rows[1].activation, None);
+    assert_eq!(summary.rows[2].inputs, [0, 1]);
+    assert!(!summary.is_chain());
+    assert!(summary.to_string().contains("Inputs"));
+
+    let dot = summary.to_dot();
+    assert!(dot.starts_with("digraph model {"));
+    assert!(dot.contains("n0 -> n2;"));
+    assert!(dot.contains("n1 -> n2;"));
+    assert!(dot.contains("n3 [label=\"{dense (linear)|(1), 9 params}\", style=bold];"));
+}
+
+#[test]
+fn architectures_are_drawn_as_png_and_svg() {
+    let summary = residual_graph().summary();
+
+    let mut svg = Vec::new();
+    write_architecture(&mut svg, &summary, ImageFormat::Svg).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.contains("attention"));
+
+    let dir = env::temp_dir().join(format!("summary_{}", process::id()));
+    let path = plot_architecture(&dir, "residual", &summary, ImageFormat::Png).unwrap();
+    assert_eq!(path, dir.join("residual_architecture.png"));
+
+    let decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+    let info = decoder.read_info().unwrap().info().clone();
+    assert!(info.width > 0 && info.height > info.width);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,82 @@
+use neural_network_rs::{
+    dataset::{example_datasets, synthetic::Synthetic},
+    error::Error,
+};
+
+#[test]
+fn classes_are_balanced_and_one_hot_beyond_two() {
+    let synthetic = Synthetic::new(300, 0.05);
+
+    let (x, y) = synthetic.blobs(3).unwrap();
+    assert_eq!((x.dim(), y.dim()), ((300, 2), (300, 3)));
+    for class in 0..3 {
+        assert_eq!(y.column(class).sum(), 100.0);
+    }
+    assert!(y.rows().into_iter().all(|row| row.sum() == 1.0));
+
+    let (x, y) = synthetic.moons().unwrap();
+    assert_eq!((x.dim(), y.dim()), ((300, 2), (300, 1)));
+    assert_eq!(y.sum(), 150.0);
+
+    let (x, y) = synthetic.swiss_roll().unwrap();
+    assert_eq!((x.ncols(), y.ncols()), (3, 1));
+    assert!(y.iter().all(|&t| (0.0..=1.0).contains(&t)));
+}
+
+#[test]
+fn samples_follow_their_shape_without_noise() {
+    let synthetic = Synthetic::new(200, 0.0);
+
+    let (x, y) = synthetic.rings(4).unwrap();
+    for (point, label) in x.rows().into_iter().zip(y.rows()) {
+        let class = label.iter().position(|&value| value == 1.0).unwrap();
+        let radius = ((point[0] - 0.5).powi(2) + (point[1] - 0.5).powi(2)).sqrt();
+        assert!((radius - 0.45 * (class + 1) as f64 / 4.0).abs() < 1e-12);
+    }
+
+    let (x, y) = synthetic.checkerboard(2).unwrap();
+    for (point, label) in x.rows().into_iter().zip(y.column(0)) {
+        let white = (point[0] < 0.5) != (point[1] < 0.5);
+        assert_eq!(*label, if white { 1.0 } else { 0.0 });
+    }
+
+    let (x, y) = synthetic.sine().unwrap();
+    for (x, y) in x.column(0).iter().zip(y.column(0)) {
+        assert!((y - 0.5 - 0.4 * (2.0 * std::f64::consts::PI * x).sin()).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn the_seed_fixes_the_samples() {
+    let synthetic = Synthetic::new(64, 0.1);
+
+    assert_eq!(synthetic.spirals(2).unwrap(), synthetic.spirals(2).unwrap());
+    assert_ne!(
+        synthetic.spirals(2).unwrap().0,
+        synthetic.clone().seed(1).spirals(2).unwrap().0
+    );
+
+    let spirals = example_datasets::by_name("SPIRALS").unwrap();
+    assert_eq!(spirals.get_full().unwrap(), spirals.get_full().unwrap());
+    assert_eq!(spirals.get_batch(16).unwrap().0.dim(), (16, 2));
+}
+
+#[test]
+fn invalid_parameters_are_errors() {
+    assert!(matches!(
+        Synthetic::new(10, 0.1).blobs(1),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(matches!(
+        Synthetic::new(10, 0.1).checkerboard(0),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(matches!(
+        Synthetic::new(10, -1.0).moons(),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(matches!(
+        Synthetic::new(10, f64::NAN).sine(),
+        Err(Error::InvalidData(_))
+    ));
+}
@@ -0,0 +1,142 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use ndarray::{array, Array2};
+use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+use neural_network_rs::{
+    dataset::{Dataset, DatasetType},
+    neural_network::{
+        activation_function::{Linear, Relu, Sigmoid},
+        cost_function::QuadraticCost,
+        divergence::DivergencePolicy,
+        optimizer::adam_optimizer::ADAM,
+        workspace::Workspace,
+        Network,
+    },
+};
+
+// Counts the allocations of the current thread, so tests running in parallel
+// don't interfere
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn network() -> Network {
+    Network::builder()
+        .input(2)
+        .dense(16, Relu)
+        .dense(16, Sigmoid)
+        .dense(3, Linear)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .build()
+        .unwrap()
+}
+
+fn batch(samples: usize) -> (Array2<f64>, Array2<f64>) {
+    (
+        Array2::random((samples, 2), Uniform::new(0.0, 1.0)),
+        Array2::random((samples, 3), Uniform::new(0.0, 1.0)),
+    )
+}
+
+#[test]
+fn reused_workspaces_give_the_same_gradients() {
+    let network = network();
+    let mut workspace = Workspace::new();
+
+    // the batch size changes between calls, so the buffers are resized
+    for samples in [8, 8, 3, 16, 1] {
+        let (x, y) = batch(samples);
+        network.backprop_into(&x, &y, &mut workspace).unwrap();
+        let (nabla_bs, nabla_ws) = network.backprop(&x, &y).unwrap();
+
+        assert_eq!(workspace.nabla_bs(), &nabla_bs[..]);
+        assert_eq!(workspace.nabla_ws(), &nabla_ws[..]);
+        assert_eq!(workspace.output(), &network.predict(&x).unwrap());
+    }
+}
+
+#[test]
+fn training_steps_only_allocate_for_matrix_products() {
+    let mut network = network();
+    let batch = batch(8);
+    network.train_minibatch(&batch).unwrap();
+
+    // without BLAS, matrixmultiply packs every product into a fresh buffer:
+    // one product per layer forward, one per weight gradient and one per
+    // delta passed back to a previous layer
+    let products = 3 + 3 + 2;
+    let step = allocations(|| {
+        network.train_minibatch(&batch).unwrap();
+    });
+    assert!(step <= products, "{} allocations", step);
+}
+
+#[test]
+fn checkpointed_embedding_steps_only_allocate_for_matrix_products() {
+    let mut network = Network::builder()
+        .input(4)
+        .embedding(10, 3)
+        .dense(2, Linear)
+        .optimizer(ADAM::default())
+        .cost(QuadraticCost)
+        .divergence_policy(DivergencePolicy::Rollback)
+        .build()
+        .unwrap();
+    let batch = (
+        array![[0.0, 1.0, 2.0, 3.0], [4.0, 5.0, 6.0, 7.0]],
+        Array2::zeros((2, 2)),
+    );
+    network.train_minibatch(&batch).unwrap();
+
+    // the dense layer forward, its weight gradient and the delta passed back
+    // to the embedding, which only looks up and accumulates rows
+    let products = 3;
+    let step = allocations(|| {
+        network.train_minibatch(&batch).unwrap();
+    });
+    assert!(step <= products, "{} allocations", step);
+}
+
+#[test]
+fn in_memory_batches_are_sampled_without_allocating() {
+    let (x, y) = batch(32);
+    let dataset = Dataset::new("memory", DatasetType::InMemory(x.clone(), y));
+    let mut batch = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
+    dataset.get_batch_into(8, &mut batch).unwrap();
+
+    let sampled = allocations(|| dataset.get_batch_into(8, &mut batch).unwrap());
+    assert_eq!(sampled, 0);
+    assert_eq!(batch.0.dim(), (8, 2));
+    assert!(batch
+        .0
+        .rows()
+        .into_iter()
+        .all(|row| x.rows().into_iter().any(|sample| sample == row)));
+}